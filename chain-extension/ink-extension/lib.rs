@@ -0,0 +1,152 @@
+//! ink! interface of the receipt registry chain extension.
+//!
+//! Contracts use [`ReceiptRegistryEnvironment`] as their environment, or their own environment
+//! with [`ReceiptRegistryExtension`] as its chain extension:
+//!
+//! ```ignore
+//! #[ink::contract(env = receipt_registry_extension::ReceiptRegistryEnvironment)]
+//! mod my_contract {
+//!     // ...
+//!     let logs = self.env().extension().logs_for_receipt(5, block_number, receipt_hash, address)?;
+//! }
+//! ```
+//!
+//! Chains are identified by their chain id, and resolved to the chain registered with this id
+//! whatever its kind, see [`Error::NotRegistered`].
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink::env::{chain_extension::FromStatusCode, DefaultEnvironment, Environment};
+use ink::prelude::vec::Vec;
+
+/// Topics and data of a log.
+pub type Log = (Vec<[u8; 32]>, Vec<u8>);
+
+#[ink::chain_extension]
+pub trait ReceiptRegistryExtension {
+    type ErrorCode = Error;
+
+    // 0004 stands for the registered ID on chain of the chain extension
+    // 0001 stands for method that called using this interface.
+    /// Logs emitted by `contract_address` in a proven receipt.
+    #[ink(extension = 0x00040001)]
+    fn logs_for_receipt(
+        chain_id: u32,
+        block_number: u64,
+        receipt_hash: [u8; 32],
+        contract_address: [u8; 20],
+    ) -> Vec<Log>;
+
+    /// Submits a proof on behalf of the calling contract, which has to be a registered relayer.
+    #[ink(extension = 0x00040002)]
+    fn submit_proof(chain_id: u32, event_proof: Vec<u8>);
+
+    /// Registers the calling contract as a relayer, reserving `bond` from its balance.
+    #[ink(extension = 0x00040003)]
+    fn register_relayer(bond: u128);
+
+    /// Transfers the rewards accrued by the calling contract to its balance.
+    #[ink(extension = 0x00040004)]
+    fn claim_rewards();
+
+    /// Whether a receipt has been proven.
+    #[ink(extension = 0x00040005)]
+    fn is_receipt_proved(chain_id: u32, receipt_hash: [u8; 32]) -> bool;
+
+    /// Contracts watched on a chain.
+    #[ink(extension = 0x00040006)]
+    fn watched_contracts(chain_id: u32) -> Vec<[u8; 20]>;
+
+    /// Proven receipts with logs of a watched contract in the blocks from `from` to `to`
+    /// inclusive, as `(block number, receipt hash)`. The range may span at most 1024 blocks and
+    /// hold at most 1024 receipts.
+    #[ink(extension = 0x00040007)]
+    fn receipts_for_contract(
+        chain_id: u32,
+        contract_address: [u8; 20],
+        from: u64,
+        to: u64,
+    ) -> Vec<(u64, [u8; 32])>;
+}
+
+/// chain extension errors.
+#[derive(scale::Encode, scale::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Error {
+    /// The receipt has not been proven.
+    NotFound,
+    /// The contract is not watched on the chain.
+    NotWatched,
+    /// The input or the output of the extension could not be decoded.
+    DecodingError,
+    /// The block range is too large.
+    RangeTooLarge,
+    /// The registry call failed.
+    DispatchFailed,
+    /// Proofs of the chain are paused, and its receipts can't be trusted.
+    Paused,
+    /// No chain, or chains of several kinds, are registered with the chain id.
+    NotRegistered,
+    /// The block range holds too many receipts.
+    TooManyReceipts,
+    /// The extension returned a status code this version doesn't know.
+    UnknownStatusCode(u32),
+}
+
+impl FromStatusCode for Error {
+    fn from_status_code(status_code: u32) -> core::result::Result<(), Self> {
+        match status_code {
+            0 => Err(Self::NotFound),
+            1 => Ok(()),
+            2 => Err(Self::NotWatched),
+            3 => Err(Self::DecodingError),
+            4 => Err(Self::RangeTooLarge),
+            5 => Err(Self::DispatchFailed),
+            6 => Err(Self::Paused),
+            7 => Err(Self::NotRegistered),
+            8 => Err(Self::TooManyReceipts),
+            status_code => Err(Self::UnknownStatusCode(status_code)),
+        }
+    }
+}
+
+impl From<scale::Error> for Error {
+    fn from(_: scale::Error) -> Self {
+        Self::DecodingError
+    }
+}
+
+/// Default environment with the receipt registry chain extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ReceiptRegistryEnvironment {}
+
+impl Environment for ReceiptRegistryEnvironment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <DefaultEnvironment as Environment>::AccountId;
+    type Balance = <DefaultEnvironment as Environment>::Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+
+    type ChainExtension = ReceiptRegistryExtension;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(Error::from_status_code(0), Err(Error::NotFound));
+        assert_eq!(Error::from_status_code(1), Ok(()));
+        assert_eq!(Error::from_status_code(2), Err(Error::NotWatched));
+        assert_eq!(Error::from_status_code(3), Err(Error::DecodingError));
+        assert_eq!(Error::from_status_code(4), Err(Error::RangeTooLarge));
+        assert_eq!(Error::from_status_code(5), Err(Error::DispatchFailed));
+        assert_eq!(Error::from_status_code(6), Err(Error::Paused));
+        assert_eq!(Error::from_status_code(7), Err(Error::NotRegistered));
+        assert_eq!(Error::from_status_code(8), Err(Error::TooManyReceipts));
+        assert_eq!(Error::from_status_code(9), Err(Error::UnknownStatusCode(9)));
+    }
+}
@@ -3,9 +3,99 @@
 extern crate alloc;
 
 use frame_support::{
-    dispatch::Encode, inherent::Vec, sp_runtime::DispatchError, sp_std::marker::PhantomData,
+    dispatch::{DispatchResult, Encode, GetDispatchInfo},
+    inherent::Vec,
+    sp_runtime::{traits::Zero, DispatchError},
+    sp_std::marker::PhantomData,
+    traits::{Get, UnfilteredDispatchable},
+    weights::Weight,
 };
-use pallet_contracts::chain_extension::{ChainExtension, Environment, Ext, InitState, RetVal};
+use pallet_contracts::chain_extension::{
+    BufInBufOutState, ChainExtension, Environment, Ext, InitState, RetVal,
+};
+use pallet_contracts::Determinism;
+use pallet_receipt_registry::{BalanceOf, CallbackDispatcher, CallbackTarget};
+use webb_proposals::TypedChainId;
+
+/// Weight charged per log returned to the contract, on top of the storage read.
+pub const LOG_WEIGHT: Weight = Weight::from_parts(100_000, 0);
+
+/// Weight charged per byte of encoded logs returned to the contract.
+pub const LOG_BYTE_WEIGHT: Weight = Weight::from_parts(1_000, 1);
+
+pub use pallet_receipt_registry::{MAX_BLOCK_RANGE, MAX_RANGE_RECEIPTS};
+
+/// Weight of returning `count` logs, encoded into `len` bytes, to the contract.
+pub fn logs_weight(count: usize, len: usize) -> Weight {
+    LOG_WEIGHT
+        .saturating_mul(count as u64)
+        .saturating_add(LOG_BYTE_WEIGHT.saturating_mul(len as u64))
+}
+
+/// Charges the weight of the work done by a function of the extension.
+///
+/// Implemented by the [`Environment`] of the calling contract.
+pub trait ChargeWeight {
+    fn charge_weight(&mut self, amount: Weight) -> Result<(), DispatchError>;
+}
+
+impl<E: Ext> ChargeWeight for Environment<'_, '_, E, BufInBufOutState> {
+    fn charge_weight(&mut self, amount: Weight) -> Result<(), DispatchError> {
+        Environment::charge_weight(self, amount).map(|_| ())
+    }
+}
+
+/// Logs of the contract in a stored receipt, encoded as returned by `LogsForReceipt`, or `None`
+/// if the receipt isn't stored.
+///
+/// The whole stored receipt is charged before it's decoded, whatever the number of logs of the
+/// contract in it.
+pub fn logs_for_receipt<Runtime>(
+    env: &mut impl ChargeWeight,
+    typed_chain_id: TypedChainId,
+    block_number: u64,
+    receipt_hash: types::H256,
+    contract_address: types::H160,
+) -> Result<Option<Vec<u8>>, DispatchError>
+where
+    Runtime: pallet_receipt_registry::Config,
+{
+    env.charge_weight(<Runtime as frame_system::Config>::DbWeight::get().reads(1))?;
+    let Some(size) = pallet_receipt_registry::Pallet::<Runtime>::processed_receipt_size(
+        typed_chain_id,
+        block_number,
+        receipt_hash,
+    ) else {
+        return Ok(None);
+    };
+    env.charge_weight(LOG_BYTE_WEIGHT.saturating_mul(size.into()))?;
+
+    let Some(data) = pallet_receipt_registry::Pallet::<Runtime>::processed_receipts((
+        typed_chain_id,
+        block_number,
+        receipt_hash,
+    )) else {
+        return Ok(None);
+    };
+
+    let logs: Vec<_> = data
+        .into_iter()
+        .filter(|log| log.address == contract_address)
+        .map(|log| {
+            let topics: Vec<_> = log
+                .topics
+                .into_iter()
+                .map(|topic| sp_core::H256(topic.0))
+                .collect();
+            (topics, log.data)
+        })
+        .collect();
+
+    let count = logs.len();
+    let logs = logs.encode();
+    env.charge_weight(logs_weight(count, logs.len()))?;
+    Ok(Some(logs))
+}
 
 #[derive(parity_scale_codec::Encode, parity_scale_codec::Decode, Debug, Clone, PartialEq)]
 pub struct Arguments {
@@ -15,8 +105,68 @@ pub struct Arguments {
     pub contract_address: [u8; 20],
 }
 
-enum ReceiptRegistryFuncId {
-    LogsForReceipt,
+#[derive(parity_scale_codec::Encode, parity_scale_codec::Decode, Debug, Clone, PartialEq)]
+pub struct SubmitProofArguments {
+    pub chain_id: u32,
+    pub event_proof: Vec<u8>,
+}
+
+#[derive(parity_scale_codec::Encode, parity_scale_codec::Decode, Debug, Clone, PartialEq)]
+pub struct IsReceiptProvedArguments {
+    pub chain_id: u32,
+    pub receipt_hash: [u8; 32],
+}
+
+#[derive(parity_scale_codec::Encode, parity_scale_codec::Decode, Debug, Clone, PartialEq)]
+pub struct ReceiptsForContractArguments {
+    pub chain_id: u32,
+    pub contract_address: [u8; 20],
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Status code returned to the contract by every function of the extension.
+///
+/// `Success` and `NotFound` keep the values `logs_for_receipt` returned before the other codes
+/// were added, so that deployed contracts keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Status {
+    /// The receipt has not been proven.
+    NotFound = 0,
+    Success = 1,
+    /// The contract is not watched on the chain.
+    NotWatched = 2,
+    /// The input of the function could not be decoded.
+    DecodingError = 3,
+    /// The block range spans more than [`MAX_BLOCK_RANGE`] blocks.
+    RangeTooLarge = 4,
+    /// The dispatched registry call failed.
+    DispatchFailed = 5,
+    /// Proofs of the chain are paused, and its receipts can't be trusted.
+    Paused = 6,
+    /// No chain, or chains of several kinds, are registered with the chain id.
+    NotRegistered = 7,
+    /// The block range holds more than [`MAX_RANGE_RECEIPTS`] receipts.
+    TooManyReceipts = 8,
+}
+
+impl From<Status> for RetVal {
+    fn from(status: Status) -> Self {
+        RetVal::Converging(status as u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum ReceiptRegistryFuncId {
+    LogsForReceipt = 1,
+    SubmitProof = 2,
+    RegisterRelayer = 3,
+    ClaimRewards = 4,
+    IsReceiptProved = 5,
+    WatchedContracts = 6,
+    ReceiptsForContract = 7,
 }
 
 impl TryFrom<u16> for ReceiptRegistryFuncId {
@@ -25,6 +175,12 @@ impl TryFrom<u16> for ReceiptRegistryFuncId {
     fn try_from(value: u16) -> Result<Self, Self::Error> {
         match value {
             1 => Ok(ReceiptRegistryFuncId::LogsForReceipt),
+            2 => Ok(ReceiptRegistryFuncId::SubmitProof),
+            3 => Ok(ReceiptRegistryFuncId::RegisterRelayer),
+            4 => Ok(ReceiptRegistryFuncId::ClaimRewards),
+            5 => Ok(ReceiptRegistryFuncId::IsReceiptProved),
+            6 => Ok(ReceiptRegistryFuncId::WatchedContracts),
+            7 => Ok(ReceiptRegistryFuncId::ReceiptsForContract),
             _ => Err(DispatchError::Other(
                 "Unsupported func id in receipt registry chain extension",
             )),
@@ -55,58 +211,318 @@ where
 
         let func_id = env.func_id().try_into()?;
         let mut env = env.buf_in_buf_out();
+        let db_weight = <Runtime as frame_system::Config>::DbWeight::get();
 
         match func_id {
             ReceiptRegistryFuncId::LogsForReceipt => {
-                // TODO: proper weight calculation
-
-                let Arguments {
+                let Some(Arguments {
                     chain_id,
                     block_number,
                     receipt_hash,
                     contract_address,
-                } = env.read_as_unbounded(env.in_len())?;
+                }) = read_arguments(&mut env)
+                else {
+                    return Ok(Status::DecodingError.into());
+                };
 
                 log::debug!(
                     target: TARGET,
                     "logs_for_receipt with receipt hash: {receipt_hash:?} and contract address: {contract_address:?}",
                 );
 
-                let (chain_id, receipt_hash, contract_address) = (
-                    webb_proposals::TypedChainId::Evm(chain_id),
+                let Some(typed_chain_id) = registered_chain(&mut env, chain_id)? else {
+                    return Ok(Status::NotRegistered.into());
+                };
+                if is_paused(&mut env, typed_chain_id)? {
+                    return Ok(Status::Paused.into());
+                }
+
+                let Some(logs) = logs_for_receipt::<Runtime>(
+                    &mut env,
+                    typed_chain_id,
+                    block_number,
                     types::H256(receipt_hash),
                     types::H160(contract_address),
-                );
+                )?
+                else {
+                    return Ok(Status::NotFound.into());
+                };
+                env.write(&logs, false, None)?;
 
-                let data = if let Some(data) =
-                    pallet_receipt_registry::Pallet::<Runtime>::processed_receipts((
-                        chain_id,
-                        block_number,
-                        receipt_hash,
-                    )) {
-                    data
-                } else {
-                    return Ok(RetVal::Converging(0));
+                Ok(Status::Success.into())
+            }
+            ReceiptRegistryFuncId::SubmitProof => {
+                let Some(SubmitProofArguments {
+                    chain_id,
+                    event_proof,
+                }) = read_arguments(&mut env)
+                else {
+                    return Ok(Status::DecodingError.into());
+                };
+                let Some(typed_chain_id) = registered_chain(&mut env, chain_id)? else {
+                    return Ok(Status::NotRegistered.into());
                 };
+                if is_paused(&mut env, typed_chain_id)? {
+                    return Ok(Status::Paused.into());
+                }
 
-                let logs: Vec<_> = data
+                dispatch(
+                    &mut env,
+                    pallet_receipt_registry::Call::<Runtime>::submit_proof {
+                        typed_chain_id,
+                        event_proof,
+                    },
+                )
+            }
+            ReceiptRegistryFuncId::RegisterRelayer => {
+                let Some(bond) = read_arguments::<BalanceOf<Runtime>, _>(&mut env) else {
+                    return Ok(Status::DecodingError.into());
+                };
+
+                dispatch(
+                    &mut env,
+                    pallet_receipt_registry::Call::<Runtime>::register_relayer { bond },
+                )
+            }
+            ReceiptRegistryFuncId::ClaimRewards => dispatch(
+                &mut env,
+                pallet_receipt_registry::Call::<Runtime>::claim_rewards {},
+            ),
+            ReceiptRegistryFuncId::IsReceiptProved => {
+                let Some(IsReceiptProvedArguments {
+                    chain_id,
+                    receipt_hash,
+                }) = read_arguments(&mut env)
+                else {
+                    return Ok(Status::DecodingError.into());
+                };
+                let Some(typed_chain_id) = registered_chain(&mut env, chain_id)? else {
+                    return Ok(Status::NotRegistered.into());
+                };
+                if is_paused(&mut env, typed_chain_id)? {
+                    return Ok(Status::Paused.into());
+                }
+
+                env.charge_weight(db_weight.reads(1))?;
+                let proved = pallet_receipt_registry::Pallet::<Runtime>::processed_receipts_hash(
+                    typed_chain_id,
+                    types::H256(receipt_hash),
+                )
+                .is_some();
+                env.write(&proved.encode(), false, None)?;
+
+                Ok(Status::Success.into())
+            }
+            ReceiptRegistryFuncId::WatchedContracts => {
+                let Some(chain_id) = read_arguments::<u32, _>(&mut env) else {
+                    return Ok(Status::DecodingError.into());
+                };
+                let Some(typed_chain_id) = registered_chain(&mut env, chain_id)? else {
+                    return Ok(Status::NotRegistered.into());
+                };
+
+                env.charge_weight(db_weight.reads(1))?;
+                let contracts =
+                    pallet_receipt_registry::Pallet::<Runtime>::watched_contracts(typed_chain_id)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|address| address.0)
+                        .collect::<Vec<_>>();
+                env.write(&contracts.encode(), false, None)?;
+
+                Ok(Status::Success.into())
+            }
+            ReceiptRegistryFuncId::ReceiptsForContract => {
+                let Some(ReceiptsForContractArguments {
+                    chain_id,
+                    contract_address,
+                    from,
+                    to,
+                }) = read_arguments(&mut env)
+                else {
+                    return Ok(Status::DecodingError.into());
+                };
+
+                let blocks = to.saturating_sub(from);
+                if blocks >= MAX_BLOCK_RANGE {
+                    return Ok(Status::RangeTooLarge.into());
+                }
+                let Some(typed_chain_id) = registered_chain(&mut env, chain_id)? else {
+                    return Ok(Status::NotRegistered.into());
+                };
+                if is_paused(&mut env, typed_chain_id)? {
+                    return Ok(Status::Paused.into());
+                }
+
+                let contract_address = types::H160(contract_address);
+
+                env.charge_weight(db_weight.reads(1))?;
+                let watched =
+                    pallet_receipt_registry::Pallet::<Runtime>::watched_contracts(typed_chain_id)
+                        .map_or(false, |contracts| contracts.contains(&contract_address));
+                if !watched {
+                    return Ok(Status::NotWatched.into());
+                }
+
+                if from <= to {
+                    env.charge_weight(db_weight.reads(blocks + 1))?;
+                }
+                let receipts =
+                    match pallet_receipt_registry::Pallet::<Runtime>::receipts_for_contract(
+                        typed_chain_id,
+                        contract_address,
+                        from,
+                        to,
+                    ) {
+                        Ok(receipts) => receipts,
+                        Err(pallet_receipt_registry::Error::RangeTooLarge) => {
+                            return Ok(Status::RangeTooLarge.into())
+                        }
+                        Err(_) => return Ok(Status::TooManyReceipts.into()),
+                    };
+                let receipts = receipts
                     .into_iter()
-                    .filter(|log| log.address == contract_address)
-                    .map(|log| {
-                        let topics: Vec<_> = log
-                            .topics
-                            .into_iter()
-                            .map(|topic| sp_core::H256(topic.0))
-                            .collect();
-                        (topics, log.data)
-                    })
-                    .collect();
-
-                let logs = logs.encode();
-                env.write(&logs, false, None)?;
+                    .map(|(block_number, receipt_hash)| (block_number, receipt_hash.0))
+                    .collect::<Vec<_>>()
+                    .encode();
+                env.charge_weight(LOG_BYTE_WEIGHT.saturating_mul(receipts.len() as u64))?;
+                env.write(&receipts, false, None)?;
 
-                Ok(RetVal::Converging(1))
+                Ok(Status::Success.into())
             }
         }
     }
 }
+
+/// Calls the ink! callbacks of the receipt registry from
+/// [`pallet_receipt_registry::Pallet::callback_account_id`], so that contracts can check where
+/// the logs come from with `self.env().caller()`.
+///
+/// The message receives `(chain_id: u32, block_number: u64, receipt_hash: [u8; 32],
+/// contract_address: [u8; 20], topics: Vec<[u8; 32]>, data: Vec<u8>)`. The calling account holds
+/// no funds, so the storage deposit limit of the call is zero: callbacks can update their storage
+/// but not grow it.
+pub struct ContractsCallbackDispatcher<Runtime>(PhantomData<Runtime>);
+
+impl<Runtime> CallbackDispatcher<Runtime::AccountId> for ContractsCallbackDispatcher<Runtime>
+where
+    Runtime: pallet_contracts::Config + pallet_receipt_registry::Config,
+{
+    fn dispatch(
+        _owner: &Runtime::AccountId,
+        target: &CallbackTarget<Runtime::AccountId>,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: types::H256,
+        log: &types::Log,
+        weight_limit: Weight,
+    ) -> Option<(Weight, DispatchResult)> {
+        const TARGET: &str = "pallet-chain-extension-receipt-registry::callback";
+
+        let CallbackTarget::Ink { contract, selector } = target else {
+            return None;
+        };
+
+        let topics: Vec<_> = log.topics.iter().map(|topic| topic.0).collect();
+        let mut input = selector.to_vec();
+        (
+            typed_chain_id.underlying_chain_id(),
+            block_number,
+            receipt_hash.0,
+            log.address.0,
+            topics,
+            &log.data,
+        )
+            .encode_to(&mut input);
+
+        let result = pallet_contracts::Pallet::<Runtime>::bare_call(
+            pallet_receipt_registry::Pallet::<Runtime>::callback_account_id(),
+            contract.clone(),
+            Default::default(),
+            weight_limit,
+            Some(Zero::zero()),
+            input,
+            false,
+            Determinism::Enforced,
+        );
+
+        let outcome = match result.result {
+            Ok(value) if !value.did_revert() => Ok(()),
+            Ok(_) => Err(DispatchError::Other("ink! callback reverted")),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = outcome {
+            log::debug!(target: TARGET, "callback of {contract:?} failed: {err:?}");
+        }
+        Some((result.gas_consumed, outcome))
+    }
+}
+
+/// Reads the arguments of a function, or `None` if they can't be decoded.
+fn read_arguments<A, E>(env: &mut Environment<E, BufInBufOutState>) -> Option<A>
+where
+    A: parity_scale_codec::Decode,
+    E: Ext,
+{
+    let len = env.in_len();
+    env.read_as_unbounded(len).ok()
+}
+
+/// Registered chain with the chain id given by the contract, whatever its kind, charging the
+/// storage reads.
+fn registered_chain<Runtime, E>(
+    env: &mut Environment<E, BufInBufOutState>,
+    chain_id: u32,
+) -> Result<Option<TypedChainId>, DispatchError>
+where
+    Runtime: pallet_contracts::Config + pallet_receipt_registry::Config,
+    E: Ext<T = Runtime>,
+{
+    env.charge_weight(
+        <Runtime as frame_system::Config>::DbWeight::get()
+            .reads(pallet_receipt_registry::CHAIN_KINDS.len() as u64),
+    )?;
+    Ok(pallet_receipt_registry::Pallet::<Runtime>::registered_chain(chain_id))
+}
+
+/// Whether proofs of the chain are paused, charging the storage reads.
+fn is_paused<Runtime, E>(
+    env: &mut Environment<E, BufInBufOutState>,
+    typed_chain_id: TypedChainId,
+) -> Result<bool, DispatchError>
+where
+    Runtime: pallet_contracts::Config + pallet_receipt_registry::Config,
+    E: Ext<T = Runtime>,
+{
+    env.charge_weight(<Runtime as frame_system::Config>::DbWeight::get().reads(2))?;
+    Ok(pallet_receipt_registry::Pallet::<Runtime>::is_paused(
+        typed_chain_id,
+    ))
+}
+
+/// Dispatches a registry call on behalf of the calling contract, charging its weight.
+fn dispatch<Runtime, E>(
+    env: &mut Environment<E, BufInBufOutState>,
+    call: pallet_receipt_registry::Call<Runtime>,
+) -> Result<RetVal, DispatchError>
+where
+    Runtime: pallet_contracts::Config + pallet_receipt_registry::Config,
+    E: Ext<T = Runtime>,
+{
+    const TARGET: &str = "pallet-chain-extension-receipt-registry::dispatch";
+
+    let info = call.get_dispatch_info();
+    let charged = env.charge_weight(info.weight)?;
+
+    let origin = frame_system::RawOrigin::Signed(env.ext().address().clone());
+    let (post_info, status) = match call.dispatch_bypass_filter(origin.into()) {
+        Ok(post_info) => (post_info, Status::Success),
+        Err(err) => {
+            log::debug!(target: TARGET, "registry call failed: {:?}", err.error);
+            (err.post_info, Status::DispatchFailed)
+        }
+    };
+    env.adjust_weight(charged, post_info.calc_actual_weight(&info));
+
+    Ok(status.into())
+}
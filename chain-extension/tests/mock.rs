@@ -0,0 +1,132 @@
+use frame_support::{
+    parameter_types, sp_io,
+    sp_runtime::{
+        testing::Header,
+        traits::{BlakeTwo256, IdentityLookup},
+        AccountId32,
+    },
+    weights::{IdentityFee, Weight},
+    PalletId,
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+
+pub type AccountId = AccountId32;
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap()
+        .into()
+}
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for Test {
+    type AccountData = pallet_balances::AccountData<u128>;
+    type AccountId = AccountId;
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockHashCount = BlockHashCount;
+    type BlockLength = ();
+    type BlockNumber = u64;
+    type BlockWeights = ();
+    type RuntimeCall = RuntimeCall;
+    type DbWeight = ();
+    type RuntimeEvent = RuntimeEvent;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type Header = Header;
+    type Index = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type MaxConsumers = frame_support::traits::ConstU32<16>;
+    type OnKilledAccount = ();
+    type OnNewAccount = ();
+    type OnSetCode = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type PalletInfo = PalletInfo;
+    type SS58Prefix = SS58Prefix;
+    type SystemWeightInfo = ();
+    type Version = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+    type Balance = u128;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ExistentialDeposit;
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type HoldIdentifier = ();
+    type FreezeIdentifier = ();
+    type MaxHolds = ();
+    type MaxFreezes = ();
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const StoragePricePerByte: u128 = 1;
+    pub const Eth2ClientPalletId: PalletId = PalletId(*b"py/eth2c");
+    pub const MaxAncestryLength: u32 = 2;
+    pub const MaxWatchedContracts: u32 = 100;
+    pub const MinRelayerBond: u128 = 100;
+    pub const RewardPoolLowThreshold: u32 = 3;
+    pub const MaxOnReceiptProvedWeight: Weight = Weight::zero();
+    pub const MaxCallbackWeight: Weight = Weight::from_parts(10_000_000, 0);
+    pub const MaxCallbacksPerReceipt: u32 = 2;
+    pub const MaxFinalityStall: u32 = 10;
+    pub const MaxFinalityChecksPerBlock: u32 = 2;
+    pub const MaxPendingReceiptsPerBlock: u32 = 2;
+}
+
+impl pallet_eth2_light_client::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type StoragePricePerByte = StoragePricePerByte;
+    type PalletId = Eth2ClientPalletId;
+    type Currency = Balances;
+}
+
+impl pallet_receipt_registry::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = Eth2ClientPalletId;
+    type Currency = Balances;
+    type PrivilegedOrigin = EnsureRoot<AccountId>;
+    type MaxWatchedContracts = MaxWatchedContracts;
+    type MaxAncestryLength = MaxAncestryLength;
+    type MinRelayerBond = MinRelayerBond;
+    type RewardPoolLowThreshold = RewardPoolLowThreshold;
+    type OnReceiptProved = ();
+    type MaxOnReceiptProvedWeight = MaxOnReceiptProvedWeight;
+    type CallbackDispatcher = ();
+    type WeightToFee = IdentityFee<u128>;
+    type MaxCallbackWeight = MaxCallbackWeight;
+    type MaxCallbacksPerReceipt = MaxCallbacksPerReceipt;
+    type MaxFinalityStall = MaxFinalityStall;
+    type MaxFinalityChecksPerBlock = MaxFinalityChecksPerBlock;
+    type MaxPendingReceiptsPerBlock = MaxPendingReceiptsPerBlock;
+}
+
+// Configure a mock runtime to test the chain extension.
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Event<T>},
+        Eth2Client: pallet_eth2_light_client::{Pallet, Call, Storage, Event<T>},
+        ReceiptRegistry: pallet_receipt_registry::{Pallet, Call, Storage, Config<T>, Event<T>},
+    }
+);
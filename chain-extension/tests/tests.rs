@@ -0,0 +1,118 @@
+use frame_support::{
+    pallet_prelude::{NMapKey, StorageNMap},
+    sp_runtime::DispatchError,
+    storage_alias,
+    weights::Weight,
+    Blake2_128Concat,
+};
+use pallet_chain_extension_receipt_registry::{
+    logs_for_receipt, logs_weight, ChargeWeight, ReceiptRegistryFuncId, Status, LOG_BYTE_WEIGHT,
+    LOG_WEIGHT,
+};
+use types::{Log, H160, H256};
+use webb_proposals::TypedChainId;
+
+mod mock;
+use mock::{new_test_ext, ReceiptRegistry, Test};
+
+#[storage_alias]
+type ProcessedReceipts = StorageNMap<
+    ReceiptRegistry,
+    (
+        NMapKey<Blake2_128Concat, TypedChainId>,
+        NMapKey<Blake2_128Concat, u64>,
+        NMapKey<Blake2_128Concat, H256>,
+    ),
+    Vec<Log>,
+>;
+
+/// Sums the weight charged by the extension.
+#[derive(Default)]
+struct WeightMeter(Weight);
+
+impl ChargeWeight for WeightMeter {
+    fn charge_weight(&mut self, amount: Weight) -> Result<(), DispatchError> {
+        self.0.saturating_accrue(amount);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_logs_weight_scales_with_payload() {
+    assert_eq!(logs_weight(0, 0), Weight::zero());
+    assert_eq!(logs_weight(1, 0), LOG_WEIGHT);
+    assert_eq!(logs_weight(2, 100), LOG_WEIGHT * 2 + LOG_BYTE_WEIGHT * 100);
+    assert!(logs_weight(2, 1000).all_gt(logs_weight(2, 100)));
+    assert!(logs_weight(3, 100).any_gt(logs_weight(2, 100)));
+}
+
+#[test]
+fn test_func_ids_and_status_codes() {
+    // Both are part of the interface of the contracts, see `ink-extension/lib.rs`.
+    for func_id in [
+        ReceiptRegistryFuncId::LogsForReceipt,
+        ReceiptRegistryFuncId::SubmitProof,
+        ReceiptRegistryFuncId::RegisterRelayer,
+        ReceiptRegistryFuncId::ClaimRewards,
+        ReceiptRegistryFuncId::IsReceiptProved,
+        ReceiptRegistryFuncId::WatchedContracts,
+        ReceiptRegistryFuncId::ReceiptsForContract,
+    ] {
+        assert_eq!(ReceiptRegistryFuncId::try_from(func_id as u16), Ok(func_id));
+    }
+    assert!(ReceiptRegistryFuncId::try_from(0).is_err());
+    assert!(ReceiptRegistryFuncId::try_from(8).is_err());
+
+    assert_eq!(
+        [
+            Status::Success,
+            Status::NotFound,
+            Status::NotWatched,
+            Status::DecodingError,
+            Status::RangeTooLarge,
+            Status::DispatchFailed,
+            Status::Paused,
+            Status::NotRegistered,
+            Status::TooManyReceipts,
+        ]
+        .map(|status| status as u32),
+        [1, 0, 2, 3, 4, 5, 6, 7, 8]
+    );
+}
+
+#[test]
+fn test_logs_for_receipt_charges_the_stored_receipt() {
+    new_test_ext().execute_with(|| {
+        const CHAIN: TypedChainId = TypedChainId::Evm(5);
+        let (contract, other) = (H160([1; 20]), H160([2; 20]));
+        let receipt_hash = H256([3; 32]);
+        let log = |address, len| Log {
+            address,
+            topics: vec![H256([4; 32])],
+            data: vec![0; len],
+        };
+        let read = |stored: Vec<Log>| {
+            ProcessedReceipts::insert((CHAIN, 1, receipt_hash), stored);
+            let mut meter = WeightMeter::default();
+            let logs =
+                logs_for_receipt::<Test>(&mut meter, CHAIN, 1, receipt_hash, contract).unwrap();
+            (logs, meter.0)
+        };
+
+        // The same logs are returned, but the larger receipt costs more to read.
+        let (logs, small) = read(vec![log(contract, 10), log(other, 10)]);
+        let (large_logs, large) = read(vec![log(contract, 10), log(other, 10_000)]);
+        assert!(logs.is_some());
+        assert_eq!(logs, large_logs);
+        assert!(large.all_gt(small));
+        assert!(large
+            .saturating_sub(small)
+            .all_gte(LOG_BYTE_WEIGHT.saturating_mul(10_000 - 10)));
+
+        let mut meter = WeightMeter::default();
+        assert_eq!(
+            logs_for_receipt::<Test>(&mut meter, CHAIN, 2, receipt_hash, contract),
+            Ok(None)
+        );
+    });
+}
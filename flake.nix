@@ -100,7 +100,7 @@
         commonWasmArgs = commonArgs // {
           pname = "workspace-wasm";
           cargoExtraArgs =
-                "-p pallet-receipt-registry -p pallet-chain-extension-receipt-registry -p pallet-evm-eth-receipt-provider --target wasm32-unknown-unknown --no-default-features";
+                "-p pallet-receipt-registry -p pallet-receipt-registry-runtime-api -p pallet-chain-extension-receipt-registry -p pallet-evm-eth-receipt-provider -p merkle-generator --target wasm32-unknown-unknown --no-default-features";
         };
 
         craneLibLLvmTools = craneLib.overrideToolchain
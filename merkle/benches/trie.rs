@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use merkle_generator::{IterativeTrie, PatriciaTrie};
+use types::{Bloom, Log, Receipt, TransactionReceipt, TxType, H160, H256};
+
+const SIZES: [usize; 2] = [1_000, 10_000];
+
+fn receipts(count: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
+    (0..count)
+        .map(|i| {
+            let receipt = TransactionReceipt {
+                bloom: Bloom::new([i as u8; 256]),
+                receipt: Receipt {
+                    tx_type: TxType::EIP1559,
+                    success: true,
+                    cumulative_gas_used: 21_000 * i as u64,
+                    logs: vec![Log {
+                        address: H160::new([i as u8; 20]),
+                        topics: vec![H256([i as u8; 32])],
+                        data: vec![i as u8; 64],
+                    }],
+                },
+            };
+            (alloy_rlp::encode(i), alloy_rlp::encode(receipt))
+        })
+        .collect()
+}
+
+fn build_trie(receipts: &[(Vec<u8>, Vec<u8>)]) -> PatriciaTrie {
+    let mut trie = PatriciaTrie::new();
+    for (key, value) in receipts {
+        trie.insert(key.clone(), value.clone());
+    }
+    trie
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    for size in SIZES {
+        let receipts = receipts(size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &receipts,
+            |b, receipts| b.iter(|| build_trie(black_box(receipts))),
+        );
+    }
+    group.finish();
+}
+
+fn merkle_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_proof");
+    for size in SIZES {
+        let trie = build_trie(&receipts(size));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &trie, |b, trie| {
+            b.iter(|| trie.merkle_proof(black_box(alloy_rlp::encode(size / 2))))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert, merkle_proof);
+criterion_main!(benches);
@@ -0,0 +1,220 @@
+use alloc::vec::Vec;
+
+use types::{Nibbles, H256};
+
+use crate::encode;
+use crate::trie::root_from_encoding;
+
+/// Builds a trie root from keys inserted in strictly increasing order, in a single pass.
+///
+/// Only the branches on the path to the last inserted key are kept in memory: every subtree
+/// left behind is hashed as soon as the next key shows that it is complete. Keys must be
+/// prefix-free, which holds for RLP encoded keys.
+#[derive(Debug, Default)]
+pub struct OrderedTrieBuilder {
+    /// Last inserted leaf. Its node isn't encoded until the next key shows how deep it is.
+    last: Option<(Vec<u8>, Nibbles, Vec<u8>)>,
+    /// Branches on the path to the last leaf, from the root down, with their depth.
+    branches: Vec<(usize, [Vec<u8>; 16])>,
+}
+
+impl OrderedTrieBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        let nibbles = Nibbles::from_raw(key.clone(), true);
+
+        if let Some((last_raw, last_key, last_value)) = self.last.take() {
+            assert!(
+                last_raw < key,
+                "keys must be inserted in strictly increasing order"
+            );
+            // Both keys end with the leaf terminator, so they can only share real nibbles.
+            let depth = nibbles.common_prefix(&last_key);
+            assert!(
+                depth < last_key.len() - 1,
+                "keys must not be prefixes of each other"
+            );
+
+            let child = self.fold(&last_key, &last_value, depth + 1);
+            match self.branches.last_mut() {
+                Some((branch_depth, children)) if *branch_depth == depth => {
+                    children[last_key.at(depth)] = child;
+                }
+                _ => {
+                    let mut children: [Vec<u8>; 16] = Default::default();
+                    children.fill_with(encode::empty);
+                    children[last_key.at(depth)] = child;
+                    self.branches.push((depth, children));
+                }
+            }
+        }
+
+        self.last = Some((key, nibbles, value));
+    }
+
+    /// Hashes the remaining nodes and returns the root of the trie.
+    pub fn finish(mut self) -> H256 {
+        let root = match self.last.take() {
+            Some((_, last_key, last_value)) => self.fold(&last_key, &last_value, 0),
+            None => encode::empty(),
+        };
+        root_from_encoding(&root)
+    }
+
+    /// Encodes all the branches at `start` depth or deeper, and returns the reference to the node
+    /// on the path to the last leaf that begins at `start` depth.
+    fn fold(&mut self, last_key: &Nibbles, last_value: &[u8], start: usize) -> Vec<u8> {
+        // Branch under construction with its depth, or `None` for the leaf itself.
+        let mut node: Option<(usize, Vec<u8>)> = None;
+        while let Some((depth, _)) = self.branches.last() {
+            if *depth < start {
+                break;
+            }
+            let (depth, mut children) = self.branches.pop().expect("checked above");
+            children[last_key.at(depth)] = Self::node_at(last_key, last_value, depth + 1, node);
+
+            let branch =
+                encode::reference(encode::branch(children.iter().map(Vec::as_slice), None));
+            node = Some((depth, branch));
+        }
+        Self::node_at(last_key, last_value, start, node)
+    }
+
+    /// Reference to the node that begins at `start` depth and leads to `node`.
+    fn node_at(
+        last_key: &Nibbles,
+        last_value: &[u8],
+        start: usize,
+        node: Option<(usize, Vec<u8>)>,
+    ) -> Vec<u8> {
+        match node {
+            None => encode::reference(encode::leaf(&last_key.offset(start), last_value)),
+            Some((depth, branch)) if depth == start => branch,
+            Some((depth, branch)) => {
+                encode::reference(encode::extension(&last_key.slice(start, depth), &branch))
+            }
+        }
+    }
+}
+
+/// Builds the root of a trie keyed by RLP encoded indices, e.g. receipts or transactions of a
+/// block, from values pushed in index order.
+///
+/// RLP encoded `0` sorts after `1..=127`, so the first value is held back until it can be
+/// inserted in order.
+#[derive(Debug, Default)]
+pub struct IndexedTrieBuilder {
+    builder: OrderedTrieBuilder,
+    first: Option<Vec<u8>>,
+    len: usize,
+}
+
+impl IndexedTrieBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the value with the next index.
+    pub fn push(&mut self, value: Vec<u8>) {
+        let index = self.len;
+        self.len += 1;
+
+        if index == 0 {
+            self.first = Some(value);
+            return;
+        }
+        if index == 0x80 {
+            self.insert_first();
+        }
+        self.builder.insert(alloy_rlp::encode(index), value);
+    }
+
+    /// Number of pushed values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn finish(mut self) -> H256 {
+        self.insert_first();
+        self.builder.finish()
+    }
+
+    fn insert_first(&mut self) {
+        if let Some(first) = self.first.take() {
+            self.builder.insert(alloy_rlp::encode(0usize), first);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::{thread_rng, Rng};
+    use types::{Bloom, Receipt, TransactionReceipt, TxType};
+
+    use super::{IndexedTrieBuilder, OrderedTrieBuilder};
+    use crate::{IterativeTrie, PatriciaTrie, ReceiptsTrie};
+
+    #[test]
+    fn ordered_builder_matches_trie() {
+        let mut rng = thread_rng();
+        for count in [0, 1, 2, 17, 300] {
+            // Keys of the same length are prefix-free.
+            let kv = (0..count)
+                .map(|_| {
+                    let key = (0..4).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+                    let value = (0..40).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+                    (key, value)
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            let mut trie = PatriciaTrie::new();
+            let mut builder = OrderedTrieBuilder::new();
+            for (key, value) in kv {
+                trie.insert(key.clone(), value.clone());
+                builder.insert(key, value);
+            }
+            assert_eq!(builder.finish(), trie.root_hash());
+        }
+    }
+
+    #[test]
+    fn indexed_builder_matches_receipts_trie() {
+        for count in [0u8, 1, 2, 127, 128, 129, 255] {
+            let receipts = (0..count)
+                .map(|e| TransactionReceipt {
+                    bloom: Bloom::new([e; 256]),
+                    receipt: Receipt {
+                        tx_type: TxType::EIP1559,
+                        logs: vec![],
+                        cumulative_gas_used: e as u64,
+                        success: true,
+                    },
+                })
+                .collect::<Vec<_>>();
+
+            let mut builder = IndexedTrieBuilder::new();
+            receipts
+                .iter()
+                .for_each(|receipt| builder.push(alloy_rlp::encode(receipt)));
+            assert_eq!(builder.len(), count as usize);
+            assert_eq!(builder.finish(), ReceiptsTrie::new(&receipts).root());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing order")]
+    fn unordered_keys() {
+        let mut builder = OrderedTrieBuilder::new();
+        builder.insert(vec![2], vec![0; 40]);
+        builder.insert(vec![1], vec![0; 40]);
+    }
+}
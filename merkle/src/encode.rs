@@ -0,0 +1,148 @@
+use alloc::vec::Vec;
+
+use alloy_rlp::{Encodable, Header, EMPTY_STRING_CODE};
+use keccak_hash::keccak;
+use types::{Nibbles, H256};
+
+// Encoders return the RLP of a node, while its children are passed as node references, i.e. as
+// they are stored in the parent: see [`reference`].
+
+/// Encoding of an empty node, which is also its reference.
+pub(crate) fn empty() -> Vec<u8> {
+    vec![EMPTY_STRING_CODE]
+}
+
+/// Turns the RLP of a node into its reference: nodes shorter than a hash are stored inline,
+/// longer ones are replaced with their hash.
+pub(crate) fn reference(rlp: Vec<u8>) -> Vec<u8> {
+    if rlp.len() < 32 {
+        rlp
+    } else {
+        keccak(&rlp).0.to_vec()
+    }
+}
+
+pub(crate) fn leaf(key: &Nibbles, value: &[u8]) -> Vec<u8> {
+    let mut payload = vec![];
+    key.encode_compact().as_slice().encode(&mut payload);
+    value.encode(&mut payload);
+    list(payload)
+}
+
+pub(crate) fn branch<'a>(
+    children: impl IntoIterator<Item = &'a [u8]>,
+    value: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut payload = vec![];
+    for child in children {
+        embed(child, &mut payload);
+    }
+    match value {
+        Some(value) => value.encode(&mut payload),
+        None => payload.push(EMPTY_STRING_CODE),
+    }
+    list(payload)
+}
+
+pub(crate) fn extension(prefix: &Nibbles, child: &[u8]) -> Vec<u8> {
+    let mut payload = vec![];
+    prefix.encode_compact().as_slice().encode(&mut payload);
+    embed(child, &mut payload);
+    list(payload)
+}
+
+/// Writes a child reference into the parent node. Hashes are RLP strings, while inline nodes are
+/// already RLP encoded.
+fn embed(child: &[u8], out: &mut Vec<u8>) {
+    if child.len() == 32 {
+        child.encode(out);
+    } else {
+        out.extend_from_slice(child);
+    }
+}
+
+fn list(payload: Vec<u8>) -> Vec<u8> {
+    let mut rlp = Vec::with_capacity(payload.len() + 3);
+    Header {
+        list: true,
+        payload_length: payload.len(),
+    }
+    .encode(&mut rlp);
+    rlp.extend_from_slice(&payload);
+    rlp
+}
+
+/// Child reference as found in the RLP of its parent.
+pub(crate) enum ChildRef<'a> {
+    Empty,
+    Hash(H256),
+    /// RLP of a node shorter than a hash.
+    Inline(&'a [u8]),
+}
+
+/// Node decoded from its RLP, with children left as references.
+pub(crate) enum Decoded<'a> {
+    Empty,
+    Leaf(Nibbles, Vec<u8>),
+    Extension(Nibbles, ChildRef<'a>),
+    Branch(Vec<ChildRef<'a>>, Option<Vec<u8>>),
+}
+
+/// Decodes the RLP of a node. Returns `None` if it isn't a valid trie node.
+pub(crate) fn decode(mut rlp: &[u8]) -> Option<Decoded<'_>> {
+    if rlp == [EMPTY_STRING_CODE] {
+        return Some(Decoded::Empty);
+    }
+    let header = Header::decode(&mut rlp).ok()?;
+    if !header.list || header.payload_length != rlp.len() {
+        return None;
+    }
+
+    // Items of the list, lists are kept with their header as they are inline nodes.
+    let mut items = vec![];
+    while !rlp.is_empty() {
+        let item = rlp;
+        let header = Header::decode(&mut rlp).ok()?;
+        if header.payload_length > rlp.len() {
+            return None;
+        }
+        let (payload, rest) = rlp.split_at(header.payload_length);
+        items.push((header.list, payload, &item[..item.len() - rest.len()]));
+        rlp = rest;
+    }
+
+    match items.as_slice() {
+        [(false, key, _), value] => {
+            // Flags of the compact encoding: 0 and 1 for extensions, 2 and 3 for leaves.
+            let flag = key.first()? >> 4;
+            if flag > 3 || (flag & 1 == 0 && key[0] & 0x0f != 0) {
+                return None;
+            }
+            let key = Nibbles::from_compact(key.to_vec());
+            match value {
+                (false, value, _) if flag >= 2 => Some(Decoded::Leaf(key, value.to_vec())),
+                _ if flag < 2 => Some(Decoded::Extension(key, child_ref(*value)?)),
+                _ => None,
+            }
+        }
+        [children @ .., (false, value, _)] if children.len() == 16 => {
+            let children = children
+                .iter()
+                .map(|item| child_ref(*item))
+                .collect::<Option<_>>()?;
+            let value = (!value.is_empty()).then(|| value.to_vec());
+            Some(Decoded::Branch(children, value))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes a child reference from a list item, given as `(is_list, payload, item)`.
+fn child_ref<'a>((list, payload, item): (bool, &'a [u8], &'a [u8])) -> Option<ChildRef<'a>> {
+    match (list, payload.len()) {
+        (true, _) => Some(ChildRef::Inline(item)),
+        (false, 0) => Some(ChildRef::Empty),
+        (false, 32) => Some(ChildRef::Hash(H256::from_slice(payload))),
+        _ => None,
+    }
+}
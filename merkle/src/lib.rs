@@ -1,5 +1,19 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
 pub mod node;
 
+mod builder;
+mod encode;
+mod receipts;
+mod store;
 mod trie;
 
+pub use builder::{IndexedTrieBuilder, OrderedTrieBuilder};
+pub use receipts::ReceiptsTrie;
+#[cfg(feature = "sqlite")]
+pub use store::SqliteStore;
+pub use store::{MemoryStore, NodeStore, StoreError};
 pub use trie::{IterativeTrie, PatriciaTrie};
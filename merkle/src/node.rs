@@ -1,103 +1,79 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+//! Nodes of a [`PatriciaTrie`](crate::PatriciaTrie).
+//!
+//! Nodes live in the arena of the trie and point to their children by [`NodeId`], so the trie is
+//! `Send + Sync`. This breaks the previous API, where nodes were shared as `Rc<RefCell<_>>`:
+//! `Node::into_leaf`, `Node::into_extension`, `BranchNode::insert` and `HashNode` were removed;
+//! match on [`Node`] and set [`BranchNode::children`] or [`BranchNode::value`] directly instead.
+
+use alloc::vec::Vec;
 
 use types::Nibbles;
 
-#[derive(Debug, Clone, Default)]
+/// Index of a node in the arena of a [`PatriciaTrie`](crate::PatriciaTrie).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub(crate) usize);
+
+impl NodeId {
+    /// The empty node is stored once at the start of the arena and shared by all empty slots.
+    pub const EMPTY: NodeId = NodeId(0);
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum Node {
     #[default]
     Empty,
-    Leaf(Rc<RefCell<LeafNode>>),
-    Extension(Rc<RefCell<ExtensionNode>>),
-    Branch(Rc<RefCell<BranchNode>>),
+    Leaf(LeafNode),
+    Extension(ExtensionNode),
+    Branch(BranchNode),
 }
 
 impl Node {
     pub fn from_leaf(key: Nibbles, value: Vec<u8>) -> Self {
-        let leaf = Rc::new(RefCell::new(LeafNode { key, value }));
-        Node::Leaf(leaf)
+        Node::Leaf(LeafNode { key, value })
     }
 
-    pub fn from_branch(children: [Node; 16], value: Option<Vec<u8>>) -> Self {
-        let branch = Rc::new(RefCell::new(BranchNode { children, value }));
-        Node::Branch(branch)
+    pub fn from_branch(children: [NodeId; 16], value: Option<Vec<u8>>) -> Self {
+        Node::Branch(BranchNode { children, value })
     }
 
-    pub fn from_extension(prefix: Nibbles, node: Node) -> Self {
-        let ext = Rc::new(RefCell::new(ExtensionNode { prefix, node }));
-        Node::Extension(ext)
-    }
-
-    pub fn into_leaf(self) -> Option<Rc<RefCell<LeafNode>>> {
-        match self {
-            Node::Leaf(leaf) => Some(leaf),
-            _ => None,
-        }
-    }
-
-    pub fn into_extension(self) -> Option<Rc<RefCell<ExtensionNode>>> {
-        match self {
-            Node::Extension(ext) => Some(ext),
-            _ => None,
-        }
+    pub fn from_extension(prefix: Nibbles, node: NodeId) -> Self {
+        Node::Extension(ExtensionNode { prefix, node })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LeafNode {
     pub key: Nibbles,
     pub value: Vec<u8>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BranchNode {
-    pub children: [Node; 16],
+    pub children: [NodeId; 16],
     pub value: Option<Vec<u8>>,
 }
 
 impl BranchNode {
-    pub fn insert(&mut self, i: usize, n: Node) {
-        if i == 16 {
-            match n {
-                Node::Leaf(leaf) => {
-                    self.value = Some(leaf.borrow().value.clone());
-                }
-                _ => panic!("The n must be leaf node"),
-            }
-        } else {
-            self.children[i] = n
+    pub fn new() -> Self {
+        BranchNode {
+            children: empty_children(),
+            value: None,
         }
     }
 }
 
-#[derive(Debug)]
-pub struct ExtensionNode {
-    pub prefix: Nibbles,
-    pub node: Node,
+impl Default for BranchNode {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[derive(Debug)]
-pub struct HashNode {
-    pub hash: Vec<u8>,
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionNode {
+    pub prefix: Nibbles,
+    pub node: NodeId,
 }
 
-pub fn empty_children() -> [Node; 16] {
-    [
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-        Node::Empty,
-    ]
+pub fn empty_children() -> [NodeId; 16] {
+    [NodeId::EMPTY; 16]
 }
@@ -0,0 +1,117 @@
+use alloc::vec::Vec;
+
+use types::{MerkleProof, TransactionReceipt, H256};
+
+use crate::node::NodeId;
+use crate::trie::root_from_encoding;
+use crate::{IterativeTrie, PatriciaTrie};
+
+/// Receipts trie of a single block.
+///
+/// The trie is built once from all the receipts of the block and encodings of its nodes are
+/// cached, so proofs for any number of receipts are generated without re-hashing the trie.
+#[derive(Debug, Clone)]
+pub struct ReceiptsTrie {
+    trie: PatriciaTrie,
+    /// Encoding of every node of the trie, indexed by [`NodeId`].
+    encoded_nodes: Vec<Vec<u8>>,
+    len: usize,
+}
+
+impl ReceiptsTrie {
+    /// Builds the trie from all the receipts of a block, ordered by transaction index.
+    pub fn new(receipts: &[TransactionReceipt]) -> Self {
+        let mut trie = PatriciaTrie::new();
+        for (index, receipt) in receipts.iter().enumerate() {
+            trie.insert(alloy_rlp::encode(index), alloy_rlp::encode(receipt));
+        }
+
+        let mut encoded_nodes = vec![vec![]; trie.nodes_count()];
+        trie.encode_subtree(trie.root_node(), |id, _, reference| {
+            encoded_nodes[id.0] = reference.to_vec();
+        });
+
+        ReceiptsTrie {
+            trie,
+            encoded_nodes,
+            len: receipts.len(),
+        }
+    }
+
+    /// Root of the trie, to be compared with the `receipts_root` of the block header.
+    pub fn root(&self) -> H256 {
+        root_from_encoding(&self.encoded_nodes[self.trie.root_node().0])
+    }
+
+    /// Number of receipts in the trie.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Creates a proof for the receipt with the given transaction index.
+    pub fn merkle_proof(&self, index: usize) -> MerkleProof {
+        self.trie
+            .merkle_proof_with(alloy_rlp::encode(index), |id| self.encoded_node(id))
+    }
+
+    fn encoded_node(&self, id: NodeId) -> Vec<u8> {
+        self.encoded_nodes[id.0].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{Bloom, Receipt, TransactionReceipt, TxType, H256};
+
+    use super::ReceiptsTrie;
+    use crate::IterativeTrie;
+
+    fn receipts(count: u8) -> Vec<TransactionReceipt> {
+        (0..count)
+            .map(|e| TransactionReceipt {
+                bloom: Bloom::new([e; 256]),
+                receipt: Receipt {
+                    tx_type: TxType::EIP1559,
+                    logs: vec![],
+                    cumulative_gas_used: e as u64,
+                    success: true,
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn proofs_match_rebuilt_trie() {
+        let receipts = receipts(200);
+        let receipts_trie = ReceiptsTrie::new(&receipts);
+        assert_eq!(receipts_trie.len(), 200);
+
+        let mut trie = crate::PatriciaTrie::new();
+        for (index, receipt) in receipts.iter().enumerate() {
+            trie.insert(alloy_rlp::encode(index), alloy_rlp::encode(receipt));
+        }
+
+        for index in [0, 1, 55, 127, 128, 199] {
+            let proof = receipts_trie.merkle_proof(index);
+            assert_eq!(proof, trie.merkle_proof(alloy_rlp::encode(index)));
+            assert_eq!(proof.merkle_root(&receipts[index]), receipts_trie.root());
+        }
+    }
+
+    #[test]
+    fn empty_trie_root() {
+        let trie = ReceiptsTrie::new(&[]);
+        assert!(trie.is_empty());
+        assert_eq!(
+            trie.root(),
+            H256::from_slice(
+                &hex::decode("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+                    .unwrap()
+            )
+        );
+    }
+}
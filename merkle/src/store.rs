@@ -0,0 +1,213 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use types::H256;
+
+/// Storage of encoded trie nodes, keyed by their hashes.
+///
+/// Only nodes that are referenced by hash are stored: nodes shorter than a hash are inlined in
+/// their parents. The root is always stored, so the trie can be reopened from its root hash with
+/// [`PatriciaTrie::open`](crate::PatriciaTrie::open).
+pub trait NodeStore {
+    type Error;
+
+    /// Returns the RLP of the node with the given hash.
+    fn get(&self, hash: &H256) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Stores a batch of nodes as `(hash, rlp)` pairs.
+    fn insert(&mut self, nodes: Vec<(H256, Vec<u8>)>) -> Result<(), Self::Error>;
+}
+
+/// Error of loading a trie from a [`NodeStore`].
+#[derive(Debug)]
+pub enum StoreError<E> {
+    /// The store failed.
+    Store(E),
+    /// The node is referenced by the trie, but isn't in the store.
+    MissingNode(H256),
+    /// The stored node, or a node inlined in it, can't be decoded.
+    InvalidNode(H256),
+}
+
+/// Node store that keeps everything in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    nodes: BTreeMap<H256, Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Number of stored nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl NodeStore for MemoryStore {
+    type Error = core::convert::Infallible;
+
+    fn get(&self, hash: &H256) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.nodes.get(hash).cloned())
+    }
+
+    fn insert(&mut self, nodes: Vec<(H256, Vec<u8>)>) -> Result<(), Self::Error> {
+        self.nodes.extend(nodes);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::path::Path;
+
+    use rusqlite::{Connection, OptionalExtension};
+    use types::H256;
+
+    use super::NodeStore;
+
+    /// Node store backed by a SQLite database.
+    pub struct SqliteStore {
+        conn: Connection,
+    }
+
+    impl SqliteStore {
+        /// Opens the database at `path`, creating the nodes table if needed.
+        pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+            Self::new(Connection::open(path)?)
+        }
+
+        /// Uses an already opened connection, creating the nodes table if needed.
+        pub fn new(conn: Connection) -> rusqlite::Result<Self> {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS trie_nodes (
+                    hash BLOB PRIMARY KEY NOT NULL,
+                    node BLOB NOT NULL
+                );",
+            )?;
+            Ok(SqliteStore { conn })
+        }
+    }
+
+    impl NodeStore for SqliteStore {
+        type Error = rusqlite::Error;
+
+        fn get(&self, hash: &H256) -> Result<Option<Vec<u8>>, Self::Error> {
+            self.conn
+                .query_row(
+                    "SELECT node FROM trie_nodes WHERE hash = ?1",
+                    [hash.0],
+                    |row| row.get(0),
+                )
+                .optional()
+        }
+
+        fn insert(&mut self, nodes: Vec<(H256, Vec<u8>)>) -> Result<(), Self::Error> {
+            // Nodes are content addressed, so an already stored node doesn't have to be replaced.
+            let tx = self.conn.transaction()?;
+            {
+                let mut stmt =
+                    tx.prepare("INSERT OR IGNORE INTO trie_nodes(hash, node) VALUES (?1, ?2)")?;
+                for (hash, node) in nodes {
+                    stmt.execute((hash.0, node))?;
+                }
+            }
+            tx.commit()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::{MemoryStore, NodeStore, StoreError};
+    use crate::{IterativeTrie, PatriciaTrie};
+
+    fn random_trie(count: usize) -> (PatriciaTrie, Vec<Vec<u8>>) {
+        let mut rng = thread_rng();
+        let mut trie = PatriciaTrie::new();
+        let keys = (0..count)
+            .map(|_| {
+                // Keys of the same length are prefix-free.
+                let key = (0..4).map(|_| rng.gen()).collect::<Vec<u8>>();
+                let value = (0..rng.gen_range(1, 60))
+                    .map(|_| rng.gen())
+                    .collect::<Vec<u8>>();
+                trie.insert(key.clone(), value);
+                key
+            })
+            .collect();
+        (trie, keys)
+    }
+
+    fn assert_reopens(store: &mut impl NodeStore<Error = impl core::fmt::Debug>) {
+        for count in [0, 1, 2, 300] {
+            let (trie, keys) = random_trie(count);
+            let root = trie.commit(store).unwrap();
+            assert_eq!(root, trie.root_hash());
+
+            let reopened = PatriciaTrie::open(store, root).unwrap();
+            assert_eq!(reopened.root_hash(), root);
+            for key in keys {
+                assert_eq!(reopened.get(&key), trie.get(&key));
+                assert_eq!(
+                    reopened.merkle_proof(key.clone()),
+                    trie.merkle_proof(key.clone())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reopens_branch_values() {
+        let mut trie = PatriciaTrie::new();
+        for (key, value) in [
+            ("do", "verb"),
+            ("dog", "puppy"),
+            ("doge", "coin"),
+            ("horse", "s"),
+        ] {
+            trie.insert(key.into(), value.into());
+        }
+        let mut store = MemoryStore::new();
+        let root = trie.commit(&mut store).unwrap();
+
+        let reopened = PatriciaTrie::open(&store, root).unwrap();
+        assert_eq!(reopened.root_hash(), root);
+        assert_eq!(reopened.get(b"do"), Some(b"verb".to_vec()));
+        assert_eq!(reopened.get(b"doge"), Some(b"coin".to_vec()));
+        assert_eq!(reopened.get(b"dogs"), None);
+    }
+
+    #[test]
+    fn memory_store_reopens_trie() {
+        assert_reopens(&mut MemoryStore::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store_reopens_trie() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        assert_reopens(&mut super::SqliteStore::new(conn).unwrap());
+    }
+
+    #[test]
+    fn missing_node() {
+        let (trie, _) = random_trie(100);
+        let root = trie.commit(&mut MemoryStore::new()).unwrap();
+        assert!(matches!(
+            PatriciaTrie::open(&MemoryStore::new(), root),
+            Err(StoreError::MissingNode(hash)) if hash == root
+        ));
+    }
+}
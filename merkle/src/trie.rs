@@ -1,20 +1,63 @@
-use std::cell::RefCell;
-use std::convert::TryInto;
-use std::rc::Rc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
 
-use alloy_rlp::EMPTY_STRING_CODE;
-use types::{MerkleProof, MerkleProofNode, Nibbles, H256};
+use keccak_hash::keccak;
+use types::{MerkleProof, MerkleProofNode, Nibbles, NodeRef, H256};
 
-use crate::node::{empty_children, BranchNode, Node};
+use crate::encode::{self, ChildRef, Decoded};
+use crate::node::{BranchNode, ExtensionNode, LeafNode, Node, NodeId};
+use crate::store::{NodeStore, StoreError};
 
 pub trait IterativeTrie {
     fn insert(&mut self, key: Vec<u8>, value: Vec<u8>);
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+    fn contains(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+    /// Removes the key from the trie. Returns whether the key was present.
+    fn remove(&mut self, key: &[u8]) -> bool;
     fn merkle_proof(&self, key: Vec<u8>) -> MerkleProof;
 }
 
-#[derive(Debug, Default)]
+/// Patricia trie storing its nodes in an arena.
+///
+/// Nodes reference each other by [`NodeId`], so the trie owns all of its data and can be moved
+/// across threads.
+#[derive(Debug, Clone)]
 pub struct PatriciaTrie {
-    root: Node,
+    nodes: Vec<Node>,
+    root: NodeId,
+    /// Slots of removed nodes, reused by the next insertions.
+    free: Vec<NodeId>,
+}
+
+impl Default for PatriciaTrie {
+    fn default() -> Self {
+        PatriciaTrie {
+            nodes: vec![Node::Empty],
+            root: NodeId::EMPTY,
+            free: vec![],
+        }
+    }
+}
+
+/// Turns the encoding of the root node into the root hash.
+pub(crate) fn root_from_encoding(encoded: &[u8]) -> H256 {
+    // Nodes shorter than a hash are stored inline, but the root is always hashed.
+    if encoded.len() < 32 {
+        H256(keccak(encoded).0)
+    } else {
+        H256::from_slice(encoded)
+    }
+}
+
+/// Place in the trie that references a node.
+#[derive(Clone, Copy, Debug)]
+enum Link {
+    Root,
+    Branch(NodeId, usize),
+    Extension(NodeId),
 }
 
 #[derive(Clone, Debug)]
@@ -27,15 +70,15 @@ enum TraceStatus {
 
 #[derive(Clone, Debug)]
 struct TraceNode {
-    node: Node,
+    node: NodeId,
     status: TraceStatus,
 }
 
 impl TraceNode {
-    fn advance(&mut self) {
+    fn advance(&mut self, node: &Node) {
         self.status = match &self.status {
             TraceStatus::Start => TraceStatus::Doing,
-            TraceStatus::Doing => match self.node {
+            TraceStatus::Doing => match node {
                 Node::Branch(_) => TraceStatus::Child(0),
                 _ => TraceStatus::End,
             },
@@ -45,8 +88,8 @@ impl TraceNode {
     }
 }
 
-impl From<Node> for TraceNode {
-    fn from(node: Node) -> TraceNode {
+impl From<NodeId> for TraceNode {
+    fn from(node: NodeId) -> TraceNode {
         TraceNode {
             node,
             status: TraceStatus::Start,
@@ -54,31 +97,33 @@ impl From<Node> for TraceNode {
     }
 }
 
-pub struct TrieIterator {
+pub struct TrieIterator<'a> {
+    trie: &'a PatriciaTrie,
     nibble: Nibbles,
     nodes: Vec<TraceNode>,
 }
 
-impl Iterator for TrieIterator {
+impl<'a> Iterator for TrieIterator<'a> {
     type Item = (Vec<u8>, Vec<u8>);
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let mut now = self.nodes.last().cloned();
             if let Some(ref mut now) = now {
-                self.nodes.last_mut().unwrap().advance();
+                let node = self.trie.node(now.node);
+                self.nodes.last_mut().unwrap().advance(node);
 
-                match (now.status.clone(), &now.node) {
+                match (now.status.clone(), node) {
                     (TraceStatus::End, node) => {
-                        match *node {
-                            Node::Leaf(ref leaf) => {
+                        match node {
+                            Node::Leaf(leaf) => {
                                 let cur_len = self.nibble.len();
-                                self.nibble.truncate(cur_len - leaf.borrow().key.len());
+                                self.nibble.truncate(cur_len - leaf.key.len());
                             }
 
-                            Node::Extension(ref ext) => {
+                            Node::Extension(ext) => {
                                 let cur_len = self.nibble.len();
-                                self.nibble.truncate(cur_len - ext.borrow().prefix.len());
+                                self.nibble.truncate(cur_len - ext.prefix.len());
                             }
 
                             Node::Branch(_) => {
@@ -89,34 +134,36 @@ impl Iterator for TrieIterator {
                         self.nodes.pop();
                     }
 
-                    (TraceStatus::Doing, Node::Extension(ref ext)) => {
-                        self.nibble.extend(&ext.borrow().prefix);
-                        self.nodes.push((ext.borrow().node.clone()).into());
+                    (TraceStatus::Doing, Node::Extension(ext)) => {
+                        self.nibble.extend(&ext.prefix);
+                        self.nodes.push(ext.node.into());
                     }
 
-                    (TraceStatus::Doing, Node::Leaf(ref leaf)) => {
-                        self.nibble.extend(&leaf.borrow().key);
-                        return Some((self.nibble.encode_raw().0, leaf.borrow().value.clone()));
+                    (TraceStatus::Doing, Node::Leaf(leaf)) => {
+                        self.nibble.extend(&leaf.key);
+                        return Some((self.nibble.encode_raw().0, leaf.value.clone()));
                     }
 
-                    (TraceStatus::Doing, Node::Branch(ref branch)) => {
-                        let value = branch.borrow().value.clone();
-                        if let Some(data) = value {
+                    (TraceStatus::Doing, Node::Branch(branch)) => {
+                        if let Some(data) = branch.value.clone() {
+                            // The root branch holds the value of the empty key.
+                            if self.nibble.is_empty() {
+                                return Some((vec![], data));
+                            }
                             return Some((self.nibble.encode_raw().0, data));
                         } else {
                             continue;
                         }
                     }
 
-                    (TraceStatus::Child(i), Node::Branch(ref branch)) => {
+                    (TraceStatus::Child(i), Node::Branch(branch)) => {
                         if i == 0 {
                             self.nibble.push(0);
                         } else {
                             self.nibble.pop();
                             self.nibble.push(i);
                         }
-                        self.nodes
-                            .push((branch.borrow().children[i as usize].clone()).into());
+                        self.nodes.push(branch.children[i as usize].into());
                     }
 
                     (_, Node::Empty) => {
@@ -132,9 +179,10 @@ impl Iterator for TrieIterator {
 }
 
 impl PatriciaTrie {
-    pub fn iter(&self) -> TrieIterator {
-        let nodes = vec![self.root.clone().into()];
+    pub fn iter(&self) -> TrieIterator<'_> {
+        let nodes = vec![self.root.into()];
         TrieIterator {
+            trie: self,
             nibble: Nibbles::from_raw(vec![], false),
             nodes,
         }
@@ -145,352 +193,519 @@ impl PatriciaTrie {
 }
 
 impl PatriciaTrie {
-    pub fn root_node(&self) -> Node {
-        self.root.clone()
+    pub fn root_node(&self) -> NodeId {
+        self.root
     }
 
-    fn insert_at_iterative(n: Node, partial_key: Nibbles, value: Vec<u8>) -> Node {
-        let mut queue = vec![n];
-        let mut partial = Clone::clone(&partial_key);
+    /// Root hash of the trie.
+    pub fn root_hash(&self) -> H256 {
+        root_from_encoding(&self.encode_node(self.root))
+    }
 
-        // Part 1: Find place to insert, or replace value.
-        // Meanwhile, nodes can be replaced with branches or extensions.
-        loop {
-            let index = queue.len() - 1;
-            let borrow_node = &mut queue[index];
+    /// Writes the nodes of the trie to `store` and returns the root hash, which can be used to
+    /// [`open`](Self::open) the trie later.
+    pub fn commit<S: NodeStore>(&self, store: &mut S) -> Result<H256, S::Error> {
+        let mut nodes = vec![];
+        let root = self.encode_subtree(self.root, |_, rlp, reference| {
+            if reference.len() == 32 {
+                nodes.push((H256::from_slice(reference), rlp.to_vec()));
+            }
+        });
+        let root_hash = root_from_encoding(&root);
+        // The root is hashed even if it's short enough to be inlined.
+        if root.len() < 32 {
+            nodes.push((root_hash, root));
+        }
+        store.insert(nodes)?;
+        Ok(root_hash)
+    }
+
+    /// Loads the trie with the given root hash from `store`.
+    pub fn open<S: NodeStore>(store: &S, root: H256) -> Result<Self, StoreError<S::Error>> {
+        let mut trie = PatriciaTrie::new();
+        // Nodes to load, with the link to set once they are in the arena. Inline nodes come with
+        // their RLP and the hash of the stored node they were found in.
+        let mut stack = vec![(Link::Root, root, None)];
+        while let Some((link, hash, inline)) = stack.pop() {
+            let rlp = match inline {
+                Some(rlp) => rlp,
+                None => store
+                    .get(&hash)
+                    .map_err(StoreError::Store)?
+                    .ok_or(StoreError::MissingNode(hash))?,
+            };
+            let invalid = || StoreError::InvalidNode(hash);
+            let (node, children) = match encode::decode(&rlp).ok_or_else(invalid)? {
+                Decoded::Empty => continue,
+                Decoded::Leaf(key, value) => (Node::from_leaf(key, value), vec![]),
+                Decoded::Extension(_, ChildRef::Empty) => return Err(invalid()),
+                Decoded::Extension(prefix, child) => (
+                    Node::from_extension(prefix, NodeId::EMPTY),
+                    vec![(None, child)],
+                ),
+                Decoded::Branch(children, value) => (
+                    Node::from_branch([NodeId::EMPTY; 16], value),
+                    children
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, child)| (Some(i), child))
+                        .collect(),
+                ),
+            };
+
+            let id = trie.push_node(node);
+            trie.set_link(link, id);
+            for (i, child) in children {
+                let link = match i {
+                    Some(i) => Link::Branch(id, i),
+                    None => Link::Extension(id),
+                };
+                match child {
+                    ChildRef::Empty => {}
+                    ChildRef::Hash(child) => stack.push((link, child, None)),
+                    ChildRef::Inline(child) => stack.push((link, hash, Some(child.to_vec()))),
+                }
+            }
+        }
+        Ok(trie)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub(crate) fn nodes_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn push_node(&mut self, node: Node) -> NodeId {
+        if let Some(id) = self.free.pop() {
+            self.nodes[id.0] = node;
+            return id;
+        }
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Takes the node out of the arena, making its slot available for reuse.
+    fn free_node(&mut self, id: NodeId) -> Node {
+        if id == NodeId::EMPTY {
+            return Node::Empty;
+        }
+        self.free.push(id);
+        mem::take(&mut self.nodes[id.0])
+    }
+
+    fn set_link(&mut self, link: Link, child: NodeId) {
+        match link {
+            Link::Root => self.root = child,
+            Link::Branch(id, i) => match &mut self.nodes[id.0] {
+                Node::Branch(branch) => branch.children[i] = child,
+                _ => unreachable!("links only point to branches"),
+            },
+            Link::Extension(id) => match &mut self.nodes[id.0] {
+                Node::Extension(ext) => ext.node = child,
+                _ => unreachable!("links only point to extensions"),
+            },
+        }
+    }
+
+    /// Inserts a leaf with the given key into the branch, or sets the branch value if the key is
+    /// exhausted.
+    fn insert_into_branch(&mut self, branch: &mut BranchNode, key: Nibbles, value: Vec<u8>) {
+        let i = key.at(0);
+        if i == 0x10 {
+            branch.value = Some(value);
+        } else {
+            branch.children[i] = self.push_node(Node::from_leaf(key.offset(1), value));
+        }
+    }
+
+    fn insert_at_iterative(&mut self, partial_key: Nibbles, value: Vec<u8>) {
+        let mut partial = partial_key;
+        let mut link = Link::Root;
+        let mut id = self.root;
 
-            let node_to_push = match borrow_node {
+        // Walk down to the place to insert, or replace value.
+        // Nodes on the way are updated in place, so only an empty slot needs to be linked.
+        loop {
+            // The node is taken out of the arena while we work on it and put back afterwards.
+            match mem::take(&mut self.nodes[id.0]) {
                 Node::Empty => {
                     // Insert leaf node instead.
-                    *borrow_node = Node::from_leaf(partial.clone(), value);
-                    break;
+                    let leaf = self.push_node(Node::from_leaf(partial, value));
+                    self.set_link(link, leaf);
+                    return;
                 }
-                borrow_node if matches!(borrow_node, Node::Leaf(_)) => {
-                    // We will replace the leaf with a branch or extension most likely.
-                    let leaf = std::mem::take(borrow_node);
-                    let leaf = leaf.into_leaf().expect("checked above;");
-
-                    let mut borrow_leaf = leaf.borrow_mut();
-
-                    let old_partial = std::mem::take(&mut borrow_leaf.key);
-                    let match_index = partial.common_prefix(&old_partial);
-
-                    // Key is the same, replace value. But we need to reconstruct it as we took it out.
-                    if match_index == old_partial.len() {
-                        borrow_leaf.value = value;
-                        borrow_leaf.key = old_partial;
-                        drop(borrow_leaf);
-                        *borrow_node = Node::Leaf(leaf);
-                        break;
+                Node::Leaf(mut leaf) => {
+                    let match_index = partial.common_prefix(&leaf.key);
+
+                    // Key is the same, replace value.
+                    if match_index == leaf.key.len() {
+                        leaf.value = value;
+                        self.nodes[id.0] = Node::Leaf(leaf);
+                        return;
                     }
 
                     // Key is not the same, we need to split the leaf into a branch.
-                    let mut branch = BranchNode {
-                        children: empty_children(),
-                        value: None,
-                    };
-
-                    // Insert old leaf.
-                    let n = Node::from_leaf(
-                        old_partial.offset(match_index + 1),
-                        std::mem::take(&mut borrow_leaf.value),
+                    let LeafNode {
+                        key: old_partial,
+                        value: old_value,
+                    } = leaf;
+                    let mut branch = BranchNode::new();
+                    self.insert_into_branch(
+                        &mut branch,
+                        old_partial.offset(match_index),
+                        old_value,
                     );
-
-                    branch.insert(old_partial.at(match_index), n);
-
-                    // Insert new leaf.
-                    let n = Node::from_leaf(partial.offset(match_index + 1), value);
-                    branch.insert(partial.at(match_index), n);
+                    self.insert_into_branch(&mut branch, partial.offset(match_index), value);
 
                     // Replace current node with branch as they don't have a common prefix.
-                    if match_index == 0 {
-                        *borrow_node = Node::Branch(Rc::new(RefCell::new(branch)));
+                    self.nodes[id.0] = if match_index == 0 {
+                        Node::Branch(branch)
                     } else {
                         // Replace current node with extension.
-                        *borrow_node = Node::from_extension(
-                            partial.slice(0, match_index),
-                            Node::Branch(Rc::new(RefCell::new(branch))),
-                        );
-                    }
-                    break;
+                        let branch = self.push_node(Node::Branch(branch));
+                        Node::from_extension(partial.slice(0, match_index), branch)
+                    };
+                    return;
                 }
-                Node::Leaf(_) => unreachable!(),
-                Node::Branch(branch) => {
-                    let mut borrow_branch = branch.borrow_mut();
-
+                Node::Branch(mut branch) => {
                     // Replace value if key is the same.
                     if partial.at(0) == 0x10 {
-                        borrow_branch.value = Some(value);
-                        break;
+                        branch.value = Some(value);
+                        self.nodes[id.0] = Node::Branch(branch);
+                        return;
                     }
 
-                    // Get child node on the path and push it to the queue.
-                    let child = borrow_branch.children[partial.at(0)].clone();
+                    // Continue with the child node on the path.
+                    let i = partial.at(0);
+                    let child = branch.children[i];
+                    self.nodes[id.0] = Node::Branch(branch);
+
+                    link = Link::Branch(id, i);
+                    id = child;
                     partial = partial.offset(1);
-                    Some(child)
                 }
-                borrow_node if matches!(borrow_node, Node::Extension(_)) => {
-                    let ext = std::mem::take(borrow_node);
-                    let ext = ext.into_extension().expect("checked above;");
-
-                    let mut borrow_ext = ext.borrow_mut();
-
-                    let prefix = std::mem::take(&mut borrow_ext.prefix);
-                    let sub_node = borrow_ext.node.clone();
+                Node::Extension(ExtensionNode { prefix, node }) => {
                     let match_index = partial.common_prefix(&prefix);
 
-                    // If they don't share anything, we create a branch and insert both nodes.
+                    // If they don't share anything, we replace the extension with a branch
+                    // and re-iterate it.
                     if match_index == 0 {
-                        let mut branch = BranchNode {
-                            children: empty_children(),
-                            value: None,
+                        let mut branch = BranchNode::new();
+                        branch.children[prefix.at(0)] = if prefix.len() == 1 {
+                            node
+                        } else {
+                            self.push_node(Node::from_extension(prefix.offset(1), node))
                         };
-                        branch.insert(
-                            prefix.at(0),
-                            if prefix.len() == 1 {
-                                sub_node
-                            } else {
-                                Node::from_extension(prefix.offset(1), sub_node)
-                            },
-                        );
-                        *borrow_node = Node::Branch(Rc::new(RefCell::new(branch)));
-                        // We just updated this node, so we need to like re-iterate it.
-                        None
+                        self.nodes[id.0] = Node::Branch(branch);
                     // If they share the whole prefix, we continue with the sub node.
                     } else if match_index == prefix.len() {
-                        borrow_ext.prefix = prefix;
-                        drop(borrow_ext);
+                        self.nodes[id.0] = Node::from_extension(prefix, node);
 
+                        link = Link::Extension(id);
+                        id = node;
                         partial = partial.offset(match_index);
-                        *borrow_node = Node::Extension(ext);
-                        Some(sub_node)
                     // If they share a part of the prefix, we adjust this node to contain same prefix, and create a new extension for the rest.
-                    // This new created extension will be pushed to the queue, but on the next iteration it will be combined into branch.
+                    // This new created extension will be combined into branch on the next iteration.
                     } else {
-                        borrow_ext.prefix = prefix.slice(0, match_index);
-                        drop(borrow_ext);
+                        let new_ext =
+                            self.push_node(Node::from_extension(prefix.offset(match_index), node));
+                        self.nodes[id.0] =
+                            Node::from_extension(prefix.slice(0, match_index), new_ext);
 
-                        *borrow_node = Node::Extension(ext);
-                        let new_ext = Node::from_extension(prefix.offset(match_index), sub_node);
+                        link = Link::Extension(id);
+                        id = new_ext;
                         partial = partial.offset(match_index);
-                        Some(new_ext)
                     }
                 }
-                Node::Extension(_) => unreachable!(),
-            };
-
-            if let Some(node) = node_to_push {
-                queue.push(node);
             }
         }
+    }
 
-        // We need to restore partial key as it was partly consumed in the previous loop.
-        // We ignore the part of the key that wasn't consumed as it stored in the leaf now.
-        let partial = partial.len();
-        let mut partial = partial_key.slice(0, partial_key.len() - partial);
+    fn remove_at_iterative(&mut self, key: Nibbles) -> bool {
+        let key = key.get_data();
+        let mut depth = 0;
+        let mut link = Link::Root;
+        let mut id = self.root;
+        // Nodes on the path to the removed value, they may need to be collapsed afterwards.
+        let mut path = vec![];
 
-        // Part 2: Make links.
-        // We couldn't make links over the previous loop, so we do it now.
-        // Queue contains nodes from the root to the inserted/updated leaf.
-        // We go from the leaf to the root, and make links. This order helps us to avoid cloning nodes.
-        queue
-            .into_iter()
-            .rev()
-            .reduce(|child, parent| {
-                match &parent {
-                    Node::Branch(branch) => {
-                        let mut borrow_branch = branch.borrow_mut();
-                        let key = partial.at(partial.len() - 1);
-                        partial.pop();
-                        borrow_branch.children[key] = child;
+        loop {
+            match &self.nodes[id.0] {
+                Node::Empty => return false,
+                Node::Leaf(leaf) => {
+                    if leaf.key.get_data() != &key[depth..] {
+                        return false;
                     }
-                    Node::Extension(ext) => {
-                        let mut borrow_ext = ext.borrow_mut();
-                        partial = partial.slice(0, partial.len() - borrow_ext.prefix.len());
-                        borrow_ext.node = child;
+                    self.free_node(id);
+                    self.set_link(link, NodeId::EMPTY);
+                    break;
+                }
+                Node::Branch(branch) => {
+                    path.push((link, id));
+
+                    let i = key[depth] as usize;
+                    if i == 0x10 {
+                        if branch.value.is_none() {
+                            return false;
+                        }
+                        if let Node::Branch(branch) = &mut self.nodes[id.0] {
+                            branch.value = None;
+                        }
+                        break;
                     }
-                    _ => unreachable!(),
-                };
-                parent
-            })
-            .expect("We always have at least one node from the input")
+
+                    link = Link::Branch(id, i);
+                    id = branch.children[i];
+                    depth += 1;
+                }
+                Node::Extension(ext) => {
+                    if !key[depth..].starts_with(ext.prefix.get_data()) {
+                        return false;
+                    }
+                    path.push((link, id));
+
+                    link = Link::Extension(id);
+                    depth += ext.prefix.len();
+                    id = ext.node;
+                }
+            }
+        }
+
+        // Restore the canonical form of the trie from the bottom up.
+        for (link, id) in path.into_iter().rev() {
+            self.collapse(link, id);
+        }
+        true
     }
 
-    pub fn encode_node(&self, n: Node) -> Vec<u8> {
-        #[derive(Debug)]
-        enum NodeOrHash {
-            Node { node: Node },
-            Hash(Vec<u8>),
+    /// Collapses a node that was left with a single child or only a value after a removal.
+    fn collapse(&mut self, link: Link, id: NodeId) {
+        let node = match mem::take(&mut self.nodes[id.0]) {
+            Node::Branch(branch) => {
+                let children = branch
+                    .children
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, child)| **child != NodeId::EMPTY)
+                    .map(|(i, child)| (i, *child))
+                    .take(2)
+                    .collect::<Vec<_>>();
+                match (children.as_slice(), branch.value) {
+                    // Only the value is left, so the branch becomes a leaf.
+                    ([], Some(value)) => Node::from_leaf(Nibbles::from_hex(vec![0x10]), value),
+                    ([], None) => Node::Empty,
+                    // A single child is left, so it's merged with the nibble leading to it.
+                    ([(i, child)], None) => self.merge(Nibbles::from_hex(vec![*i as u8]), *child),
+                    (_, value) => Node::Branch(BranchNode {
+                        children: branch.children,
+                        value,
+                    }),
+                }
+            }
+            Node::Extension(ExtensionNode { prefix, node }) => self.merge(prefix, node),
+            node => node,
+        };
+
+        if node == Node::Empty {
+            self.free_node(id);
+            self.set_link(link, NodeId::EMPTY);
+        } else {
+            self.nodes[id.0] = node;
         }
+    }
 
-        let mut stack = vec![(NodeOrHash::Node { node: n }, 0, 0)];
-        let mut counter = 0;
-        loop {
-            let node_or_hash = &stack[counter];
-            // If we hashed everything, we are done.
-            let (n, depth, parent) = (
-                match &node_or_hash.0 {
-                    NodeOrHash::Node { node } => node,
-                    NodeOrHash::Hash(_) => {
-                        if counter == 0 {
-                            break;
-                        }
-                        counter -= 1;
-                        continue;
-                    }
-                },
-                node_or_hash.1,
-                node_or_hash.2,
-            );
+    /// Builds the node for the `prefix` path leading to `child`, merging it into the child if
+    /// possible.
+    fn merge(&mut self, prefix: Nibbles, child: NodeId) -> Node {
+        match self.node(child) {
+            Node::Empty => Node::Empty,
+            Node::Branch(_) => Node::from_extension(prefix, child),
+            Node::Leaf(_) | Node::Extension(_) => match self.free_node(child) {
+                Node::Leaf(leaf) => Node::from_leaf(prefix.join(&leaf.key), leaf.value),
+                Node::Extension(ext) => Node::from_extension(prefix.join(&ext.prefix), ext.node),
+                _ => unreachable!("checked above"),
+            },
+        }
+    }
 
-            match n.clone() {
+    pub fn encode_node(&self, id: NodeId) -> Vec<u8> {
+        self.encode_subtree(id, |_, _, _| {})
+    }
+
+    /// Encodes the subtree rooted at `id` and returns the reference to its root. The RLP and the
+    /// reference of every node in the subtree are passed to `visit`.
+    pub(crate) fn encode_subtree(
+        &self,
+        id: NodeId,
+        mut visit: impl FnMut(NodeId, &[u8], &[u8]),
+    ) -> Vec<u8> {
+        // Post-order traversal: children are encoded before their parent, so when we get back to
+        // the parent, references to its children are on top of the `encoded` stack.
+        let mut stack = vec![(id, false)];
+        let mut encoded: Vec<Vec<u8>> = vec![];
+        let mut push = |encoded: &mut Vec<Vec<u8>>, id: NodeId, rlp: Vec<u8>| {
+            let reference = encode::reference(rlp.clone());
+            visit(id, &rlp, &reference);
+            encoded.push(reference);
+        };
+        while let Some((id, children_encoded)) = stack.pop() {
+            match (self.node(id), children_encoded) {
                 // We can safely replace node with empty node hash
-                Node::Empty => {
-                    stack[counter].0 = NodeOrHash::Hash(vec![EMPTY_STRING_CODE]);
-                    counter = parent;
-                }
+                (Node::Empty, _) => push(&mut encoded, id, encode::empty()),
                 // Hash leaf node and replace it with hash
-                Node::Leaf(leaf) => {
-                    let borrow_leaf = leaf.borrow();
-                    let leaf = types::encoding::LeafEncoder {
-                        key: &borrow_leaf.key.encode_compact(),
-                        value: &borrow_leaf.value,
-                    };
-                    let hash = alloy_rlp::encode(leaf);
-
-                    stack[counter].0 = NodeOrHash::Hash(hash);
-                    counter = parent;
+                (Node::Leaf(leaf), _) => {
+                    push(&mut encoded, id, encode::leaf(&leaf.key, &leaf.value));
                 }
-                // It means we haven't processed all the children yet.
-                // We push the child to the stack and increase the depth counter.
-                Node::Branch(branch) if depth < 16 => {
-                    let borrow_branch: std::cell::Ref<'_, BranchNode> = branch.borrow();
-                    stack.push((
-                        NodeOrHash::Node {
-                            node: borrow_branch.children[depth].clone(),
-                        },
-                        0,
-                        counter,
-                    ));
-                    stack[counter].1 += 1;
-                    counter = stack.len() - 1;
+                // We haven't processed the children yet, so we push them to the stack in reverse
+                // order to encode them in the natural one.
+                (Node::Branch(branch), false) => {
+                    stack.push((id, true));
+                    stack.extend(branch.children.iter().rev().map(|child| (*child, false)));
                 }
                 // We have processed all the children, so we can combine and hash them.
-                Node::Branch(branch) => {
-                    let borrow_branch = branch.borrow();
-                    let branch = types::BranchNode {
-                        branches: stack
-                            .drain(counter + 1..counter + 17)
-                            .map(|(n, _, _)| match n {
-                                NodeOrHash::Node { .. } => unreachable!(),
-                                NodeOrHash::Hash(hash) => {
-                                    if hash.len() == 1 {
-                                        None
-                                    } else {
-                                        Some(H256::from_slice(&hash))
-                                    }
-                                }
-                            })
-                            .collect::<Vec<_>>()[..16]
-                            .try_into()
-                            .expect("We always have 16 branches"),
-                        value: borrow_branch.value.clone(),
-                    };
-                    stack[counter].0 = NodeOrHash::Hash(alloy_rlp::encode(&branch));
-                    counter = parent;
+                (Node::Branch(branch), true) => {
+                    let children = encoded.split_off(encoded.len() - 16);
+                    let branch =
+                        encode::branch(children.iter().map(Vec::as_slice), branch.value.as_deref());
+                    push(&mut encoded, id, branch);
                 }
-                // It means we haven't processed the child yet. We push the child to the stack and increase the depth counter.
-                Node::Extension(ext) if depth == 0 => {
-                    let borrow_ext = ext.borrow();
-                    stack.push((
-                        NodeOrHash::Node {
-                            node: borrow_ext.node.clone(),
-                        },
-                        0,
-                        counter,
-                    ));
-                    stack[counter].1 += 1;
-                    counter = stack.len() - 1;
+                (Node::Extension(ext), false) => {
+                    stack.push((id, true));
+                    stack.push((ext.node, false));
                 }
                 // We have processed the child, so we can hash it.
-                Node::Extension(ext) => {
-                    let borrow_ext = ext.borrow();
-                    let extension = types::ExtensionNode::new(
-                        borrow_ext.prefix.clone(),
-                        H256::from_slice(&match &stack[counter + 1].0 {
-                            NodeOrHash::Node { .. } => unreachable!(),
-                            NodeOrHash::Hash(hash) => hash.clone(),
-                        }),
-                    );
-                    stack[counter].0 = NodeOrHash::Hash(alloy_rlp::encode(&extension));
-                    stack.pop();
-                    counter = parent;
+                (Node::Extension(ext), true) => {
+                    let child = encoded.pop().expect("child is encoded before the parent");
+                    push(&mut encoded, id, encode::extension(&ext.prefix, &child));
                 }
             }
         }
         // I expect that we have only one element in the stack as we combined everything.
-        assert!(stack.len() == 1);
+        assert!(encoded.len() == 1);
 
-        // We return the root hash.
-        match stack.pop() {
-            Some((NodeOrHash::Hash(hash), _, _)) => hash,
-            _ => unreachable!(),
-        }
+        encoded.pop().expect("checked above")
     }
 }
 
 impl IterativeTrie for PatriciaTrie {
     fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
-        let root = self.root.clone();
-        self.root =
-            PatriciaTrie::insert_at_iterative(root, Nibbles::from_raw(key, true), value.to_vec());
+        self.insert_at_iterative(Nibbles::from_raw(key, true), value);
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let key = Nibbles::from_raw(key.to_vec(), true);
+        let key = key.get_data();
+        let mut depth = 0;
+        let mut id = self.root;
+        loop {
+            match self.node(id) {
+                Node::Empty => return None,
+                Node::Leaf(leaf) => {
+                    return (leaf.key.get_data() == &key[depth..]).then(|| leaf.value.clone());
+                }
+                Node::Branch(branch) => {
+                    let i = key[depth] as usize;
+                    if i == 0x10 {
+                        return branch.value.clone();
+                    }
+                    id = branch.children[i];
+                    depth += 1;
+                }
+                Node::Extension(ext) => {
+                    if !key[depth..].starts_with(ext.prefix.get_data()) {
+                        return None;
+                    }
+                    depth += ext.prefix.len();
+                    id = ext.node;
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) -> bool {
+        self.remove_at_iterative(Nibbles::from_raw(key.to_vec(), true))
     }
 
     /// Creates a proof for the given key.
     /// The proof is a list of nodes that are needed to prove that the key is in the trie.
     /// The nodes are on the path from the root to the leaf. All other subtrees are hashed.
     fn merkle_proof(&self, proving_key: Vec<u8>) -> MerkleProof {
+        self.merkle_proof_with(proving_key, |id| self.encode_node(id))
+    }
+}
+
+impl PatriciaTrie {
+    /// Creates a proof for the given key, using `encode_node` to get encodings of the subtrees
+    /// that are not on the path to the leaf.
+    pub(crate) fn merkle_proof_with(
+        &self,
+        proving_key: Vec<u8>,
+        encode_node: impl Fn(NodeId) -> Vec<u8>,
+    ) -> MerkleProof {
         let mut key = Nibbles::from_raw(proving_key.clone(), true);
 
         let mut processing_queue = vec![self.root_node()];
         let mut proof = vec![];
         while let Some(node) = processing_queue.pop() {
-            match node {
+            match self.node(node) {
                 // If we encounter a extension node, we skip common prefix and continue processing it's child
                 Node::Extension(node) => {
-                    let node = node.borrow();
-
                     key = key.offset(key.common_prefix(&node.prefix));
                     proof.push(MerkleProofNode::ExtensionNode {
                         prefix: node.prefix.clone(),
                     });
-                    processing_queue.push(node.node.clone());
+                    processing_queue.push(node.node);
                 }
                 // if we encounter a branch node, we have to hash all the children except the one on the path to the leaf
                 Node::Branch(node) => {
-                    let node = node.borrow();
+                    // The key ends at the branch, so the proven value is the value of the branch.
+                    // It's provided by the caller of the verification function, like leaf data.
+                    if key.at(0) == 16 {
+                        let branches = node
+                            .children
+                            .iter()
+                            .map(|child| {
+                                let encoded_node = encode_node(*child);
+                                (encoded_node.len() != 1)
+                                    .then(|| NodeRef::from_encoded(encoded_node))
+                            })
+                            .collect::<Vec<_>>();
+                        proof.push(MerkleProofNode::BranchNode {
+                            branches: Box::new(
+                                branches
+                                    .try_into()
+                                    .expect("branches are 16 long, so this should never fail"),
+                            ),
+                            index: 16,
+                            value: None,
+                        });
+                        continue;
+                    }
+
                     let branches = node
                         .children
-                        .clone()
                         .iter()
                         .enumerate()
-                        .map(|(i, node)| {
+                        .map(|(i, child)| {
                             // We don't need to encode the node on the path to the leaf as it will be processed
                             if i == key.at(0) {
                                 return None;
                             }
 
                             // Encode subtree it's not on the path to the leaf
-                            let encoded_node = self.encode_node(node.clone());
+                            let encoded_node = encode_node(*child);
                             // It will return a single byte if the node is empty
                             if encoded_node.len() == 1 {
                                 None
                             } else {
-                                Some(H256::from_slice(&encoded_node))
+                                Some(NodeRef::from_encoded(encoded_node))
                             }
                         })
                         .collect::<Vec<_>>();
-                    let next = node.children[key.at(0)].clone();
+                    let next = node.children[key.at(0)];
                     proof.push(MerkleProofNode::BranchNode {
                         branches: Box::new(
                             branches
@@ -507,7 +722,6 @@ impl IterativeTrie for PatriciaTrie {
                 // We don't need to process them:
                 // * Leaf node data is provided by the caller of the verification function
                 // * Empty nodes are not included in the proof
-                // * Hash nodes are included by merkle_generator.get_proof
                 Node::Empty | Node::Leaf(_) => (),
             };
         }
@@ -522,12 +736,15 @@ impl IterativeTrie for PatriciaTrie {
 #[cfg(test)]
 mod tests {
     use cita_trie::Trie;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
     use rand::distributions::Alphanumeric;
     use rand::{thread_rng, Rng};
     use std::collections::HashMap;
     use std::sync::Arc;
 
     use hasher::HasherKeccak;
+    use test_strategy::proptest;
 
     use super::{IterativeTrie, PatriciaTrie};
 
@@ -545,6 +762,12 @@ mod tests {
         assert_eq!(trie.iter().count(), 10000);
     }
 
+    #[test]
+    fn trie_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PatriciaTrie>();
+    }
+
     #[test]
     fn test_trie_insert() {
         let mut trie = PatriciaTrie::new();
@@ -569,6 +792,69 @@ mod tests {
         assert_eq!(trie.iter().count(), 1000);
     }
 
+    #[test]
+    fn get_insert_remove() {
+        let mut trie = PatriciaTrie::new();
+        for (key, value) in [
+            (&b"do"[..], &b"verb"[..]),
+            (b"dog", b"puppy"),
+            (b"doge", b"coin"),
+            (b"horse", b"stallion"),
+        ] {
+            trie.insert(key.to_vec(), value.to_vec());
+        }
+        // Root from the Ethereum trie tests, it covers inline nodes and branch values.
+        assert_eq!(
+            hex::encode(trie.root_hash().0),
+            "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+        );
+
+        assert_eq!(trie.get(b"dog"), Some(b"puppy".to_vec()));
+        assert_eq!(trie.get(b"do"), Some(b"verb".to_vec()));
+        assert_eq!(trie.get(b"d"), None);
+        assert!(!trie.contains(b"dogs"));
+
+        assert!(trie.remove(b"do"));
+        assert!(!trie.remove(b"do"));
+        assert_eq!(trie.get(b"do"), None);
+        assert_eq!(trie.get(b"doge"), Some(b"coin".to_vec()));
+
+        for key in [&b"dog"[..], b"doge", b"horse"] {
+            assert!(trie.remove(key));
+        }
+        assert_eq!(trie.root_hash(), PatriciaTrie::new().root_hash());
+        assert_eq!(trie.iter().count(), 0);
+    }
+
+    #[proptest]
+    fn insert_and_remove_match_cita_trie(
+        #[strategy(vec((vec(0u8..4, 0..4), vec(any::<u8>(), 1..40)), 0..64))] entries: Vec<(
+            Vec<u8>,
+            Vec<u8>,
+        )>,
+        #[strategy(vec(vec(0u8..4, 0..4), 0..64))] removals: Vec<Vec<u8>>,
+    ) {
+        let mut trie = PatriciaTrie::new();
+        let mut cita_trie = cita_trie::PatriciaTrie::new(
+            Arc::new(cita_trie::MemoryDB::new(true)),
+            Arc::new(HasherKeccak::new()),
+        );
+
+        for (key, value) in &entries {
+            trie.insert(key.clone(), value.clone());
+            cita_trie.insert(key.clone(), value.clone()).unwrap();
+        }
+        prop_assert_eq!(trie.root_hash().0.to_vec(), cita_trie.root().unwrap());
+
+        for key in &removals {
+            prop_assert_eq!(trie.remove(key), cita_trie.remove(key).unwrap());
+            prop_assert_eq!(trie.root_hash().0.to_vec(), cita_trie.root().unwrap());
+        }
+        for (key, _) in &entries {
+            prop_assert_eq!(trie.get(key), cita_trie.get(key).unwrap());
+        }
+    }
+
     #[test]
     fn iterator_trie() {
         let mut kv = HashMap::new();
@@ -602,7 +888,9 @@ mod merkle_proof {
     use cita_trie::{MemoryDB, PatriciaTrie, Trie};
     use hasher::HasherKeccak;
 
-    use types::{Bloom, Receipt, TransactionReceipt, H256};
+    use types::{
+        Bloom, CompactMerkleProof, MerkleProof, MerkleProofNode, Receipt, TransactionReceipt, H256,
+    };
 
     use crate::IterativeTrie;
 
@@ -660,5 +948,36 @@ mod merkle_proof {
                 .map(transaction_to_key_value),
         );
         assert_eq!(root, restored_root);
+        assert_eq!(root, trie.root_hash());
+    }
+
+    #[test]
+    fn merkle_proof_of_branch_value() {
+        let receipt = TransactionReceipt {
+            bloom: Bloom::new([1; 256]),
+            receipt: Receipt {
+                tx_type: types::TxType::EIP1559,
+                logs: vec![],
+                cumulative_gas_used: 1,
+                success: true,
+            },
+        };
+        let mut trie = crate::PatriciaTrie::new();
+        // The key of the receipt is a prefix of the other keys, so the receipt is stored as the
+        // value of a branch rather than in a leaf.
+        trie.insert(vec![0x12], alloy_rlp::encode(&receipt));
+        trie.insert(vec![0x12, 0x34], vec![1; 40]);
+        trie.insert(vec![0x12, 0x56], vec![2; 40]);
+
+        let proof = trie.merkle_proof(vec![0x12]);
+        assert!(matches!(
+            proof.proof.last(),
+            Some(MerkleProofNode::BranchNode { index: 16, .. })
+        ));
+        assert_eq!(proof.merkle_root(&receipt), trie.root_hash());
+
+        let compact = CompactMerkleProof::from(&proof);
+        assert_eq!(compact.merkle_root(&receipt), Ok(trie.root_hash()));
+        assert_eq!(MerkleProof::try_from(&compact).unwrap(), proof);
     }
 }
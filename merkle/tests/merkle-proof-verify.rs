@@ -1,5 +1,6 @@
+use alloy_rlp::Decodable;
 use merkle_generator::IterativeTrie;
-use types::{EventProof, H256};
+use types::{CompactMerkleProof, EventProof, MerkleProof, MerkleProofNode, NodeRef, H256};
 
 mod common;
 
@@ -17,6 +18,14 @@ fn merkle_proof_test(test_block: &str, test_block_receipts: &str) {
     for (i, receipt) in receipts.iter().enumerate() {
         let key = alloy_rlp::encode(i);
         let proof = trie.merkle_proof(key);
+
+        let compact = CompactMerkleProof::from(&proof);
+        assert_eq!(compact.merkle_root(receipt), Ok(block_header.receipts_root));
+        let encoded = alloy_rlp::encode(&compact);
+        let decoded = CompactMerkleProof::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded, compact);
+        assert_eq!(MerkleProof::try_from(&decoded).unwrap(), proof);
+
         let hash = H256::hash(receipt);
         let proof = EventProof {
             block_hash,
@@ -26,7 +35,40 @@ fn merkle_proof_test(test_block: &str, test_block_receipts: &str) {
             merkle_proof_of_receipt: proof,
         };
 
-        proof.validate().unwrap()
+        proof.validate().unwrap();
+
+        // Proofs are submitted as JSON, which carries the compact proof.
+        let json = serde_json::to_string(&proof).unwrap();
+        let submitted: EventProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            submitted.merkle_proof_of_receipt,
+            proof.merkle_proof_of_receipt
+        );
+        submitted.validate().unwrap()
+    }
+}
+
+fn receipts_trie_test(test_block: &str, test_block_receipts: &str) {
+    let (_, block_header) = common::load_block(test_block);
+    let receipts = common::load_receipts(test_block_receipts);
+
+    let trie = merkle_generator::ReceiptsTrie::new(&receipts);
+    assert_eq!(trie.root(), block_header.receipts_root);
+
+    let mut builder = merkle_generator::IndexedTrieBuilder::new();
+    receipts
+        .iter()
+        .for_each(|receipt| builder.push(alloy_rlp::encode(receipt)));
+    assert_eq!(builder.finish(), block_header.receipts_root);
+
+    let mut patricia_trie = merkle_generator::PatriciaTrie::new();
+    receipts.iter().enumerate().for_each(|(i, receipt)| {
+        patricia_trie.insert(alloy_rlp::encode(i), alloy_rlp::encode(receipt));
+    });
+    assert_eq!(patricia_trie.root_hash(), block_header.receipts_root);
+
+    for (i, receipt) in receipts.iter().enumerate() {
+        assert_eq!(trie.merkle_proof(i).merkle_root(receipt), trie.root());
     }
 }
 
@@ -50,3 +92,54 @@ fn merkle_proof_8652100() {
     let block_receipts = include_str!("../tests/suits/block_8652100_receipts.json");
     merkle_proof_test(test_block, block_receipts)
 }
+
+#[test]
+fn receipts_trie() {
+    receipts_trie_test(
+        include_str!("../tests/suits/block_17819525.json"),
+        include_str!("../tests/suits/block_17819525_receipts.json"),
+    );
+    receipts_trie_test(
+        include_str!("../tests/suits/block_18027905.json"),
+        include_str!("../tests/suits/block_18027905_receipts.json"),
+    );
+    receipts_trie_test(
+        include_str!("../tests/suits/block_8652100.json"),
+        include_str!("../tests/suits/block_8652100_receipts.json"),
+    );
+}
+
+#[test]
+fn inline_nodes() {
+    let receipts =
+        common::load_receipts(include_str!("../tests/suits/block_17819525_receipts.json"));
+    let receipt = &receipts[0];
+
+    let mut trie = merkle_generator::PatriciaTrie::new();
+    // Receipts are long enough to be hashed, the rest of the trie is short enough to be embedded.
+    trie.insert(vec![0x80], alloy_rlp::encode(receipt));
+    trie.insert(vec![0x84, 0x00], alloy_rlp::encode(receipt));
+    // Embedded leaf next to the receipts.
+    trie.insert(vec![0x81], vec![0x01]);
+    // Embedded extension leading to a branch of embedded leaves.
+    trie.insert(vec![0x83, 0x10, 0x00], vec![0x02]);
+    trie.insert(vec![0x83, 0x10, 0x01], vec![0x03]);
+    // Value of the branch on the path to the second receipt.
+    trie.insert(vec![0x84], vec![0x04]);
+
+    let root = trie.root_hash();
+    for key in [vec![0x80], vec![0x84, 0x00]] {
+        let proof = trie.merkle_proof(key);
+        assert!(proof.proof.iter().any(|node| matches!(
+            node,
+            MerkleProofNode::BranchNode { branches, .. }
+                if branches.iter().any(|child| matches!(child, Some(NodeRef::Inline(_))))
+        )));
+        assert_eq!(proof.merkle_root(receipt), root);
+
+        let compact = CompactMerkleProof::from(&proof);
+        let encoded = alloy_rlp::encode(&compact);
+        let decoded = CompactMerkleProof::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded.merkle_root(receipt), Ok(root));
+    }
+}
@@ -0,0 +1,246 @@
+//! JSON-RPC of `pallet-receipt-registry`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use pallet_receipt_registry_runtime_api::ReceiptRegistryApi as ReceiptRegistryRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+use types::{DecodedLog, Log, H160, H256};
+use webb_proposals::TypedChainId;
+
+/// Maximum number of blocks `receiptRegistry_receiptsInRange` can scan.
+pub const MAX_BLOCK_RANGE: u64 = 1024;
+
+/// Receipt proven in the registry.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvedReceipt {
+    pub block_number: u64,
+    pub receipt_hash: H256,
+    pub logs: Vec<Log>,
+}
+
+/// Decoding of a log proven in the registry.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvedLog {
+    /// Id of the event ABI the log was decoded with, `None` for the log stored as is.
+    pub abi_id: Option<H256>,
+    pub decoded_log: DecodedLog,
+}
+
+#[rpc(client, server)]
+pub trait ReceiptRegistryApi<BlockHash> {
+    /// Whether the receipt has been proven.
+    #[method(name = "receiptRegistry_isProved")]
+    fn is_proved(
+        &self,
+        typed_chain_id: TypedChainId,
+        receipt_hash: H256,
+        at: Option<BlockHash>,
+    ) -> RpcResult<bool>;
+
+    /// Logs of a proven receipt.
+    #[method(name = "receiptRegistry_logs")]
+    fn logs(
+        &self,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<Vec<Log>>>;
+
+    /// Decodings of a log proven with `submit_log_proof`.
+    #[method(name = "receiptRegistry_decodedLogs")]
+    fn decoded_logs(
+        &self,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        log_index: u32,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<ProvedLog>>;
+
+    /// Contracts watched on the chain.
+    #[method(name = "receiptRegistry_watchedContracts")]
+    fn watched_contracts(
+        &self,
+        typed_chain_id: TypedChainId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<H160>>;
+
+    /// Receipts proven in the blocks from `from` to `to` inclusive, with their logs filtered by
+    /// the emitting contract and the topic if set.
+    #[method(name = "receiptRegistry_receiptsInRange")]
+    fn receipts_in_range(
+        &self,
+        typed_chain_id: TypedChainId,
+        from: u64,
+        to: u64,
+        address: Option<H160>,
+        topic: Option<H256>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<ProvedReceipt>>;
+}
+
+/// Error codes of the RPC.
+pub enum Error {
+    /// The runtime API call failed.
+    RuntimeError,
+    /// The requested block range is empty or larger than [`MAX_BLOCK_RANGE`].
+    InvalidRange,
+    /// The requested block range holds too many receipts.
+    TooManyReceipts,
+}
+
+impl From<Error> for i32 {
+    fn from(e: Error) -> i32 {
+        match e {
+            Error::RuntimeError => 1,
+            Error::InvalidRange => 2,
+            Error::TooManyReceipts => 3,
+        }
+    }
+}
+
+/// Implementation of the receipt registry RPC.
+pub struct ReceiptRegistry<C, Block> {
+    client: Arc<C>,
+    _marker: PhantomData<Block>,
+}
+
+impl<C, Block> ReceiptRegistry<C, Block> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+fn runtime_error(err: impl ToString) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        Error::RuntimeError.into(),
+        "Unable to query the receipt registry.",
+        Some(err.to_string()),
+    ))
+    .into()
+}
+
+impl<C, Block> ReceiptRegistryApiServer<<Block as BlockT>::Hash> for ReceiptRegistry<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: ReceiptRegistryRuntimeApi<Block>,
+{
+    fn is_proved(
+        &self,
+        typed_chain_id: TypedChainId,
+        receipt_hash: H256,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<bool> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .is_proved(at, typed_chain_id, receipt_hash)
+            .map_err(runtime_error)
+    }
+
+    fn logs(
+        &self,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<Vec<Log>>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .logs(at, typed_chain_id, block_number, receipt_hash)
+            .map_err(runtime_error)
+    }
+
+    fn decoded_logs(
+        &self,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        log_index: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<ProvedLog>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let logs = self
+            .client
+            .runtime_api()
+            .decoded_logs(at, typed_chain_id, block_number, receipt_hash, log_index)
+            .map_err(runtime_error)?;
+        Ok(logs
+            .into_iter()
+            .map(|(abi_id, decoded_log)| ProvedLog {
+                abi_id,
+                decoded_log,
+            })
+            .collect())
+    }
+
+    fn watched_contracts(
+        &self,
+        typed_chain_id: TypedChainId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<H160>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .watched_contracts(at, typed_chain_id)
+            .map_err(runtime_error)
+    }
+
+    fn receipts_in_range(
+        &self,
+        typed_chain_id: TypedChainId,
+        from: u64,
+        to: u64,
+        address: Option<H160>,
+        topic: Option<H256>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<ProvedReceipt>> {
+        if from > to || to - from >= MAX_BLOCK_RANGE {
+            return Err(CallError::Custom(ErrorObject::owned(
+                Error::InvalidRange.into(),
+                "Invalid block range.",
+                Some(format!(
+                    "the range has to be non-empty and at most {MAX_BLOCK_RANGE} blocks long"
+                )),
+            ))
+            .into());
+        }
+
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let receipts = self
+            .client
+            .runtime_api()
+            .receipts_in_range(at, typed_chain_id, from, to, address, topic)
+            .map_err(runtime_error)?
+            .map_err(|err| {
+                CallError::Custom(ErrorObject::owned(
+                    Error::TooManyReceipts.into(),
+                    "Too many receipts in the block range.",
+                    Some(format!("{err:?}, query a smaller range")),
+                ))
+            })?;
+        Ok(receipts
+            .into_iter()
+            .map(|(block_number, receipt_hash, logs)| ProvedReceipt {
+                block_number,
+                receipt_hash,
+                logs,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,83 @@
+//! Runtime API of `pallet-receipt-registry`.
+//!
+//! The runtime implements it with the pallet helpers:
+//!
+//! ```ignore
+//! impl pallet_receipt_registry_runtime_api::ReceiptRegistryApi<Block> for Runtime {
+//!     fn is_proved(typed_chain_id: TypedChainId, receipt_hash: H256) -> bool {
+//!         ReceiptRegistry::processed_receipts_hash(typed_chain_id, receipt_hash).is_some()
+//!     }
+//!
+//!     fn logs(typed_chain_id: TypedChainId, block_number: u64, receipt_hash: H256) -> Option<Vec<Log>> {
+//!         ReceiptRegistry::processed_receipts((typed_chain_id, block_number, receipt_hash))
+//!     }
+//!
+//!     fn decoded_logs(
+//!         typed_chain_id: TypedChainId,
+//!         block_number: u64,
+//!         receipt_hash: H256,
+//!         log_index: u32,
+//!     ) -> Vec<(Option<H256>, DecodedLog)> {
+//!         ReceiptRegistry::decoded_logs(typed_chain_id, block_number, receipt_hash, log_index)
+//!     }
+//!
+//!     fn watched_contracts(typed_chain_id: TypedChainId) -> Vec<H160> {
+//!         ReceiptRegistry::watched_contracts(typed_chain_id).map(Into::into).unwrap_or_default()
+//!     }
+//!
+//!     fn receipts_in_range(
+//!         typed_chain_id: TypedChainId,
+//!         from: u64,
+//!         to: u64,
+//!         address: Option<H160>,
+//!         topic: Option<H256>,
+//!     ) -> Result<Vec<(u64, H256, Vec<Log>)>, DispatchError> {
+//!         ReceiptRegistry::receipts_in_range(typed_chain_id, from, to, address, topic)
+//!             .map_err(Into::into)
+//!     }
+//! }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use sp_runtime::DispatchError;
+use types::{DecodedLog, Log, H160, H256};
+use webb_proposals::TypedChainId;
+
+sp_api::decl_runtime_apis! {
+    pub trait ReceiptRegistryApi {
+        /// Whether the receipt has been proven.
+        fn is_proved(typed_chain_id: TypedChainId, receipt_hash: H256) -> bool;
+
+        /// Logs of a proven receipt.
+        fn logs(typed_chain_id: TypedChainId, block_number: u64, receipt_hash: H256) -> Option<Vec<Log>>;
+
+        /// Decodings of a log proven with `submit_log_proof`, with the id of the event ABI each
+        /// one was decoded with, or `None` for the log stored as is.
+        fn decoded_logs(
+            typed_chain_id: TypedChainId,
+            block_number: u64,
+            receipt_hash: H256,
+            log_index: u32,
+        ) -> Vec<(Option<H256>, DecodedLog)>;
+
+        /// Contracts watched on the chain.
+        fn watched_contracts(typed_chain_id: TypedChainId) -> Vec<H160>;
+
+        /// Receipts proven in the blocks from `from` to `to` inclusive, as `(block number,
+        /// receipt hash, logs)`. Only the logs emitted by `address` and having `topic` are
+        /// returned if set, and receipts without such logs are skipped.
+        ///
+        /// Fails if the range spans more than 1024 blocks or holds more than 1024 receipts.
+        fn receipts_in_range(
+            typed_chain_id: TypedChainId,
+            from: u64,
+            to: u64,
+            address: Option<H160>,
+            topic: Option<H256>,
+        ) -> Result<Vec<(u64, H256, Vec<Log>)>, DispatchError>;
+    }
+}
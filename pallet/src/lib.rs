@@ -2,20 +2,236 @@
 #![feature(slice_pattern)]
 
 use frame_support::sp_std::{convert::TryInto, prelude::*};
-use frame_support::traits::ExistenceRequirement::AllowDeath;
-use frame_support::{pallet_prelude::ensure, traits::Get, PalletId};
+use frame_support::traits::ExistenceRequirement::{AllowDeath, KeepAlive};
+use frame_support::{
+    dispatch::DispatchResult,
+    pallet_prelude::{ensure, Decode, Encode, TypeInfo},
+    sp_runtime::{DispatchError, TransactionOutcome},
+    storage::with_transaction,
+    traits::{BalanceStatus, Get, ReservableCurrency},
+    weights::{Weight, WeightToFee},
+    PalletId,
+};
 pub use pallet::*;
-use types::{EventProof, TransactionReceipt};
+use types::{AncestryProof, DecodedLog, EventAbi, EventProof, Log, LogProof, TransactionReceipt};
 use types::{H160, H256};
 use webb_proposals::TypedChainId;
 
-use frame_support::{sp_runtime::traits::AccountIdConversion, traits::Currency};
+use frame_support::{
+    sp_runtime::traits::{AccountIdConversion, Saturating, Zero},
+    traits::Currency,
+};
 
-type BalanceOf<T> =
+pub type BalanceOf<T> =
     <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 type CurrencyOf<T> = <T as Config>::Currency;
 
+/// Kinds of `TypedChainId` wrapping a `u32` chain id, see [`Pallet::registered_chain`].
+pub const CHAIN_KINDS: [fn(u32) -> TypedChainId; 8] = [
+    TypedChainId::Evm,
+    TypedChainId::Substrate,
+    TypedChainId::PolkadotParachain,
+    TypedChainId::KusamaParachain,
+    TypedChainId::RococoParachain,
+    TypedChainId::Cosmos,
+    TypedChainId::Solana,
+    TypedChainId::Ink,
+];
+
+/// Maximum number of blocks a range query of the proven receipts can span.
+pub const MAX_BLOCK_RANGE: u64 = 1024;
+
+/// Maximum number of receipts a range query of the proven receipts can return.
+pub const MAX_RANGE_RECEIPTS: usize = 1024;
+
+/// Weight charged per byte of a submitted log proof, for decoding and verifying it and for storing
+/// the log it pins, which is part of the proof.
+pub const LOG_PROOF_BYTE_WEIGHT: Weight = Weight::from_parts(1_000, 1);
+
+/// Bond and statistics of a registered relayer.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct RelayerInfo<Balance> {
+    /// Amount reserved from the relayer account, slashed on rejected submissions.
+    pub bond: Balance,
+    /// Number of submissions that stored a new receipt or log.
+    pub proofs_accepted: u32,
+    /// Number of submissions that have been slashed.
+    pub proofs_rejected: u32,
+}
+
+pub mod migrations;
+
+/// Handler of receipts proven through `submit_proof` or `submit_ancestry_proof`, for pallets
+/// that react to events of the watched contracts.
+pub trait OnReceiptProved {
+    /// Called after a receipt has been stored, with the logs of the receipt that are emitted by
+    /// watched contracts. Returns the consumed weight, which must not exceed
+    /// `Config::MaxOnReceiptProvedWeight`.
+    fn on_receipt_proved(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        logs: &[Log],
+    ) -> Weight;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl OnReceiptProved for Tuple {
+    fn on_receipt_proved(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        logs: &[Log],
+    ) -> Weight {
+        let mut weight = Weight::zero();
+        for_tuples!( #(
+            weight.saturating_accrue(Tuple::on_receipt_proved(
+                typed_chain_id,
+                block_number,
+                receipt_hash,
+                logs,
+            ));
+        )* );
+        weight
+    }
+}
+
+/// Contract called with the proven logs of a watched contract.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum CallbackTarget<AccountId> {
+    /// Message of a `pallet-contracts` contract.
+    Ink {
+        contract: AccountId,
+        selector: [u8; 4],
+    },
+    /// Function of a `pallet-evm` contract.
+    Evm { contract: H160, selector: [u8; 4] },
+}
+
+/// Callback registered for the logs of a watched contract having a topic.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct Callback<AccountId, Balance> {
+    /// Account that registered the callback, the only one allowed to change it.
+    pub owner: AccountId,
+    /// Contract to call.
+    pub target: CallbackTarget<AccountId>,
+    /// Maximum weight of a call.
+    pub weight_limit: Weight,
+    /// Funds reserved from the owner to pay for the weight of the calls.
+    pub budget: Balance,
+}
+
+/// Executor of the callbacks, implemented for the contract pallets of the runtime.
+pub trait CallbackDispatcher<AccountId> {
+    /// Calls `target` with a proven log for the callback registered by `owner`, using at most
+    /// `weight_limit`.
+    ///
+    /// Returns `None` if the kind of target isn't supported, and the consumed weight with the
+    /// result of the call otherwise.
+    fn dispatch(
+        owner: &AccountId,
+        target: &CallbackTarget<AccountId>,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        log: &Log,
+        weight_limit: Weight,
+    ) -> Option<(Weight, DispatchResult)>;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl<AccountId> CallbackDispatcher<AccountId> for Tuple {
+    fn dispatch(
+        owner: &AccountId,
+        target: &CallbackTarget<AccountId>,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        log: &Log,
+        weight_limit: Weight,
+    ) -> Option<(Weight, DispatchResult)> {
+        for_tuples!( #(
+            if let Some(result) = Tuple::dispatch(
+                owner,
+                target,
+                typed_chain_id,
+                block_number,
+                receipt_hash,
+                log,
+                weight_limit,
+            ) {
+                return Some(result);
+            }
+        )* );
+        None
+    }
+}
+
+/// Settings of a chain registered in the pallet.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainSettings<Balance> {
+    /// Whether the chain accepts proofs and new watched contracts.
+    pub enabled: bool,
+    /// Set in emergencies to reject proofs while keeping the rest of the chain configuration.
+    pub paused: bool,
+    /// Amount slashed from the relayer bond for a proof that doesn't store anything new.
+    pub proof_deposit: Balance,
+    /// Reward per proof new reward pools of the chain start with.
+    pub default_reward: Balance,
+    /// Number of the latest finalized blocks whose receipts are kept, `None` to keep them
+    /// forever. Older receipts are garbage collected and can't be proven anymore.
+    pub retention: Option<u64>,
+    /// Number of blocks the light client has to be ahead of a proven block.
+    pub min_confirmations: u64,
+    /// Number of blocks of this chain a proven receipt stays pending, and can be disputed,
+    /// before it is stored. Receipts are stored right away if zero.
+    pub challenge_period: u32,
+}
+
+impl<Balance: Default> Default for ChainSettings<Balance> {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            paused: false,
+            proof_deposit: Default::default(),
+            default_reward: Default::default(),
+            retention: None,
+            min_confirmations: 0,
+            challenge_period: 0,
+        }
+    }
+}
+
+/// Receipt proven on a chain with a challenge period, waiting for the period to end.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PendingReceipt<AccountId, BlockNumber> {
+    /// Relayer rewarded once the receipt is stored, or slashed if it is disputed.
+    pub relayer: AccountId,
+    pub block_number: u64,
+    /// Number and hash of the finalized block the proof has been checked against, the block of
+    /// the receipt or the last descendant of an ancestry proof. The receipt can be disputed once
+    /// the light client finalizes another block at this number.
+    pub anchor: (u64, H256),
+    pub logs: Vec<Log>,
+    /// Watched contracts that emitted logs of the receipt.
+    pub contracts: Vec<H160>,
+    /// Block at which the receipt is stored if it hasn't been disputed.
+    pub challenge_end: BlockNumber,
+}
+
+/// Rewards funded for the receipts of a watched contract.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct RewardPool<AccountId, Balance> {
+    /// Account that funded the pool first, the only one allowed to configure it.
+    pub owner: AccountId,
+    /// Funds left in the pool, held by the pallet account.
+    pub balance: Balance,
+    /// Reward paid for each proven receipt or log of the contract.
+    pub reward: Balance,
+}
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
@@ -26,9 +242,12 @@ pub mod pallet {
         Blake2_128Concat,
     };
     use frame_system::pallet_prelude::*;
-    use types::Log;
+
+    /// The current storage version.
+    pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(3);
 
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -41,9 +260,64 @@ pub mod pallet {
         #[pallet::constant]
         type PalletId: Get<PalletId>;
 
-        type Currency: Currency<<Self as frame_system::Config>::AccountId>;
+        type Currency: ReservableCurrency<<Self as frame_system::Config>::AccountId>;
 
         type PrivilegedOrigin: EnsureOrigin<<Self as frame_system::Config>::RuntimeOrigin>;
+
+        /// Maximum number of watched contracts per chain.
+        #[pallet::constant]
+        type MaxWatchedContracts: Get<u32>;
+
+        /// Maximum number of headers linking a proven block to a finalized one.
+        #[pallet::constant]
+        type MaxAncestryLength: Get<u32>;
+
+        /// Minimum bond a relayer has to keep reserved to submit proofs.
+        #[pallet::constant]
+        type MinRelayerBond: Get<BalanceOf<Self>>;
+
+        /// Number of rewards under which a reward pool is reported as running low.
+        #[pallet::constant]
+        type RewardPoolLowThreshold: Get<u32>;
+
+        /// Handler of newly proven receipts.
+        type OnReceiptProved: OnReceiptProved;
+
+        /// Maximum weight of `OnReceiptProved` for a single receipt, included in the weight of
+        /// the calls that store receipts.
+        #[pallet::constant]
+        type MaxOnReceiptProvedWeight: Get<Weight>;
+
+        /// Executor of the callbacks registered for the logs of watched contracts.
+        type CallbackDispatcher: CallbackDispatcher<<Self as frame_system::Config>::AccountId>;
+
+        /// Converts the weight consumed by a callback to the fee taken from its budget.
+        type WeightToFee: WeightToFee<Balance = BalanceOf<Self>>;
+
+        /// Maximum weight of a callback call.
+        #[pallet::constant]
+        type MaxCallbackWeight: Get<Weight>;
+
+        /// Maximum number of callbacks called for a single receipt, the following ones are
+        /// skipped. Together with `MaxCallbackWeight`, bounds the weight of storing a receipt.
+        #[pallet::constant]
+        type MaxCallbacksPerReceipt: Get<u32>;
+
+        /// Number of blocks without new finalized headers in the light client after which a
+        /// chain is paused, zero to never pause chains automatically.
+        #[pallet::constant]
+        type MaxFinalityStall: Get<u32>;
+
+        /// Maximum number of chains whose finality progress is checked in a block, taking turns
+        /// when more chains are registered. Stalls are detected later for these chains.
+        #[pallet::constant]
+        type MaxFinalityChecksPerBlock: Get<u32>;
+
+        /// Maximum number of pending receipts stored in a block once their challenge period
+        /// ends, counting the empty blocks of the challenge queue read. The following ones are
+        /// stored in the next blocks.
+        #[pallet::constant]
+        type MaxPendingReceiptsPerBlock: Get<u32>;
     }
 
     /// ProcessedReceipts
@@ -80,26 +354,196 @@ pub mod pallet {
         OptionQuery,
     >;
 
+    /// Receipts proven through `submit_proof` or `submit_ancestry_proof`, indexed by the watched
+    /// contracts that emitted their logs.
+    ///
+    /// TypedChainId -> ContractAddress -> BlockNumber -> [TransactionReceiptHash]
+    #[pallet::storage]
+    #[pallet::getter(fn contract_receipts)]
+    pub(crate) type ContractReceipts<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>, // ChainList Id https://chainlist.org/
+            NMapKey<Blake2_128Concat, H160>,         // Address of the watched contract
+            NMapKey<Blake2_128Concat, u64>,          // Block height
+        ),
+        Vec<H256>,
+        ValueQuery,
+    >;
+
     /// the contract addresses we're watching
     #[pallet::storage]
     #[pallet::getter(fn watched_contracts)]
-    pub(crate) type WatchedContracts<T: Config> =
-        StorageMap<_, Blake2_128Concat, TypedChainId, BoundedVec<H160, ConstU32<100>>, OptionQuery>;
+    pub(crate) type WatchedContracts<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        TypedChainId,
+        BoundedVec<H160, T::MaxWatchedContracts>,
+        OptionQuery,
+    >;
+
+    /// chains the pallet accepts proofs for, with their settings
+    #[pallet::storage]
+    #[pallet::getter(fn registered_chains)]
+    pub(crate) type RegisteredChains<T: Config> =
+        StorageMap<_, Blake2_128Concat, TypedChainId, ChainSettings<BalanceOf<T>>, OptionQuery>;
+
+    /// Receipts proven on chains with a challenge period that aren't stored yet.
+    ///
+    /// TypedChainId -> TransactionReceiptHash -> PendingReceipt
+    #[pallet::storage]
+    #[pallet::getter(fn pending_receipts)]
+    pub(crate) type PendingReceipts<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        TypedChainId,
+        Blake2_128Concat,
+        H256,
+        PendingReceipt<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>,
+        OptionQuery,
+    >;
+
+    /// Pending receipts by the block their challenge period ends at.
+    #[pallet::storage]
+    pub(crate) type ChallengeQueue<T: Config> =
+        StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<(TypedChainId, H256)>, ValueQuery>;
+
+    /// First block of the challenge queue with receipts left to store, when more receipts than
+    /// `MaxPendingReceiptsPerBlock` were due.
+    #[pallet::storage]
+    #[pallet::getter(fn challenge_queue_cursor)]
+    pub(crate) type ChallengeQueueCursor<T: Config> =
+        StorageValue<_, BlockNumberFor<T>, OptionQuery>;
+
+    /// Number of pending receipts proven by each relayer.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_proofs)]
+    pub(crate) type PendingProofs<T: Config> =
+        StorageMap<_, Blake2_128Concat, <T as frame_system::Config>::AccountId, u32, ValueQuery>;
+
+    /// Set in emergencies to reject proofs of every chain.
+    #[pallet::storage]
+    #[pallet::getter(fn paused)]
+    pub(crate) type Paused<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    /// Latest finalized block of the light client of each chain, and the block of this chain it
+    /// has been seen first at.
+    #[pallet::storage]
+    #[pallet::getter(fn finality_progress)]
+    pub(crate) type FinalityProgress<T: Config> =
+        StorageMap<_, Blake2_128Concat, TypedChainId, (u64, BlockNumberFor<T>), OptionQuery>;
+
+    /// Last chain whose finality progress has been checked, the next check starts after it.
+    #[pallet::storage]
+    #[pallet::getter(fn finality_check_cursor)]
+    pub(crate) type FinalityCheckCursor<T: Config> = StorageValue<_, TypedChainId, OptionQuery>;
+
+    /// Lowest block that may still have stored receipts or logs, for chains with a retention.
+    #[pallet::storage]
+    #[pallet::getter(fn prune_cursor)]
+    pub(crate) type PruneCursor<T: Config> =
+        StorageMap<_, Blake2_128Concat, TypedChainId, u64, OptionQuery>;
+
+    /// Chain the pruning stopped at for lack of weight, the next pruning starts with it.
+    #[pallet::storage]
+    #[pallet::getter(fn prune_chain_cursor)]
+    pub(crate) type PruneChainCursor<T: Config> = StorageValue<_, TypedChainId, OptionQuery>;
+
+    /// reward pools funded for the watched contracts
+    #[pallet::storage]
+    #[pallet::getter(fn reward_pools)]
+    pub(crate) type RewardPools<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        TypedChainId,
+        Blake2_128Concat,
+        H160,
+        RewardPool<<T as frame_system::Config>::AccountId, BalanceOf<T>>,
+        OptionQuery,
+    >;
+
+    /// Logs proven one by one through `submit_log_proof`, together with their decoded parameters.
+    ///
+    /// A log is stored once per event ABI it was decoded with, so a decoding with a wrong ABI
+    /// can't take the place of the right one.
+    ///
+    /// TypedChainId -> BlockNumber -> TransactionReceiptHash -> LogIndex -> AbiId -> DecodedLog
+    #[pallet::storage]
+    #[pallet::getter(fn processed_logs)]
+    pub(crate) type ProcessedLogs<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>, // ChainList Id https://chainlist.org/
+            NMapKey<Blake2_128Concat, u64>,          // Block height
+            NMapKey<Blake2_128Concat, H256>,         // Hash of the receipt containing the log
+            NMapKey<Blake2_128Concat, u32>,          // Index of the log in the receipt
+            NMapKey<Blake2_128Concat, Option<H256>>, // `EventAbi::id`, if the log was decoded
+        ),
+        DecodedLog,
+        OptionQuery,
+    >;
+
+    /// Registered relayers with their bonds and statistics.
+    #[pallet::storage]
+    #[pallet::getter(fn relayers)]
+    pub(crate) type Relayers<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        <T as frame_system::Config>::AccountId,
+        RelayerInfo<BalanceOf<T>>,
+        OptionQuery,
+    >;
 
-    /// pay validator proof deposit
+    /// Rewards accrued by relayers and not claimed yet.
     #[pallet::storage]
-    #[pallet::getter(fn proof_deposit)]
-    pub(crate) type ProofDeposit<T: Config> =
-        StorageMap<_, Blake2_128Concat, TypedChainId, BalanceOf<T>, ValueQuery>;
+    #[pallet::getter(fn pending_rewards)]
+    pub(crate) type PendingRewards<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        <T as frame_system::Config>::AccountId,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
 
-    /// reward for Proof of Submission
+    /// Callbacks called with the proven logs of watched contracts, by topic of the log.
+    ///
+    /// TypedChainId -> ContractAddress -> Topic -> Callback
     #[pallet::storage]
-    #[pallet::getter(fn proof_reward)]
-    pub(crate) type ProofReward<T: Config> =
-        StorageMap<_, Blake2_128Concat, TypedChainId, BalanceOf<T>, ValueQuery>;
+    #[pallet::getter(fn callbacks)]
+    pub(crate) type Callbacks<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>, // ChainList Id https://chainlist.org/
+            NMapKey<Blake2_128Concat, H160>,         // Address of the watched contract
+            NMapKey<Blake2_128Concat, H256>,         // Topic of the log
+        ),
+        Callback<<T as frame_system::Config>::AccountId, BalanceOf<T>>,
+        OptionQuery,
+    >;
 
     /************* STORAGE ************ */
 
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        pub chains: Vec<(TypedChainId, ChainSettings<BalanceOf<T>>)>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self { chains: vec![] }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            for (typed_chain_id, settings) in &self.chains {
+                RegisteredChains::<T>::insert(typed_chain_id, settings);
+            }
+        }
+    }
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -121,6 +565,126 @@ pub mod pallet {
             proof_deposit: BalanceOf<T>,
             proof_reward: BalanceOf<T>,
         },
+        ChainRegistered {
+            typed_chain_id: TypedChainId,
+        },
+        ChainSettingsUpdated {
+            typed_chain_id: TypedChainId,
+        },
+        ChainDeregistered {
+            typed_chain_id: TypedChainId,
+        },
+        /// Proofs of the chain, or of every chain if `None`, are rejected.
+        Paused {
+            typed_chain_id: Option<TypedChainId>,
+        },
+        Unpaused {
+            typed_chain_id: Option<TypedChainId>,
+        },
+        /// The light client of the chain hasn't finalized a block for `MaxFinalityStall` blocks,
+        /// so the chain has been paused.
+        FinalityStalled {
+            typed_chain_id: TypedChainId,
+            head: u64,
+        },
+        SubmitProcessedLog {
+            typed_chain_id: TypedChainId,
+            block_number: u64,
+            receipt_hash: H256,
+            log_index: u32,
+            abi_id: Option<H256>,
+        },
+        RelayerRegistered {
+            relayer: <T as frame_system::Config>::AccountId,
+            bond: BalanceOf<T>,
+        },
+        RelayerUnregistered {
+            relayer: <T as frame_system::Config>::AccountId,
+            bond: BalanceOf<T>,
+        },
+        RewardAccrued {
+            relayer: <T as frame_system::Config>::AccountId,
+            amount: BalanceOf<T>,
+        },
+        RelayerSlashed {
+            relayer: <T as frame_system::Config>::AccountId,
+            amount: BalanceOf<T>,
+        },
+        RewardsClaimed {
+            relayer: <T as frame_system::Config>::AccountId,
+            amount: BalanceOf<T>,
+        },
+        RewardPoolFunded {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            amount: BalanceOf<T>,
+            balance: BalanceOf<T>,
+        },
+        RewardPoolWithdrawn {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            amount: BalanceOf<T>,
+            balance: BalanceOf<T>,
+        },
+        UpdateReceiptReward {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            reward: BalanceOf<T>,
+        },
+        /// The pool can only pay a few more rewards.
+        RewardPoolLow {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            balance: BalanceOf<T>,
+        },
+        /// A proof hasn't been rewarded because the pool can't pay for it.
+        RewardPoolEmpty {
+            typed_chain_id: TypedChainId,
+            address: H160,
+        },
+        CallbackRegistered {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            topic: H256,
+            owner: <T as frame_system::Config>::AccountId,
+            budget: BalanceOf<T>,
+        },
+        CallbackRemoved {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            topic: H256,
+        },
+        /// A callback has been called, its changes are reverted if it failed.
+        CallbackExecuted {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            topic: H256,
+            success: bool,
+            weight: Weight,
+            fee: BalanceOf<T>,
+        },
+        /// A callback hasn't been called because its budget can't pay for its weight limit, or
+        /// because the receipt already reached `MaxCallbacksPerReceipt` calls.
+        CallbackSkipped {
+            typed_chain_id: TypedChainId,
+            address: H160,
+            topic: H256,
+        },
+        /// A receipt has been proven on a chain with a challenge period, and is stored at
+        /// `challenge_end` unless it is disputed.
+        ReceiptPending {
+            typed_chain_id: TypedChainId,
+            block_number: u64,
+            receipt_hash: H256,
+            challenge_end: BlockNumberFor<T>,
+        },
+        /// A pending receipt has been dropped, the relayer that proved it is slashed.
+        ReceiptDisputed {
+            typed_chain_id: TypedChainId,
+            receipt_hash: H256,
+            relayer: <T as frame_system::Config>::AccountId,
+            disputer: Option<<T as frame_system::Config>::AccountId>,
+        },
     }
 
     #[pallet::error]
@@ -135,22 +699,94 @@ pub mod pallet {
         NoMonitoredAddressesForChain,
         /// Too many watched contracts
         TooManyAddresses,
+        /// The log index is out of the receipt logs range
+        LogIndexOutOfBounds,
+        /// The log doesn't match the provided event ABI
+        LogDecodeFail,
+        /// The log is not emitted by a watched contract
+        LogNotFromWatchedContract,
+        /// The ancestry proof has more headers than allowed
+        AncestryTooLong,
+        /// The account is not a registered relayer
+        NotRegisteredRelayer,
+        /// The relayer bond is below `MinRelayerBond`
+        BondTooLow,
+        /// There are no rewards to claim
+        NoRewards,
+        /// The contract is not watched
+        ContractNotWatched,
+        /// There is no reward pool for the contract
+        NoRewardPool,
+        /// The caller doesn't own the reward pool
+        NotRewardPoolOwner,
+        /// The reward pool balance is lower than the requested amount
+        InsufficientPoolBalance,
+        /// The chain is not registered
+        ChainNotRegistered,
+        /// The chain is already registered
+        ChainAlreadyRegistered,
+        /// A chain of another kind is registered with the same chain id
+        ChainIdAlreadyRegistered,
+        /// The chain is disabled
+        ChainDisabled,
+        /// Proofs for the chain are paused
+        ChainPaused,
+        /// Proofs for every chain are paused
+        RegistryPaused,
+        /// The block doesn't have enough confirmations yet
+        NotEnoughConfirmations,
+        /// The block is older than the retention of the chain
+        BlockOutsideRetention,
+        /// There is no callback for the topic
+        NoCallback,
+        /// The caller doesn't own the callback
+        NotCallbackOwner,
+        /// The weight limit of the callback is above `MaxCallbackWeight`
+        CallbackWeightTooHigh,
+        /// The runtime can't call the callback target
+        UnsupportedCallbackTarget,
+        /// There is no pending receipt with the hash
+        NoPendingReceipt,
+        /// The light client doesn't finalize another block in place of the anchor of the receipt
+        ReceiptNotDisputable,
+        /// The relayer has proven receipts that are still pending
+        RelayerHasPendingReceipts,
+        /// Single logs can't be proven on chains with a challenge period
+        ChainHasChallengePeriod,
+        /// The block range spans more than `MAX_BLOCK_RANGE` blocks
+        RangeTooLarge,
+        /// The block range holds more than `MAX_RANGE_RECEIPTS` receipts
+        TooManyReceipts,
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+            Self::check_finality_progress(n).saturating_add(Self::store_pending_receipts(n))
+        }
+
+        fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            let used = crate::migrations::v2::index_receipts::<T>(remaining_weight);
+            used.saturating_add(Self::prune_processed_receipts(
+                remaining_weight.saturating_sub(used),
+            ))
+        }
+    }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         /// submitting proof that a receipt has been included in a block
-        #[pallet::weight({6})]
+        #[pallet::weight(
+            Weight::from_parts(6, 0).saturating_add(Pallet::<T>::max_store_weight())
+        )]
         #[pallet::call_index(6)]
         pub fn submit_proof(
             origin: OriginFor<T>,
             typed_chain_id: TypedChainId,
             event_proof: Vec<u8>,
         ) -> DispatchResultWithPostInfo {
-            let validator = ensure_signed(origin)?;
+            let relayer = ensure_signed(origin)?;
+            Self::ensure_relayer(&relayer)?;
 
             // Create a str slice from the body.
             let event_proof_str = frame_support::sp_std::str::from_utf8(&event_proof)
@@ -159,112 +795,194 @@ pub mod pallet {
             let event_proof: EventProof =
                 serde_json::from_str(event_proof_str).map_err(|_| Error::<T>::DeserializeFail)?;
 
-            let finalized_execution_header_hash =
-                pallet_eth2_light_client::Pallet::<T>::finalized_execution_blocks(
-                    typed_chain_id,
-                    event_proof.block_header.number,
-                )
-                .ok_or(Error::<T>::HeaderHashDoesNotExist)?;
-
-            let block_hash = event_proof.block_hash;
-
-            ensure!(
-                block_hash.0 == finalized_execution_header_hash.0 .0,
-                Error::<T>::BlockHashesDoNotMatch,
-            );
+            Self::ensure_finalized(
+                typed_chain_id,
+                event_proof.block_header.number,
+                event_proof.block_hash,
+            )?;
+            Self::ensure_chain_accepts(typed_chain_id, event_proof.block_header.number)?;
 
             // 1 verifying its cryptographic integrity
             ensure!(event_proof.validate().is_ok(), Error::<T>::VerifyProofFail);
 
-            let treasury = Self::account_id();
-            let transaction_receipt_hash: H256 = event_proof.transaction_receipt_hash;
+            let anchor = (event_proof.block_header.number, event_proof.block_hash);
+            let store_weight =
+                Self::process_receipt(typed_chain_id, &relayer, &event_proof, anchor)?;
+
+            Ok(Some(Weight::from_parts(6, 0).saturating_add(store_weight)).into())
+        }
+
+        /// submitting proof that a single log has been emitted in a block.
+        ///
+        /// Unlike `submit_proof`, only the log pinned by the proof is stored, decoded with the
+        /// event ABI if the proof provides one.
+        #[pallet::weight(Pallet::<T>::submit_log_proof_weight(log_proof.len()))]
+        #[pallet::call_index(9)]
+        pub fn submit_log_proof(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            log_proof: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let relayer = ensure_signed(origin)?;
+            Self::ensure_relayer(&relayer)?;
+
+            let log_proof_str = frame_support::sp_std::str::from_utf8(&log_proof)
+                .map_err(|_| Error::<T>::ConvertToStringFailed)?;
+
+            let log_proof: LogProof =
+                serde_json::from_str(log_proof_str).map_err(|_| Error::<T>::DeserializeFail)?;
 
-            // If the receipt proof has already been processed
-            let rewarded = if !<ProcessedReceiptsHash<T>>::contains_key(
+            Self::ensure_finalized(
                 typed_chain_id,
-                transaction_receipt_hash,
-            ) {
-                //2 checking the receipt includes a LOG emitted by a contract address we are watching.
+                log_proof.event_proof.block_header.number,
+                log_proof.event_proof.block_hash,
+            )?;
+            Self::ensure_chain_accepts(typed_chain_id, log_proof.event_proof.block_header.number)?;
+            ensure!(
+                Self::registered_chains(typed_chain_id)
+                    .map_or(0, |settings| settings.challenge_period)
+                    == 0,
+                Error::<T>::ChainHasChallengePeriod
+            );
 
-                let block_number = event_proof.block_header.number;
-                let mut rewarded = false;
+            ensure!(
+                log_proof.event_proof.validate().is_ok(),
+                Error::<T>::VerifyProofFail
+            );
 
-                let addresses = Self::watched_contracts(typed_chain_id);
-                ensure!(
-                    addresses.is_some(),
-                    Error::<T>::NoMonitoredAddressesForChain
-                );
+            let log = log_proof.log().ok_or(Error::<T>::LogIndexOutOfBounds)?;
 
-                for address in addresses.expect("checked above") {
-                    if Self::is_contract_address_in_log(&event_proof.transaction_receipt, address) {
-                        ProcessedReceipts::<T>::insert(
-                            (typed_chain_id, block_number, transaction_receipt_hash),
-                            event_proof.transaction_receipt.receipt.logs.clone(),
-                        );
-                        ProcessedReceiptsHash::<T>::insert(
-                            typed_chain_id,
-                            transaction_receipt_hash,
-                            (),
-                        );
+            let addresses = Self::watched_contracts(typed_chain_id)
+                .ok_or(Error::<T>::NoMonitoredAddressesForChain)?;
+            ensure!(
+                addresses.contains(&log.address),
+                Error::<T>::LogNotFromWatchedContract
+            );
 
-                        Self::deposit_event(Event::SubmitProcessedReceipts {
-                            typed_chain_id,
-                            block_number,
-                            receipt_hash: transaction_receipt_hash,
-                        });
-                        rewarded = true;
-                    }
-                }
-                rewarded
-            } else {
-                false
-            };
+            let block_number = log_proof.event_proof.block_header.number;
+            let receipt_hash = log_proof.event_proof.transaction_receipt_hash;
+            let log_index = log_proof.log_index;
+            let abi_id = log_proof.abi.as_ref().map(EventAbi::id);
+            let key = (
+                typed_chain_id,
+                block_number,
+                receipt_hash,
+                log_index,
+                abi_id,
+            );
 
-            let _success = if rewarded {
-                // Rewarding relayer for submitting a proof of inclusion of a receipt
-                CurrencyOf::<T>::transfer(
-                    &treasury,
-                    &validator,
-                    Self::proof_reward(typed_chain_id),
-                    AllowDeath,
-                )
-            } else {
-                // Validator
-                CurrencyOf::<T>::transfer(
-                    &validator,
-                    &treasury,
-                    Self::proof_deposit(typed_chain_id),
-                    AllowDeath,
-                )
-            };
+            if ProcessedLogs::<T>::contains_key(key) {
+                Self::settle_proof_fee(typed_chain_id, &relayer, &[]);
+                return Ok(().into());
+            }
 
-            debug_assert!(_success.is_ok());
+            let decoded_log = log_proof.decode().map_err(|_| Error::<T>::LogDecodeFail)?;
+            // Only the first proof of the log is rewarded, decodings with other ABIs are
+            // stored alongside it without a reward.
+            let first_proof = ProcessedLogs::<T>::iter_key_prefix((
+                typed_chain_id,
+                block_number,
+                receipt_hash,
+                log_index,
+            ))
+            .next()
+            .is_none();
+            ProcessedLogs::<T>::insert(key, decoded_log);
+            Self::note_stored_block(typed_chain_id, block_number);
+
+            Self::deposit_event(Event::SubmitProcessedLog {
+                typed_chain_id,
+                block_number,
+                receipt_hash,
+                log_index,
+                abi_id,
+            });
+
+            if first_proof {
+                Self::settle_proof_fee(typed_chain_id, &relayer, &[log.address]);
+            }
 
             Ok(().into())
         }
 
-        /// update watching address
-        #[pallet::weight({7})]
-        #[pallet::call_index(7)]
-        pub fn update_watching_address(
+        /// submitting proof that a receipt has been included in a block that is an ancestor of
+        /// a finalized block.
+        ///
+        /// Used for blocks that are not stored by the light client, e.g. already garbage
+        /// collected ones.
+        #[pallet::weight(
+            Weight::from_parts(10, 0).saturating_add(Pallet::<T>::max_store_weight())
+        )]
+        #[pallet::call_index(10)]
+        pub fn submit_ancestry_proof(
             origin: OriginFor<T>,
             typed_chain_id: TypedChainId,
-            address: H160,
-            add: bool,
+            ancestry_proof: Vec<u8>,
         ) -> DispatchResultWithPostInfo {
-            T::PrivilegedOrigin::ensure_origin(origin)?;
+            let relayer = ensure_signed(origin)?;
+            Self::ensure_relayer(&relayer)?;
 
-            let result =
-                WatchedContracts::<T>::mutate(typed_chain_id, |addresses| match (addresses, add) {
-                    (Some(ref mut addresses), true) => addresses.try_push(address),
+            let ancestry_proof_str = frame_support::sp_std::str::from_utf8(&ancestry_proof)
+                .map_err(|_| Error::<T>::ConvertToStringFailed)?;
+
+            let ancestry_proof: AncestryProof = serde_json::from_str(ancestry_proof_str)
+                .map_err(|_| Error::<T>::DeserializeFail)?;
+
+            ensure!(
+                ancestry_proof.ancestry.len() <= T::MaxAncestryLength::get() as usize,
+                Error::<T>::AncestryTooLong
+            );
+
+            let (anchor_number, anchor_hash) = ancestry_proof.anchor();
+            Self::ensure_finalized(typed_chain_id, anchor_number, anchor_hash)?;
+            Self::ensure_chain_accepts(
+                typed_chain_id,
+                ancestry_proof.event_proof.block_header.number,
+            )?;
+
+            ensure!(
+                ancestry_proof.validate().is_ok(),
+                Error::<T>::VerifyProofFail
+            );
+
+            let store_weight = Self::process_receipt(
+                typed_chain_id,
+                &relayer,
+                &ancestry_proof.event_proof,
+                (anchor_number, anchor_hash),
+            )?;
+
+            Ok(Some(Weight::from_parts(10, 0).saturating_add(store_weight)).into())
+        }
+
+        /// update watching address
+        #[pallet::weight({7})]
+        #[pallet::call_index(7)]
+        pub fn update_watching_address(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            address: H160,
+            add: bool,
+        ) -> DispatchResultWithPostInfo {
+            T::PrivilegedOrigin::ensure_origin(origin)?;
+
+            if add {
+                let settings = Self::registered_chains(typed_chain_id)
+                    .ok_or(Error::<T>::ChainNotRegistered)?;
+                ensure!(settings.enabled, Error::<T>::ChainDisabled);
+            }
+
+            let result =
+                WatchedContracts::<T>::mutate(typed_chain_id, |addresses| match (addresses, add) {
+                    (Some(ref mut addresses), true) => addresses.try_push(address),
                     (Some(ref mut addresses), false) => {
                         addresses.retain(|&x| x != address);
                         Ok(())
                     }
                     (option, true) if option.is_none() => {
-                        *option = Some(
-                            BoundedVec::try_from(vec![address]).expect("unfailable conversion"),
-                        );
+                        let mut addresses = BoundedVec::default();
+                        addresses.try_push(address)?;
+                        *option = Some(addresses);
                         Ok(())
                     }
                     _ => Ok(()),
@@ -290,7 +1008,10 @@ pub mod pallet {
             Ok(().into())
         }
 
-        /// update ProofDeposit and ProofReward
+        /// update the proof deposit and the default proof reward of a registered chain
+        ///
+        /// Deprecated: rewards are paid from per-contract pools, configured with
+        /// `set_receipt_reward`. `proof_reward` only sets the reward new pools start with.
         #[pallet::weight({8})]
         #[pallet::call_index(8)]
         pub fn update_proof_fee(
@@ -301,8 +1022,12 @@ pub mod pallet {
         ) -> DispatchResultWithPostInfo {
             T::PrivilegedOrigin::ensure_origin(origin)?;
 
-            ProofDeposit::<T>::insert(typed_chain_id, proof_deposit);
-            ProofReward::<T>::insert(typed_chain_id, proof_reward);
+            RegisteredChains::<T>::try_mutate(typed_chain_id, |settings| {
+                let settings = settings.as_mut().ok_or(Error::<T>::ChainNotRegistered)?;
+                settings.proof_deposit = proof_deposit;
+                settings.default_reward = proof_reward;
+                Ok::<_, Error<T>>(())
+            })?;
 
             Self::deposit_event(Event::UpdateProofFee {
                 typed_chain_id,
@@ -312,6 +1037,425 @@ pub mod pallet {
 
             Ok(().into())
         }
+
+        /// register as a relayer, or top up the bond of an already registered one.
+        ///
+        /// The bond is reserved from the caller, and the total bond must be at least
+        /// `MinRelayerBond`.
+        #[pallet::weight({11})]
+        #[pallet::call_index(11)]
+        pub fn register_relayer(
+            origin: OriginFor<T>,
+            bond: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let relayer = ensure_signed(origin)?;
+
+            let mut info = Self::relayers(&relayer).unwrap_or_default();
+            info.bond = info.bond.saturating_add(bond);
+            ensure!(
+                info.bond >= T::MinRelayerBond::get(),
+                Error::<T>::BondTooLow
+            );
+
+            CurrencyOf::<T>::reserve(&relayer, bond)?;
+            Relayers::<T>::insert(&relayer, info.clone());
+
+            Self::deposit_event(Event::RelayerRegistered {
+                relayer,
+                bond: info.bond,
+            });
+
+            Ok(().into())
+        }
+
+        /// unregister a relayer, releasing what is left of its bond.
+        ///
+        /// Accrued rewards stay claimable.
+        #[pallet::weight({12})]
+        #[pallet::call_index(12)]
+        pub fn unregister_relayer(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            let relayer = ensure_signed(origin)?;
+
+            ensure!(
+                Self::pending_proofs(&relayer) == 0,
+                Error::<T>::RelayerHasPendingReceipts
+            );
+            let info = Relayers::<T>::take(&relayer).ok_or(Error::<T>::NotRegisteredRelayer)?;
+            CurrencyOf::<T>::unreserve(&relayer, info.bond);
+
+            Self::deposit_event(Event::RelayerUnregistered {
+                relayer,
+                bond: info.bond,
+            });
+
+            Ok(().into())
+        }
+
+        /// transfer the rewards accrued by the caller from the pallet account
+        #[pallet::weight({13})]
+        #[pallet::call_index(13)]
+        pub fn claim_rewards(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            let relayer = ensure_signed(origin)?;
+
+            let amount = PendingRewards::<T>::take(&relayer);
+            ensure!(!amount.is_zero(), Error::<T>::NoRewards);
+
+            CurrencyOf::<T>::transfer(&Self::account_id(), &relayer, amount, AllowDeath)?;
+
+            Self::deposit_event(Event::RewardsClaimed { relayer, amount });
+
+            Ok(().into())
+        }
+
+        /// fund the reward pool of a watched contract.
+        ///
+        /// The funds are moved to the pallet account. The first funder becomes the owner of the
+        /// pool, anyone can top it up afterwards.
+        #[pallet::weight({14})]
+        #[pallet::call_index(14)]
+        pub fn fund_reward_pool(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            address: H160,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let funder = ensure_signed(origin)?;
+
+            ensure!(
+                Self::watched_contracts(typed_chain_id)
+                    .map_or(false, |addresses| addresses.contains(&address)),
+                Error::<T>::ContractNotWatched
+            );
+
+            CurrencyOf::<T>::transfer(&funder, &Self::account_id(), amount, KeepAlive)?;
+
+            let pool = RewardPools::<T>::mutate(typed_chain_id, address, |pool| {
+                let pool = pool.get_or_insert_with(|| RewardPool {
+                    owner: funder,
+                    balance: Zero::zero(),
+                    reward: Self::registered_chains(typed_chain_id)
+                        .map(|settings| settings.default_reward)
+                        .unwrap_or_default(),
+                });
+                pool.balance.saturating_accrue(amount);
+                pool.clone()
+            });
+
+            Self::deposit_event(Event::RewardPoolFunded {
+                typed_chain_id,
+                address,
+                amount,
+                balance: pool.balance,
+            });
+
+            Ok(().into())
+        }
+
+        /// set the reward paid from the pool for each proven receipt of the contract
+        #[pallet::weight({15})]
+        #[pallet::call_index(15)]
+        pub fn set_receipt_reward(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            address: H160,
+            reward: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let owner = ensure_signed(origin)?;
+
+            RewardPools::<T>::try_mutate(typed_chain_id, address, |pool| {
+                let pool = pool.as_mut().ok_or(Error::<T>::NoRewardPool)?;
+                ensure!(pool.owner == owner, Error::<T>::NotRewardPoolOwner);
+                pool.reward = reward;
+                Ok::<_, Error<T>>(())
+            })?;
+
+            Self::deposit_event(Event::UpdateReceiptReward {
+                typed_chain_id,
+                address,
+                reward,
+            });
+
+            Ok(().into())
+        }
+
+        /// withdraw funds from the reward pool back to its owner
+        #[pallet::weight({16})]
+        #[pallet::call_index(16)]
+        pub fn withdraw_reward_pool(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            address: H160,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let owner = ensure_signed(origin)?;
+
+            let balance = RewardPools::<T>::try_mutate(typed_chain_id, address, |pool| {
+                let pool = pool.as_mut().ok_or(Error::<T>::NoRewardPool)?;
+                ensure!(pool.owner == owner, Error::<T>::NotRewardPoolOwner);
+                ensure!(pool.balance >= amount, Error::<T>::InsufficientPoolBalance);
+                pool.balance -= amount;
+                Ok::<_, Error<T>>(pool.balance)
+            })?;
+
+            CurrencyOf::<T>::transfer(&Self::account_id(), &owner, amount, AllowDeath)?;
+
+            Self::deposit_event(Event::RewardPoolWithdrawn {
+                typed_chain_id,
+                address,
+                amount,
+                balance,
+            });
+
+            Ok(().into())
+        }
+
+        /// register a chain to accept proofs for
+        ///
+        /// Chains of several kinds can't share a chain id, so that interfaces identifying chains
+        /// by their chain id only, see `registered_chain`, can find them.
+        #[pallet::weight({17})]
+        #[pallet::call_index(17)]
+        pub fn register_chain(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            settings: ChainSettings<BalanceOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            T::PrivilegedOrigin::ensure_origin(origin)?;
+
+            ensure!(
+                !RegisteredChains::<T>::contains_key(typed_chain_id),
+                Error::<T>::ChainAlreadyRegistered
+            );
+            ensure!(
+                Self::registered_chain(typed_chain_id.underlying_chain_id()).is_none(),
+                Error::<T>::ChainIdAlreadyRegistered
+            );
+            RegisteredChains::<T>::insert(typed_chain_id, settings);
+
+            Self::deposit_event(Event::ChainRegistered { typed_chain_id });
+
+            Ok(().into())
+        }
+
+        /// update the settings of a registered chain
+        #[pallet::weight({18})]
+        #[pallet::call_index(18)]
+        pub fn update_chain_settings(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            settings: ChainSettings<BalanceOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            T::PrivilegedOrigin::ensure_origin(origin)?;
+
+            ensure!(
+                RegisteredChains::<T>::contains_key(typed_chain_id),
+                Error::<T>::ChainNotRegistered
+            );
+            RegisteredChains::<T>::insert(typed_chain_id, settings);
+            FinalityProgress::<T>::remove(typed_chain_id);
+
+            Self::deposit_event(Event::ChainSettingsUpdated { typed_chain_id });
+
+            Ok(().into())
+        }
+
+        /// deregister a chain, dropping its watched contracts.
+        ///
+        /// Proven receipts and reward pools of the chain are kept.
+        #[pallet::weight({19})]
+        #[pallet::call_index(19)]
+        pub fn deregister_chain(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+        ) -> DispatchResultWithPostInfo {
+            T::PrivilegedOrigin::ensure_origin(origin)?;
+
+            RegisteredChains::<T>::take(typed_chain_id).ok_or(Error::<T>::ChainNotRegistered)?;
+            WatchedContracts::<T>::remove(typed_chain_id);
+            FinalityProgress::<T>::remove(typed_chain_id);
+
+            Self::deposit_event(Event::ChainDeregistered { typed_chain_id });
+
+            Ok(().into())
+        }
+
+        /// register a callback called with the proven logs of a watched contract having `topic`
+        ///
+        /// Only the owner of the reward pool of the contract can register callbacks for its logs.
+        /// `deposit` is reserved and added to the budget paying for the weight of the calls.
+        #[pallet::weight({20})]
+        #[pallet::call_index(20)]
+        pub fn register_callback(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            address: H160,
+            topic: H256,
+            target: CallbackTarget<<T as frame_system::Config>::AccountId>,
+            weight_limit: Weight,
+            deposit: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let owner = ensure_signed(origin)?;
+
+            ensure!(
+                Self::watched_contracts(typed_chain_id)
+                    .map_or(false, |contracts| contracts.contains(&address)),
+                Error::<T>::ContractNotWatched
+            );
+            ensure!(
+                Self::reward_pools(typed_chain_id, address)
+                    .map_or(false, |pool| pool.owner == owner),
+                Error::<T>::NotRewardPoolOwner
+            );
+            ensure!(
+                weight_limit.all_lte(T::MaxCallbackWeight::get()),
+                Error::<T>::CallbackWeightTooHigh
+            );
+
+            let key = (typed_chain_id, address, topic);
+            let budget = match Callbacks::<T>::get(key) {
+                Some(callback) => {
+                    ensure!(callback.owner == owner, Error::<T>::NotCallbackOwner);
+                    callback.budget
+                }
+                None => Zero::zero(),
+            };
+
+            CurrencyOf::<T>::reserve(&owner, deposit)?;
+            let budget = budget.saturating_add(deposit);
+
+            Callbacks::<T>::insert(
+                key,
+                Callback {
+                    owner: owner.clone(),
+                    target,
+                    weight_limit,
+                    budget,
+                },
+            );
+
+            Self::deposit_event(Event::CallbackRegistered {
+                typed_chain_id,
+                address,
+                topic,
+                owner,
+                budget,
+            });
+
+            Ok(().into())
+        }
+
+        /// remove a callback and unreserve what is left of its budget
+        #[pallet::weight({21})]
+        #[pallet::call_index(21)]
+        pub fn remove_callback(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            address: H160,
+            topic: H256,
+        ) -> DispatchResultWithPostInfo {
+            let owner = ensure_signed(origin)?;
+
+            let key = (typed_chain_id, address, topic);
+            let callback = Callbacks::<T>::get(key).ok_or(Error::<T>::NoCallback)?;
+            ensure!(callback.owner == owner, Error::<T>::NotCallbackOwner);
+
+            Callbacks::<T>::remove(key);
+            CurrencyOf::<T>::unreserve(&owner, callback.budget);
+
+            Self::deposit_event(Event::CallbackRemoved {
+                typed_chain_id,
+                address,
+                topic,
+            });
+
+            Ok(().into())
+        }
+
+        /// dispute a pending receipt whose anchor block has been replaced by another one in the
+        /// light client
+        ///
+        /// The privileged origin can drop any pending receipt. The proof deposit is slashed from
+        /// the bond of the relayer, to the disputer if any.
+        #[pallet::weight({22})]
+        #[pallet::call_index(22)]
+        pub fn dispute_receipt(
+            origin: OriginFor<T>,
+            typed_chain_id: TypedChainId,
+            receipt_hash: H256,
+        ) -> DispatchResultWithPostInfo {
+            let disputer = match T::PrivilegedOrigin::try_origin(origin) {
+                Ok(_) => None,
+                Err(origin) => Some(ensure_signed(origin)?),
+            };
+
+            let pending = Self::pending_receipts(typed_chain_id, receipt_hash)
+                .ok_or(Error::<T>::NoPendingReceipt)?;
+            if disputer.is_some() {
+                // A pruned anchor doesn't make the proof wrong, only a conflicting one does.
+                let (anchor_number, anchor_hash) = pending.anchor;
+                let finalized_hash =
+                    pallet_eth2_light_client::Pallet::<T>::finalized_execution_blocks(
+                        typed_chain_id,
+                        anchor_number,
+                    );
+                ensure!(
+                    finalized_hash.map_or(false, |hash| hash.0 .0 != anchor_hash.0),
+                    Error::<T>::ReceiptNotDisputable
+                );
+            }
+
+            PendingReceipts::<T>::remove(typed_chain_id, receipt_hash);
+            PendingProofs::<T>::mutate(&pending.relayer, |count| *count = count.saturating_sub(1));
+
+            let beneficiary = disputer.clone().unwrap_or_else(Self::account_id);
+            Relayers::<T>::mutate(&pending.relayer, |info| {
+                if let Some(info) = info {
+                    Self::slash_proof_deposit(typed_chain_id, &pending.relayer, info, &beneficiary);
+                }
+            });
+
+            Self::deposit_event(Event::ReceiptDisputed {
+                typed_chain_id,
+                receipt_hash,
+                relayer: pending.relayer,
+                disputer,
+            });
+
+            Ok(().into())
+        }
+
+        /// reject proofs of a chain, or of every chain if `typed_chain_id` is `None`
+        #[pallet::weight({23})]
+        #[pallet::call_index(23)]
+        pub fn pause(
+            origin: OriginFor<T>,
+            typed_chain_id: Option<TypedChainId>,
+        ) -> DispatchResultWithPostInfo {
+            T::PrivilegedOrigin::ensure_origin(origin)?;
+
+            Self::set_paused(typed_chain_id, true)?;
+            Self::deposit_event(Event::Paused { typed_chain_id });
+
+            Ok(().into())
+        }
+
+        /// accept proofs of a chain again, or lift the pause of every chain if `typed_chain_id`
+        /// is `None`
+        ///
+        /// Chains paused on their own stay paused when the pause of every chain is lifted.
+        #[pallet::weight({24})]
+        #[pallet::call_index(24)]
+        pub fn unpause(
+            origin: OriginFor<T>,
+            typed_chain_id: Option<TypedChainId>,
+        ) -> DispatchResultWithPostInfo {
+            T::PrivilegedOrigin::ensure_origin(origin)?;
+
+            Self::set_paused(typed_chain_id, false)?;
+            Self::deposit_event(Event::Unpaused { typed_chain_id });
+
+            Ok(().into())
+        }
     }
 }
 
@@ -320,6 +1464,843 @@ impl<T: Config> Pallet<T> {
         <T as Config>::PalletId::get().into_account_truncating()
     }
 
+    /// Account calling the callbacks of contract pallets identifying callers by account, so that
+    /// contracts can check that the logs they receive come from the registry.
+    pub fn callback_account_id() -> <T as frame_system::Config>::AccountId {
+        <T as Config>::PalletId::get().into_sub_account_truncating(b"callback")
+    }
+
+    /// Upper bound of the weight consumed by the handlers and callbacks of a stored receipt.
+    pub fn max_store_weight() -> Weight {
+        T::MaxOnReceiptProvedWeight::get().saturating_add(
+            T::MaxCallbackWeight::get().saturating_mul(T::MaxCallbacksPerReceipt::get().into()),
+        )
+    }
+
+    /// Weight of `submit_log_proof` with a proof of `len` bytes.
+    pub fn submit_log_proof_weight(len: usize) -> Weight {
+        let db_weight = <T as frame_system::Config>::DbWeight::get();
+        // Reading the relayer, the finalized header, the registry and chain settings, the light
+        // client head, the watched contracts and the decodings of the log, and writing the log,
+        // the prune cursor, and the relayer with its reward pool and pending rewards.
+        Weight::from_parts(9, 0)
+            .saturating_add(db_weight.reads_writes(12, 6))
+            .saturating_add(LOG_PROOF_BYTE_WEIGHT.saturating_mul(len as u64))
+    }
+
+    /// Sets the pause flag of a chain, or of the registry if `typed_chain_id` is `None`.
+    fn set_paused(typed_chain_id: Option<TypedChainId>, paused: bool) -> Result<(), Error<T>> {
+        let Some(typed_chain_id) = typed_chain_id else {
+            Paused::<T>::put(paused);
+            return Ok(());
+        };
+
+        RegisteredChains::<T>::try_mutate(typed_chain_id, |settings| {
+            let settings = settings.as_mut().ok_or(Error::<T>::ChainNotRegistered)?;
+            settings.paused = paused;
+            Ok::<_, Error<T>>(())
+        })?;
+        // The light client gets a fresh `MaxFinalityStall` to make progress.
+        FinalityProgress::<T>::remove(typed_chain_id);
+        Ok(())
+    }
+
+    /// Checks that the block is finalized by the light client.
+    fn ensure_finalized(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        block_hash: H256,
+    ) -> Result<(), Error<T>> {
+        let finalized_execution_header_hash =
+            pallet_eth2_light_client::Pallet::<T>::finalized_execution_blocks(
+                typed_chain_id,
+                block_number,
+            )
+            .ok_or(Error::<T>::HeaderHashDoesNotExist)?;
+
+        ensure!(
+            block_hash.0 == finalized_execution_header_hash.0 .0,
+            Error::<T>::BlockHashesDoNotMatch,
+        );
+        Ok(())
+    }
+
+    /// Amount slashed from the relayer bond for a proof that doesn't store anything new.
+    pub fn proof_deposit(typed_chain_id: TypedChainId) -> BalanceOf<T> {
+        Self::registered_chains(typed_chain_id)
+            .map(|settings| settings.proof_deposit)
+            .unwrap_or_default()
+    }
+
+    /// Reward new reward pools of the chain start with.
+    ///
+    /// Deprecated: the reward actually paid is the one of the contract's pool, see
+    /// [`Self::reward_pools`].
+    pub fn proof_reward(typed_chain_id: TypedChainId) -> BalanceOf<T> {
+        Self::registered_chains(typed_chain_id)
+            .map(|settings| settings.default_reward)
+            .unwrap_or_default()
+    }
+
+    /// Checks that the chain accepts a proof for a finalized block with the given number.
+    fn ensure_chain_accepts(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+    ) -> Result<(), Error<T>> {
+        ensure!(!Self::paused(), Error::<T>::RegistryPaused);
+        let settings =
+            Self::registered_chains(typed_chain_id).ok_or(Error::<T>::ChainNotRegistered)?;
+        ensure!(settings.enabled, Error::<T>::ChainDisabled);
+        ensure!(!settings.paused, Error::<T>::ChainPaused);
+
+        let head = pallet_eth2_light_client::Pallet::<T>::last_block_number(typed_chain_id);
+        ensure!(
+            block_number.saturating_add(settings.min_confirmations) <= head,
+            Error::<T>::NotEnoughConfirmations
+        );
+        if let Some(retention) = settings.retention {
+            ensure!(
+                block_number >= Self::retention_cutoff(head, retention),
+                Error::<T>::BlockOutsideRetention
+            );
+        }
+        Ok(())
+    }
+
+    /// Registered chain with the given chain id, whatever its kind, for interfaces identifying
+    /// chains by their chain id only. `None` if no chain is registered with this id, or if chains
+    /// of several kinds were registered with it before `register_chain` rejected them.
+    ///
+    /// Reads [`CHAIN_KINDS`]`.len()` storage entries.
+    pub fn registered_chain(chain_id: u32) -> Option<TypedChainId> {
+        let mut chains = CHAIN_KINDS
+            .into_iter()
+            .map(|kind| kind(chain_id))
+            .filter(|typed_chain_id| RegisteredChains::<T>::contains_key(typed_chain_id));
+        let typed_chain_id = chains.next()?;
+        chains.next().is_none().then_some(typed_chain_id)
+    }
+
+    /// Whether proofs of the chain are rejected, because it or the whole registry is paused.
+    ///
+    /// Readers of proven receipts should not trust them while the chain is paused either.
+    pub fn is_paused(typed_chain_id: TypedChainId) -> bool {
+        Self::paused()
+            || Self::registered_chains(typed_chain_id).map_or(false, |settings| settings.paused)
+    }
+
+    /// Pauses the chains whose light client hasn't finalized a new block for
+    /// `MaxFinalityStall` blocks.
+    ///
+    /// Checks up to `MaxFinalityChecksPerBlock` chains, starting after the ones checked in the
+    /// previous block.
+    fn check_finality_progress(now: T::BlockNumber) -> Weight {
+        let db_weight = <T as frame_system::Config>::DbWeight::get();
+        let max_stall = T::MaxFinalityStall::get();
+        if max_stall == 0 {
+            return Weight::zero();
+        }
+
+        let mut weight = db_weight.reads_writes(1, 1);
+        let chains = match Self::finality_check_cursor() {
+            Some(cursor) => {
+                RegisteredChains::<T>::iter_from(RegisteredChains::<T>::hashed_key_for(cursor))
+            }
+            None => RegisteredChains::<T>::iter(),
+        };
+        let mut checked = Vec::new();
+        for (typed_chain_id, mut settings) in chains
+            .chain(RegisteredChains::<T>::iter())
+            .take(T::MaxFinalityChecksPerBlock::get() as usize)
+        {
+            weight.saturating_accrue(db_weight.reads(1));
+            // Every chain has been checked already.
+            if checked.contains(&typed_chain_id) {
+                break;
+            }
+            checked.push(typed_chain_id);
+
+            if !settings.enabled || settings.paused {
+                continue;
+            }
+
+            weight.saturating_accrue(db_weight.reads_writes(2, 1));
+            let head = pallet_eth2_light_client::Pallet::<T>::last_block_number(typed_chain_id);
+            // The light client of the chain hasn't been initialized yet.
+            if head == 0 {
+                continue;
+            }
+
+            match Self::finality_progress(typed_chain_id) {
+                Some((last_head, since)) if last_head == head => {
+                    if now.saturating_sub(since) >= max_stall.into() {
+                        weight.saturating_accrue(db_weight.writes(1));
+                        settings.paused = true;
+                        RegisteredChains::<T>::insert(typed_chain_id, settings);
+                        FinalityProgress::<T>::remove(typed_chain_id);
+                        Self::deposit_event(Event::FinalityStalled {
+                            typed_chain_id,
+                            head,
+                        });
+                        Self::deposit_event(Event::Paused {
+                            typed_chain_id: Some(typed_chain_id),
+                        });
+                    }
+                }
+                _ => FinalityProgress::<T>::insert(typed_chain_id, (head, now)),
+            }
+        }
+        if let Some(last) = checked.last() {
+            FinalityCheckCursor::<T>::put(last);
+        }
+        weight
+    }
+
+    /// First block kept with the given retention.
+    fn retention_cutoff(head: u64, retention: u64) -> u64 {
+        head.saturating_add(1).saturating_sub(retention)
+    }
+
+    /// Moves the prune cursor of the chain back to a block that got new data.
+    fn note_stored_block(typed_chain_id: TypedChainId, block_number: u64) {
+        PruneCursor::<T>::mutate(typed_chain_id, |cursor| {
+            *cursor = Some(cursor.map_or(block_number, |cursor| cursor.min(block_number)));
+        });
+    }
+
+    /// Removes receipts and logs of blocks that fell out of the retention of their chains,
+    /// within the given weight.
+    ///
+    /// Starts with the chain the previous pruning stopped at, so that every chain gets pruned
+    /// even if the weight runs out before the last ones.
+    fn prune_processed_receipts(limit: Weight) -> Weight {
+        let db_weight = <T as frame_system::Config>::DbWeight::get();
+        // Reading and writing the chain cursor.
+        let mut used = db_weight.reads_writes(1, 1);
+        if used.any_gt(limit) {
+            return Weight::zero();
+        }
+        // Reading the settings, the cursor and the light client head, and writing the cursor.
+        let chain_weight = db_weight.reads_writes(3, 1);
+        // Reading the next key and removing the entries it points to.
+        let entry_weight = db_weight.reads_writes(1, 2);
+        let block_weight = db_weight.reads(2);
+
+        let start = Self::prune_chain_cursor();
+        let resumed = start.and_then(|typed_chain_id| {
+            Self::registered_chains(typed_chain_id).map(|settings| (typed_chain_id, settings))
+        });
+        let chains = match start {
+            Some(start) => {
+                RegisteredChains::<T>::iter_from(RegisteredChains::<T>::hashed_key_for(start))
+            }
+            None => RegisteredChains::<T>::iter(),
+        };
+        let mut pruned = Vec::new();
+        let mut stopped_at = None;
+        for (typed_chain_id, settings) in resumed
+            .into_iter()
+            .chain(chains)
+            .chain(RegisteredChains::<T>::iter())
+        {
+            // Every chain has been pruned already.
+            if pruned.contains(&typed_chain_id) {
+                break;
+            }
+            if used.saturating_add(chain_weight).any_gt(limit) {
+                stopped_at = Some(typed_chain_id);
+                break;
+            }
+            used.saturating_accrue(chain_weight);
+            pruned.push(typed_chain_id);
+
+            let Some(retention) = settings.retention else {
+                continue;
+            };
+            let Some(mut cursor) = Self::prune_cursor(typed_chain_id) else {
+                continue;
+            };
+            let head = pallet_eth2_light_client::Pallet::<T>::last_block_number(typed_chain_id);
+            let cutoff = Self::retention_cutoff(head, retention);
+
+            'blocks: while cursor < cutoff {
+                for (receipt_hash, logs) in
+                    ProcessedReceipts::<T>::iter_prefix((typed_chain_id, cursor))
+                {
+                    let mut addresses = Vec::new();
+                    for log in logs {
+                        if !addresses.contains(&log.address) {
+                            addresses.push(log.address);
+                        }
+                    }
+                    // The index entries of the block are dropped as a whole.
+                    let receipt_weight =
+                        entry_weight.saturating_add(db_weight.writes(addresses.len() as u64));
+                    if used.saturating_add(receipt_weight).any_gt(limit) {
+                        stopped_at = Some(typed_chain_id);
+                        break 'blocks;
+                    }
+                    used.saturating_accrue(receipt_weight);
+                    ProcessedReceipts::<T>::remove((typed_chain_id, cursor, receipt_hash));
+                    ProcessedReceiptsHash::<T>::remove(typed_chain_id, receipt_hash);
+                    for address in addresses {
+                        ContractReceipts::<T>::remove((typed_chain_id, address, cursor));
+                    }
+                }
+                for (receipt_hash, log_index, abi_id) in
+                    ProcessedLogs::<T>::iter_key_prefix((typed_chain_id, cursor))
+                {
+                    if used.saturating_add(entry_weight).any_gt(limit) {
+                        stopped_at = Some(typed_chain_id);
+                        break 'blocks;
+                    }
+                    used.saturating_accrue(entry_weight);
+                    ProcessedLogs::<T>::remove((
+                        typed_chain_id,
+                        cursor,
+                        receipt_hash,
+                        log_index,
+                        abi_id,
+                    ));
+                }
+
+                // Reading the end of both prefixes.
+                if used.saturating_add(block_weight).any_gt(limit) {
+                    stopped_at = Some(typed_chain_id);
+                    break;
+                }
+                used.saturating_accrue(block_weight);
+                cursor += 1;
+            }
+
+            PruneCursor::<T>::insert(typed_chain_id, cursor);
+            if stopped_at.is_some() {
+                break;
+            }
+        }
+        PruneChainCursor::<T>::set(stopped_at);
+        used
+    }
+
+    /// Stores a verified receipt if it contains a log emitted by a watched contract, or keeps
+    /// it pending if the chain has a challenge period, and settles the proof fee of the relayer.
+    /// `anchor` is the finalized block the proof has been checked against.
+    ///
+    /// Returns the weight consumed by the handlers and callbacks of the stored receipt. A pending
+    /// receipt is stored later in `on_initialize`, so [`Self::max_store_weight`] is returned to
+    /// keep it charged to the proof.
+    fn process_receipt(
+        typed_chain_id: TypedChainId,
+        relayer: &<T as frame_system::Config>::AccountId,
+        event_proof: &EventProof,
+        anchor: (u64, H256),
+    ) -> Result<Weight, Error<T>> {
+        let transaction_receipt_hash: H256 = event_proof.transaction_receipt_hash;
+
+        // If the receipt proof has already been processed
+        if <ProcessedReceiptsHash<T>>::contains_key(typed_chain_id, transaction_receipt_hash)
+            || <PendingReceipts<T>>::contains_key(typed_chain_id, transaction_receipt_hash)
+        {
+            Self::settle_proof_fee(typed_chain_id, relayer, &[]);
+            return Ok(Weight::zero());
+        }
+
+        //2 checking the receipt includes a LOG emitted by a contract address we are watching.
+
+        let block_number = event_proof.block_header.number;
+
+        let addresses = Self::watched_contracts(typed_chain_id);
+        ensure!(
+            addresses.is_some(),
+            Error::<T>::NoMonitoredAddressesForChain
+        );
+
+        let contracts = addresses
+            .expect("checked above")
+            .into_iter()
+            .filter(|address| {
+                Self::is_contract_address_in_log(&event_proof.transaction_receipt, *address)
+            })
+            .collect::<Vec<_>>();
+        let logs = event_proof.transaction_receipt.receipt.logs.clone();
+
+        let challenge_period =
+            Self::registered_chains(typed_chain_id).map_or(0, |settings| settings.challenge_period);
+        if contracts.is_empty() || challenge_period == 0 {
+            let weight = Self::store_receipt(
+                typed_chain_id,
+                block_number,
+                transaction_receipt_hash,
+                logs,
+                &contracts,
+            );
+            Self::settle_proof_fee(typed_chain_id, relayer, &contracts);
+            return Ok(weight);
+        }
+
+        let challenge_end =
+            frame_system::Pallet::<T>::block_number().saturating_add(challenge_period.into());
+        PendingReceipts::<T>::insert(
+            typed_chain_id,
+            transaction_receipt_hash,
+            PendingReceipt {
+                relayer: relayer.clone(),
+                block_number,
+                anchor,
+                logs,
+                contracts,
+                challenge_end,
+            },
+        );
+        ChallengeQueue::<T>::append(challenge_end, (typed_chain_id, transaction_receipt_hash));
+        PendingProofs::<T>::mutate(relayer, |count| *count = count.saturating_add(1));
+
+        Self::deposit_event(Event::ReceiptPending {
+            typed_chain_id,
+            block_number,
+            receipt_hash: transaction_receipt_hash,
+            challenge_end,
+        });
+        Ok(Self::max_store_weight())
+    }
+
+    /// Stores the pending receipts whose challenge period has ended by `now`, and rewards the
+    /// relayers that proved them.
+    ///
+    /// Handles up to `MaxPendingReceiptsPerBlock` receipts and empty blocks of the challenge
+    /// queue, the rest is left in the queue for the next blocks.
+    fn store_pending_receipts(now: T::BlockNumber) -> Weight {
+        let db_weight = <T as frame_system::Config>::DbWeight::get();
+        let mut weight = db_weight.reads_writes(1, 1);
+
+        let mut budget = T::MaxPendingReceiptsPerBlock::get() as usize;
+        let mut block = Self::challenge_queue_cursor().unwrap_or(now);
+        while block <= now {
+            if budget == 0 {
+                ChallengeQueueCursor::<T>::put(block);
+                return weight;
+            }
+            weight.saturating_accrue(db_weight.reads_writes(1, 1));
+
+            let mut due = ChallengeQueue::<T>::take(block);
+            if due.is_empty() {
+                budget -= 1;
+            }
+            let rest = due.split_off(budget.min(due.len()));
+            budget -= due.len();
+            for (typed_chain_id, receipt_hash) in due {
+                weight.saturating_accrue(Self::store_pending_receipt(
+                    block,
+                    typed_chain_id,
+                    receipt_hash,
+                ));
+            }
+            if !rest.is_empty() {
+                ChallengeQueue::<T>::insert(block, rest);
+                ChallengeQueueCursor::<T>::put(block);
+                return weight;
+            }
+            block.saturating_inc();
+        }
+        ChallengeQueueCursor::<T>::kill();
+        weight
+    }
+
+    /// Stores a pending receipt queued at the given block of the challenge queue.
+    fn store_pending_receipt(
+        block: T::BlockNumber,
+        typed_chain_id: TypedChainId,
+        receipt_hash: H256,
+    ) -> Weight {
+        let db_weight = <T as frame_system::Config>::DbWeight::get();
+        let mut weight = db_weight.reads_writes(1, 1);
+        // Disputed receipts are already gone, or pending again with a later end if they have
+        // been proven again.
+        let Some(pending) = PendingReceipts::<T>::get(typed_chain_id, receipt_hash) else {
+            return weight;
+        };
+        if pending.challenge_end != block {
+            return weight;
+        }
+        PendingReceipts::<T>::remove(typed_chain_id, receipt_hash);
+
+        // Counting the receipt, its hash, the prune cursor, the index entries, the relayer and
+        // the reward pools.
+        let contracts = pending.contracts.len() as u64;
+        weight.saturating_accrue(db_weight.reads_writes(3 + contracts, 5 + 2 * contracts));
+
+        PendingProofs::<T>::mutate(&pending.relayer, |count| *count = count.saturating_sub(1));
+        weight.saturating_accrue(Self::store_receipt(
+            typed_chain_id,
+            pending.block_number,
+            receipt_hash,
+            pending.logs,
+            &pending.contracts,
+        ));
+        Self::settle_proof_fee(typed_chain_id, &pending.relayer, &pending.contracts);
+        weight
+    }
+
+    /// Stores a receipt with logs emitted by the given watched contracts, and notifies the
+    /// downstream consumers of these logs. Nothing is stored if there are no such contracts.
+    ///
+    /// Returns the weight consumed by the downstream consumers, at most
+    /// [`Self::max_store_weight`].
+    fn store_receipt(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        transaction_receipt_hash: H256,
+        logs: Vec<Log>,
+        contracts: &[H160],
+    ) -> Weight {
+        if contracts.is_empty() {
+            return Weight::zero();
+        }
+
+        let contract_logs = logs
+            .iter()
+            .filter(|log| contracts.contains(&log.address))
+            .cloned()
+            .collect::<Vec<_>>();
+        ProcessedReceipts::<T>::insert(
+            (typed_chain_id, block_number, transaction_receipt_hash),
+            logs,
+        );
+        ProcessedReceiptsHash::<T>::insert(typed_chain_id, transaction_receipt_hash, ());
+        for address in contracts {
+            ContractReceipts::<T>::append(
+                (typed_chain_id, *address, block_number),
+                transaction_receipt_hash,
+            );
+            Self::deposit_event(Event::SubmitProcessedReceipts {
+                typed_chain_id,
+                block_number,
+                receipt_hash: transaction_receipt_hash,
+            });
+        }
+        Self::note_stored_block(typed_chain_id, block_number);
+
+        let weight = T::OnReceiptProved::on_receipt_proved(
+            typed_chain_id,
+            block_number,
+            transaction_receipt_hash,
+            &contract_logs,
+        );
+        weight.saturating_add(Self::run_callbacks(
+            typed_chain_id,
+            block_number,
+            transaction_receipt_hash,
+            &contract_logs,
+        ))
+    }
+
+    /// Calls the callbacks registered for the topics of newly proven logs.
+    ///
+    /// Each call runs in its own storage transaction, so a failing callback is reverted without
+    /// affecting the proof or the other callbacks. The consumed weight is paid from the budget of
+    /// the callback, and returned so that it is charged to the call storing the receipt. At most
+    /// `MaxCallbacksPerReceipt` callbacks are called.
+    fn run_callbacks(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        logs: &[Log],
+    ) -> Weight {
+        let mut total_weight = Weight::zero();
+        let mut calls_left = T::MaxCallbacksPerReceipt::get();
+        for log in logs {
+            for topic in &log.topics {
+                let key = (typed_chain_id, log.address, *topic);
+                let Some(mut callback) = Callbacks::<T>::get(key) else {
+                    continue;
+                };
+
+                if calls_left == 0
+                    || callback.budget < T::WeightToFee::weight_to_fee(&callback.weight_limit)
+                {
+                    Self::deposit_event(Event::CallbackSkipped {
+                        typed_chain_id,
+                        address: log.address,
+                        topic: *topic,
+                    });
+                    continue;
+                }
+
+                calls_left -= 1;
+
+                let (weight, result) = with_transaction(|| {
+                    let (weight, result) = T::CallbackDispatcher::dispatch(
+                        &callback.owner,
+                        &callback.target,
+                        typed_chain_id,
+                        block_number,
+                        receipt_hash,
+                        log,
+                        callback.weight_limit,
+                    )
+                    .unwrap_or((
+                        Weight::zero(),
+                        Err(Error::<T>::UnsupportedCallbackTarget.into()),
+                    ));
+                    if result.is_ok() {
+                        TransactionOutcome::Commit(Ok::<_, DispatchError>((weight, result)))
+                    } else {
+                        TransactionOutcome::Rollback(Ok((weight, result)))
+                    }
+                })
+                .unwrap_or_else(|err| (Weight::zero(), Err(err)));
+                let weight = weight.min(callback.weight_limit);
+
+                let fee = T::WeightToFee::weight_to_fee(&weight).min(callback.budget);
+                let unpaid = CurrencyOf::<T>::repatriate_reserved(
+                    &callback.owner,
+                    &Self::account_id(),
+                    fee,
+                    BalanceStatus::Free,
+                )
+                .unwrap_or(fee);
+                let fee = fee - unpaid;
+                callback.budget -= fee;
+                Callbacks::<T>::insert(key, callback);
+
+                total_weight.saturating_accrue(weight);
+                Self::deposit_event(Event::CallbackExecuted {
+                    typed_chain_id,
+                    address: log.address,
+                    topic: *topic,
+                    success: result.is_ok(),
+                    weight,
+                    fee,
+                });
+            }
+        }
+        total_weight
+    }
+
+    /// Checks that the account is a registered relayer with a sufficient bond.
+    fn ensure_relayer(relayer: &<T as frame_system::Config>::AccountId) -> Result<(), Error<T>> {
+        let info = Self::relayers(relayer).ok_or(Error::<T>::NotRegisteredRelayer)?;
+        ensure!(
+            info.bond >= T::MinRelayerBond::get(),
+            Error::<T>::BondTooLow
+        );
+        Ok(())
+    }
+
+    /// Accrues the rewards of the contracts a new proof is about to the relayer, or slashes the
+    /// proof deposit from its bond if nothing new has been proven.
+    fn settle_proof_fee(
+        typed_chain_id: TypedChainId,
+        relayer: &<T as frame_system::Config>::AccountId,
+        contracts: &[H160],
+    ) {
+        Relayers::<T>::mutate(relayer, |info| {
+            let Some(info) = info else {
+                debug_assert!(false, "checked by ensure_relayer");
+                return;
+            };
+
+            if !contracts.is_empty() {
+                // Rewarding relayer for submitting a proof of inclusion of a receipt
+                let mut amount = BalanceOf::<T>::zero();
+                for address in contracts {
+                    amount.saturating_accrue(Self::take_reward(typed_chain_id, *address));
+                }
+                info.proofs_accepted += 1;
+                PendingRewards::<T>::mutate(relayer, |pending| pending.saturating_accrue(amount));
+                Self::deposit_event(Event::RewardAccrued {
+                    relayer: relayer.clone(),
+                    amount,
+                });
+            } else {
+                Self::slash_proof_deposit(typed_chain_id, relayer, info, &Self::account_id());
+            }
+        });
+    }
+
+    /// Slashes the proof deposit of the chain from the bond of the relayer to `beneficiary`.
+    fn slash_proof_deposit(
+        typed_chain_id: TypedChainId,
+        relayer: &<T as frame_system::Config>::AccountId,
+        info: &mut RelayerInfo<BalanceOf<T>>,
+        beneficiary: &<T as frame_system::Config>::AccountId,
+    ) {
+        let deposit = Self::proof_deposit(typed_chain_id).min(info.bond);
+        // Moves as much of the deposit as is still reserved, so the bond can't go
+        // out of sync with the reserved balance.
+        let not_slashed = CurrencyOf::<T>::repatriate_reserved(
+            relayer,
+            beneficiary,
+            deposit,
+            BalanceStatus::Free,
+        )
+        .unwrap_or(deposit);
+        let amount = deposit - not_slashed;
+        info.bond -= amount;
+        info.proofs_rejected += 1;
+        Self::deposit_event(Event::RelayerSlashed {
+            relayer: relayer.clone(),
+            amount,
+        });
+    }
+
+    /// Takes the reward for a proof from the pool of the contract.
+    ///
+    /// Nothing is taken if the pool can't pay the full reward.
+    fn take_reward(typed_chain_id: TypedChainId, address: H160) -> BalanceOf<T> {
+        RewardPools::<T>::mutate(typed_chain_id, address, |pool| {
+            let Some(pool) = pool else {
+                return Zero::zero();
+            };
+            if pool.reward.is_zero() {
+                return Zero::zero();
+            }
+
+            if pool.balance < pool.reward {
+                Self::deposit_event(Event::RewardPoolEmpty {
+                    typed_chain_id,
+                    address,
+                });
+                return Zero::zero();
+            }
+
+            pool.balance -= pool.reward;
+            let low_threshold = pool
+                .reward
+                .saturating_mul(T::RewardPoolLowThreshold::get().into());
+            if pool.balance < low_threshold {
+                Self::deposit_event(Event::RewardPoolLow {
+                    typed_chain_id,
+                    address,
+                    balance: pool.balance,
+                });
+            }
+            pool.reward
+        })
+    }
+
+    /// Size of a stored receipt in bytes, read without decoding it.
+    pub fn processed_receipt_size(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+    ) -> Option<u32> {
+        let key =
+            ProcessedReceipts::<T>::hashed_key_for((typed_chain_id, block_number, receipt_hash));
+        frame_support::sp_io::storage::read(&key, &mut [], 0)
+    }
+
+    /// Decodings of a proven log, with the id of the event ABI each one was decoded with, or
+    /// `None` for the log stored as is.
+    pub fn decoded_logs(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: H256,
+        log_index: u32,
+    ) -> Vec<(Option<H256>, DecodedLog)> {
+        ProcessedLogs::<T>::iter_prefix((typed_chain_id, block_number, receipt_hash, log_index))
+            .collect()
+    }
+
+    /// Receipts with logs of the watched contract proven in the blocks from `from` to `to`
+    /// inclusive, as `(block number, receipt hash)`.
+    ///
+    /// Fails if the range spans more than [`MAX_BLOCK_RANGE`] blocks or holds more than
+    /// [`MAX_RANGE_RECEIPTS`] receipts.
+    pub fn receipts_for_contract(
+        typed_chain_id: TypedChainId,
+        address: H160,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(u64, H256)>, Error<T>> {
+        Self::ensure_block_range(from, to)?;
+        Self::collect_receipts((from..=to).flat_map(|block_number| {
+            Self::contract_receipts((typed_chain_id, address, block_number))
+                .into_iter()
+                .map(move |receipt_hash| (block_number, receipt_hash))
+        }))
+    }
+
+    /// Receipts proven in the blocks from `from` to `to` inclusive, as `(block number, receipt
+    /// hash, logs)`. Only the logs emitted by `address` and having `topic` are returned if set,
+    /// and receipts without such logs are skipped.
+    ///
+    /// Fails if the range spans more than [`MAX_BLOCK_RANGE`] blocks or holds more than
+    /// [`MAX_RANGE_RECEIPTS`] receipts, before any filtering by topic.
+    pub fn receipts_in_range(
+        typed_chain_id: TypedChainId,
+        from: u64,
+        to: u64,
+        address: Option<H160>,
+        topic: Option<H256>,
+    ) -> Result<Vec<(u64, H256, Vec<Log>)>, Error<T>> {
+        Self::receipts_in_range_metered(typed_chain_id, from, to, address, topic, |_| Ok(()))
+    }
+
+    /// Same as [`Self::receipts_in_range`], calling `on_read` with the logs of every receipt read
+    /// from storage before they are filtered, so that callers can charge for the whole read.
+    /// Stops at the first error returned by `on_read`.
+    pub fn receipts_in_range_metered<E: From<Error<T>>>(
+        typed_chain_id: TypedChainId,
+        from: u64,
+        to: u64,
+        address: Option<H160>,
+        topic: Option<H256>,
+        mut on_read: impl FnMut(&[Log]) -> Result<(), E>,
+    ) -> Result<Vec<(u64, H256, Vec<Log>)>, E> {
+        let receipts = match address {
+            Some(address) => Self::receipts_for_contract(typed_chain_id, address, from, to)?,
+            None => {
+                Self::ensure_block_range(from, to)?;
+                Self::collect_receipts((from..=to).flat_map(|block_number| {
+                    ProcessedReceipts::<T>::iter_key_prefix((typed_chain_id, block_number))
+                        .map(move |receipt_hash| (block_number, receipt_hash))
+                }))?
+            }
+        };
+
+        let mut matching = Vec::new();
+        for (block_number, receipt_hash) in receipts {
+            let Some(logs) = Self::processed_receipts((typed_chain_id, block_number, receipt_hash))
+            else {
+                continue;
+            };
+            on_read(&logs)?;
+
+            let logs = logs
+                .into_iter()
+                .filter(|log| {
+                    address.map_or(true, |address| log.address == address)
+                        && topic.map_or(true, |topic| log.topics.contains(&topic))
+                })
+                .collect::<Vec<_>>();
+            if !logs.is_empty() {
+                matching.push((block_number, receipt_hash, logs));
+            }
+        }
+        Ok(matching)
+    }
+
+    fn ensure_block_range(from: u64, to: u64) -> Result<(), Error<T>> {
+        ensure!(
+            to.saturating_sub(from) < MAX_BLOCK_RANGE,
+            Error::<T>::RangeTooLarge
+        );
+        Ok(())
+    }
+
+    /// Collects at most [`MAX_RANGE_RECEIPTS`] receipts, without reading the ones past the cap.
+    fn collect_receipts(
+        receipts: impl Iterator<Item = (u64, H256)>,
+    ) -> Result<Vec<(u64, H256)>, Error<T>> {
+        let receipts = receipts.take(MAX_RANGE_RECEIPTS + 1).collect::<Vec<_>>();
+        ensure!(
+            receipts.len() <= MAX_RANGE_RECEIPTS,
+            Error::<T>::TooManyReceipts
+        );
+        Ok(receipts)
+    }
+
     pub fn is_contract_address_in_log(
         transaction_receipt: &TransactionReceipt,
         address: H160,
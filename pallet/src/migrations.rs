@@ -0,0 +1,231 @@
+//! Storage migrations of the pallet.
+
+pub mod v1 {
+    use frame_support::{
+        pallet_prelude::*,
+        sp_std::{marker::PhantomData, prelude::*},
+        storage_alias,
+        traits::OnRuntimeUpgrade,
+        weights::Weight,
+        Blake2_128Concat,
+    };
+    use webb_proposals::TypedChainId;
+
+    use crate::{BalanceOf, ChainSettings, Config, Pallet, RegisteredChains, WatchedContracts};
+
+    /// Proof deposit per chain, moved to [`ChainSettings::proof_deposit`].
+    #[storage_alias]
+    type ProofDeposit<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, TypedChainId, BalanceOf<T>, ValueQuery>;
+
+    /// Reward per chain, moved to [`ChainSettings::default_reward`] of the reward pools.
+    #[storage_alias]
+    type ProofReward<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, TypedChainId, BalanceOf<T>, ValueQuery>;
+
+    /// Registers the chains that have watched contracts, a proof deposit or a reward, with the
+    /// deposit and the reward moved to their settings.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let db_weight = <T as frame_system::Config>::DbWeight::get();
+            if StorageVersion::get::<Pallet<T>>() != 0 {
+                return db_weight.reads(1);
+            }
+
+            let mut reads = 1;
+            let mut writes = 1;
+
+            let mut chains = WatchedContracts::<T>::iter_keys()
+                .map(|typed_chain_id| (typed_chain_id, ChainSettings::default()))
+                .collect::<Vec<(TypedChainId, ChainSettings<BalanceOf<T>>)>>();
+            reads += chains.len() as u64;
+
+            for (typed_chain_id, proof_deposit) in ProofDeposit::<T>::drain() {
+                reads += 1;
+                writes += 1;
+                match chains.iter_mut().find(|(id, _)| *id == typed_chain_id) {
+                    Some((_, settings)) => settings.proof_deposit = proof_deposit,
+                    None => chains.push((
+                        typed_chain_id,
+                        ChainSettings {
+                            proof_deposit,
+                            ..Default::default()
+                        },
+                    )),
+                }
+            }
+
+            for (typed_chain_id, default_reward) in ProofReward::<T>::drain() {
+                reads += 1;
+                writes += 1;
+                match chains.iter_mut().find(|(id, _)| *id == typed_chain_id) {
+                    Some((_, settings)) => settings.default_reward = default_reward,
+                    None => chains.push((
+                        typed_chain_id,
+                        ChainSettings {
+                            default_reward,
+                            ..Default::default()
+                        },
+                    )),
+                }
+            }
+
+            for (typed_chain_id, settings) in chains {
+                reads += 1;
+                if !RegisteredChains::<T>::contains_key(typed_chain_id) {
+                    writes += 1;
+                    RegisteredChains::<T>::insert(typed_chain_id, settings);
+                }
+            }
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            db_weight.reads_writes(reads, writes)
+        }
+    }
+}
+
+pub mod v2 {
+    use frame_support::{
+        pallet_prelude::*,
+        sp_std::{marker::PhantomData, prelude::*},
+        storage_alias,
+        traits::OnRuntimeUpgrade,
+        weights::Weight,
+    };
+
+    use crate::{Config, ContractReceipts, Pallet, ProcessedReceipts, WatchedContracts};
+
+    /// Raw key of the last proven receipt indexed by the migration, empty before the first one.
+    /// Removed once every receipt has been indexed.
+    #[storage_alias]
+    type IndexCursor<T: Config> = StorageValue<Pallet<T>, Vec<u8>, OptionQuery>;
+
+    /// Indexes the already proven receipts by the watched contracts that emitted their logs.
+    ///
+    /// The receipts are indexed over the next blocks with the weight left in `on_idle`, see
+    /// [`index_receipts`]. Until then, receipts proven before the upgrade are missing from
+    /// `receipts_for_contract`.
+    pub struct MigrateToV2<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let db_weight = <T as frame_system::Config>::DbWeight::get();
+            if StorageVersion::get::<Pallet<T>>() != 1 {
+                return db_weight.reads(1);
+            }
+
+            IndexCursor::<T>::put(Vec::<u8>::new());
+            StorageVersion::new(2).put::<Pallet<T>>();
+
+            db_weight.reads_writes(1, 2)
+        }
+    }
+
+    /// Indexes the proven receipts following the cursor of the migration within `limit`, and
+    /// returns the weight used.
+    pub(crate) fn index_receipts<T: Config>(limit: Weight) -> Weight {
+        let db_weight = <T as frame_system::Config>::DbWeight::get();
+        let mut used = db_weight.reads(1);
+        if used.any_gt(limit) {
+            return Weight::zero();
+        }
+        let Some(cursor) = IndexCursor::<T>::get() else {
+            return used;
+        };
+
+        // Reading the receipt, its next key and the watched contracts, and updating the index
+        // of every watched contract.
+        let max_contracts = T::MaxWatchedContracts::get() as u64;
+        let receipt_weight = db_weight.reads_writes(3 + max_contracts, max_contracts);
+        used.saturating_accrue(db_weight.writes(1));
+
+        let mut receipts = if cursor.is_empty() {
+            ProcessedReceipts::<T>::iter()
+        } else {
+            ProcessedReceipts::<T>::iter_from(cursor)
+        };
+        let mut last_key = None;
+        while used.saturating_add(receipt_weight).all_lte(limit) {
+            let Some(((typed_chain_id, block_number, receipt_hash), logs)) = receipts.next() else {
+                IndexCursor::<T>::kill();
+                return used;
+            };
+            used.saturating_accrue(receipt_weight);
+            last_key = Some(receipts.last_raw_key().to_vec());
+
+            let Some(addresses) = WatchedContracts::<T>::get(typed_chain_id) else {
+                continue;
+            };
+            for address in addresses {
+                if logs.iter().any(|log| log.address == address) {
+                    // Receipts proven since the upgrade are indexed already.
+                    ContractReceipts::<T>::mutate(
+                        (typed_chain_id, address, block_number),
+                        |hashes| {
+                            if !hashes.contains(&receipt_hash) {
+                                hashes.push(receipt_hash);
+                            }
+                        },
+                    );
+                }
+            }
+        }
+        if let Some(last_key) = last_key {
+            IndexCursor::<T>::put(last_key);
+        }
+        used
+    }
+}
+
+pub mod v3 {
+    use frame_support::{
+        pallet_prelude::*, sp_std::marker::PhantomData, traits::OnRuntimeUpgrade, weights::Weight,
+    };
+
+    use crate::{BalanceOf, ChainSettings, Config, Pallet, RegisteredChains};
+
+    /// [`ChainSettings`] before the challenge period.
+    #[derive(Encode, Decode)]
+    struct OldChainSettings<Balance> {
+        enabled: bool,
+        paused: bool,
+        proof_deposit: Balance,
+        default_reward: Balance,
+        retention: Option<u64>,
+        min_confirmations: u64,
+    }
+
+    /// Adds the challenge period to the settings of the registered chains, disabled so that
+    /// their receipts keep being stored right away.
+    pub struct MigrateToV3<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let db_weight = <T as frame_system::Config>::DbWeight::get();
+            if StorageVersion::get::<Pallet<T>>() != 2 {
+                return db_weight.reads(1);
+            }
+
+            let mut translated = 0;
+            RegisteredChains::<T>::translate::<OldChainSettings<BalanceOf<T>>, _>(|_, old| {
+                translated += 1;
+                Some(ChainSettings {
+                    enabled: old.enabled,
+                    paused: old.paused,
+                    proof_deposit: old.proof_deposit,
+                    default_reward: old.default_reward,
+                    retention: old.retention,
+                    min_confirmations: old.min_confirmations,
+                    challenge_period: 0,
+                })
+            });
+
+            StorageVersion::new(3).put::<Pallet<T>>();
+
+            db_weight.reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
@@ -1,17 +1,20 @@
 use consensus::network_config::{Network, NetworkConfig};
-use frame_support::{parameter_types, sp_io, traits::GenesisBuild};
+use frame_support::weights::{IdentityFee, RuntimeDbWeight, Weight};
+use frame_support::{dispatch::DispatchResult, parameter_types, sp_io, traits::GenesisBuild};
 use frame_support::{
     sp_runtime::{
         testing::Header,
         traits::{BlakeTwo256, IdentifyAccount, IdentityLookup, Verify},
-        AccountId32, MultiSignature,
+        AccountId32, DispatchError, MultiSignature,
     },
     sp_std::convert::{TryFrom, TryInto},
     PalletId,
 };
 use frame_system as system;
 use frame_system::EnsureRoot;
+use pallet_receipt_registry::{CallbackDispatcher, CallbackTarget, OnReceiptProved};
 use sp_core::H256;
+use std::cell::RefCell;
 use webb_proposals::TypedChainId;
 
 pub type Signature = MultiSignature;
@@ -21,6 +24,10 @@ type Block = frame_system::mocking::MockBlock<Test>;
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
+    PROVED_RECEIPTS.with(|proved| proved.borrow_mut().clear());
+    CALLBACKS.with(|callbacks| callbacks.borrow_mut().clear());
+    MockDbWeight::set(FREE_DB_WEIGHT);
+
     let mut storage = system::GenesisConfig::default()
         .build_storage::<Test>()
         .unwrap();
@@ -49,13 +56,24 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         ],
     }
     .assimilate_storage(&mut storage);
+    let _ = pallet_receipt_registry::GenesisConfig::<Test> {
+        chains: vec![
+            (TypedChainId::Evm(1), Default::default()),
+            (TypedChainId::Evm(5), Default::default()),
+        ],
+    }
+    .assimilate_storage(&mut storage);
 
     storage.into()
 }
 
+/// Storage accesses are free unless a test sets their weight.
+const FREE_DB_WEIGHT: RuntimeDbWeight = RuntimeDbWeight { read: 0, write: 0 };
+
 parameter_types! {
     pub const BlockHashCount: u64 = 250;
     pub const SS58Prefix: u8 = 42;
+    pub static MockDbWeight: RuntimeDbWeight = FREE_DB_WEIGHT;
 }
 
 impl system::Config for Test {
@@ -67,7 +85,7 @@ impl system::Config for Test {
     type BlockNumber = u64;
     type BlockWeights = ();
     type RuntimeCall = RuntimeCall;
-    type DbWeight = ();
+    type DbWeight = MockDbWeight;
     type RuntimeEvent = RuntimeEvent;
     type Hash = H256;
     type Hashing = BlakeTwo256;
@@ -110,6 +128,16 @@ parameter_types! {
     pub const MaxResources: u32 = 32;
     pub const StoragePricePerByte: u128 = 1;
     pub const Eth2ClientPalletId: PalletId = PalletId(*b"py/eth2c");
+    pub const MaxAncestryLength: u32 = 2;
+    pub const MaxWatchedContracts: u32 = 100;
+    pub const MinRelayerBond: u128 = 100;
+    pub const RewardPoolLowThreshold: u32 = 3;
+    pub const MaxOnReceiptProvedWeight: Weight = Weight::from_parts(10_000, 0);
+    pub const MaxCallbackWeight: Weight = Weight::from_parts(10_000, 0);
+    pub const MaxCallbacksPerReceipt: u32 = 3;
+    pub const MaxFinalityStall: u32 = 10;
+    pub const MaxFinalityChecksPerBlock: u32 = 2;
+    pub const MaxPendingReceiptsPerBlock: u32 = 2;
 }
 
 impl pallet_eth2_light_client::Config for Test {
@@ -124,6 +152,109 @@ impl pallet_receipt_registry::Config for Test {
     type PalletId = Eth2ClientPalletId;
     type Currency = Balances;
     type PrivilegedOrigin = EnsureRoot<AccountId>;
+    type MaxWatchedContracts = MaxWatchedContracts;
+    type MaxAncestryLength = MaxAncestryLength;
+    type MinRelayerBond = MinRelayerBond;
+    type RewardPoolLowThreshold = RewardPoolLowThreshold;
+    type OnReceiptProved = (ProvedReceiptsRecorder, ());
+    type MaxOnReceiptProvedWeight = MaxOnReceiptProvedWeight;
+    type CallbackDispatcher = (CallbackRecorder, ());
+    type WeightToFee = IdentityFee<u128>;
+    type MaxCallbackWeight = MaxCallbackWeight;
+    type MaxCallbacksPerReceipt = MaxCallbacksPerReceipt;
+    type MaxFinalityStall = MaxFinalityStall;
+    type MaxFinalityChecksPerBlock = MaxFinalityChecksPerBlock;
+    type MaxPendingReceiptsPerBlock = MaxPendingReceiptsPerBlock;
+}
+
+/// Weight charged by [`ProvedReceiptsRecorder`] per log.
+pub const PROVED_LOG_WEIGHT: u64 = 1_000;
+
+thread_local! {
+    static PROVED_RECEIPTS: RefCell<Vec<(TypedChainId, u64, types::H256, Vec<types::Log>)>> =
+        RefCell::new(vec![]);
+}
+
+/// Downstream consumer of proven receipts that records them.
+pub struct ProvedReceiptsRecorder;
+
+impl ProvedReceiptsRecorder {
+    pub fn proved() -> Vec<(TypedChainId, u64, types::H256, Vec<types::Log>)> {
+        PROVED_RECEIPTS.with(|proved| proved.borrow().clone())
+    }
+}
+
+impl OnReceiptProved for ProvedReceiptsRecorder {
+    fn on_receipt_proved(
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: types::H256,
+        logs: &[types::Log],
+    ) -> Weight {
+        PROVED_RECEIPTS.with(|proved| {
+            proved
+                .borrow_mut()
+                .push((typed_chain_id, block_number, receipt_hash, logs.to_vec()))
+        });
+        Weight::from_parts(PROVED_LOG_WEIGHT * logs.len() as u64, 0)
+    }
+}
+
+/// Weight consumed by each call of [`CallbackRecorder`].
+pub const CALLBACK_WEIGHT: u64 = 500;
+
+/// Selector making [`CallbackRecorder`] fail the call.
+pub const FAILING_SELECTOR: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+
+type CallbackCall = ([u8; 4], TypedChainId, u64, types::H256, types::Log);
+
+thread_local! {
+    static CALLBACKS: RefCell<Vec<CallbackCall>> = RefCell::new(vec![]);
+}
+
+/// Dispatcher of ink! callbacks that records the calls.
+///
+/// Each call writes its selector to the storage, then fails if the selector is
+/// [`FAILING_SELECTOR`].
+pub struct CallbackRecorder;
+
+impl CallbackRecorder {
+    pub fn calls() -> Vec<CallbackCall> {
+        CALLBACKS.with(|callbacks| callbacks.borrow().clone())
+    }
+}
+
+impl CallbackDispatcher<AccountId> for CallbackRecorder {
+    fn dispatch(
+        _owner: &AccountId,
+        target: &CallbackTarget<AccountId>,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: types::H256,
+        log: &types::Log,
+        _weight_limit: Weight,
+    ) -> Option<(Weight, DispatchResult)> {
+        let CallbackTarget::Ink { selector, .. } = target else {
+            return None;
+        };
+        CALLBACKS.with(|callbacks| {
+            callbacks.borrow_mut().push((
+                *selector,
+                typed_chain_id,
+                block_number,
+                receipt_hash,
+                log.clone(),
+            ))
+        });
+
+        frame_support::storage::unhashed::put(selector, &true);
+        let result = if *selector == FAILING_SELECTOR {
+            Err(DispatchError::Other("callback failed"))
+        } else {
+            Ok(())
+        };
+        Some((Weight::from_parts(CALLBACK_WEIGHT, 0), result))
+    }
 }
 
 // Configure a mock runtime to test the pallet.
@@ -136,6 +267,6 @@ frame_support::construct_runtime!(
         System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
         Balances: pallet_balances::{Pallet, Call, Storage, Event<T>},
         Eth2Client: pallet_eth2_light_client::{Pallet, Call, Storage, Event<T>},
-        ReceiptRegistry: pallet_receipt_registry::{Pallet, Call, Storage, Event<T>},
+        ReceiptRegistry: pallet_receipt_registry::{Pallet, Call, Storage, Config<T>, Event<T>},
     }
 );
@@ -1,13 +1,25 @@
 use eth_types::{eth2::LightClientUpdate, pallet::InitInput, BlockHeader};
 use frame_support::sp_runtime::AccountId32;
-use frame_support::{assert_err, assert_ok};
+use frame_support::{
+    assert_err, assert_ok,
+    dispatch::GetDispatchInfo,
+    traits::{Get, Hooks, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
 use webb_proposals::TypedChainId;
 
-use pallet_receipt_registry::Error;
-use types::{Bloom, EventProof, MerkleProof, TransactionReceipt, H160, H256, U256};
+use pallet_receipt_registry::{CallbackTarget, ChainSettings, Error, LOG_PROOF_BYTE_WEIGHT};
+use types::{
+    AbiValue, AncestryProof, Bloom, DecodedLog, EventAbi, EventProof, LogProof, MerkleProof,
+    TransactionReceipt, H160, H256, U256,
+};
 
 mod mock;
-use mock::{new_test_ext, Eth2Client, ReceiptRegistry, RuntimeOrigin, System, Test};
+use mock::{
+    new_test_ext, CallbackRecorder, Eth2Client, MaxCallbacksPerReceipt, MaxPendingReceiptsPerBlock,
+    MockDbWeight, ProvedReceiptsRecorder, ReceiptRegistry, RuntimeOrigin, System, Test,
+    CALLBACK_WEIGHT, FAILING_SELECTOR, PROVED_LOG_WEIGHT,
+};
 
 mod test_utils;
 use test_utils::*;
@@ -18,6 +30,8 @@ pub mod common;
 pub const MAINNET_CHAIN: TypedChainId = TypedChainId::Evm(1);
 pub const GOERLI_CHAIN: TypedChainId = TypedChainId::Evm(5);
 pub const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+pub const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+pub const RELAYER_BOND: u128 = 100;
 
 pub fn get_test_context(
     init_options: Option<InitOptions<[u8; 32]>>,
@@ -47,6 +61,32 @@ fn balance_of_user(user: &AccountId32) -> u128 {
     System::account(user).data.free
 }
 
+fn register_relayer(relayer: &AccountId32) {
+    assert_ok!(ReceiptRegistry::register_relayer(
+        RuntimeOrigin::signed(relayer.clone()),
+        RELAYER_BOND
+    ));
+}
+
+fn bond_of_relayer(relayer: &AccountId32) -> u128 {
+    ReceiptRegistry::relayers(relayer).unwrap().bond
+}
+
+fn create_reward_pool(address: H160, amount: u128, reward: u128) {
+    assert_ok!(ReceiptRegistry::fund_reward_pool(
+        RuntimeOrigin::signed(BOB),
+        GOERLI_CHAIN,
+        address,
+        amount
+    ));
+    assert_ok!(ReceiptRegistry::set_receipt_reward(
+        RuntimeOrigin::signed(BOB),
+        GOERLI_CHAIN,
+        address,
+        reward
+    ));
+}
+
 fn create_proof(receipts: &[TransactionReceipt], index_to_prove: usize) -> MerkleProof {
     use merkle_generator::IterativeTrie;
 
@@ -101,6 +141,8 @@ fn block_header_convert(header: eth_types::BlockHeader) -> types::BlockHeader {
 #[test]
 pub fn test_submit_proof_deserialize_fail() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         assert_err!(
             ReceiptRegistry::submit_proof(RuntimeOrigin::signed(ALICE), MAINNET_CHAIN, vec![1]),
             Error::<Test>::DeserializeFail
@@ -111,6 +153,8 @@ pub fn test_submit_proof_deserialize_fail() {
 #[test]
 pub fn test_submit_proof_header_hash_do_not_exist() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         let proof = EventProof {
             block_header: types::BlockHeader {
                 parent_hash: types::H256::zero(),
@@ -166,6 +210,8 @@ pub fn test_submit_proof_header_hash_do_not_exist() {
 #[test]
 pub fn test_submit_proof_block_hash_do_not_match() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         let (headers, _updates, _init_input) = get_test_context(None);
 
         let proof = EventProof {
@@ -220,6 +266,8 @@ pub fn test_submit_proof_block_hash_do_not_match() {
 #[test]
 pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proof_fail() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
             validate_updates: true,
             verify_bls_signatures: true,
@@ -279,6 +327,8 @@ pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proo
 #[test]
 pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proof_success() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
             validate_updates: true,
             verify_bls_signatures: true,
@@ -296,7 +346,6 @@ pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proo
         ));
 
         assert_eq!(ReceiptRegistry::proof_deposit(GOERLI_CHAIN), PROOF_DEPOSIT);
-        assert_eq!(ReceiptRegistry::proof_reward(GOERLI_CHAIN), PROOF_REWARD);
 
         let address = H160(hex_literal::hex!(
             "228612206ba22b5af70b6812cb722dfe508a83ef"
@@ -314,6 +363,7 @@ pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proo
                 .to_vec(),
             vec![address]
         );
+        create_reward_pool(address, 10 * PROOF_REWARD, PROOF_REWARD);
 
         let block_header = headers[0][0].clone();
         let block_header = block_header_convert(block_header);
@@ -333,13 +383,12 @@ pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proo
 
         let serialized_proof = serde_json::to_string(&proof).unwrap();
 
-        let balance_before = balance_of_user(&ALICE);
-        assert_ok!(ReceiptRegistry::submit_proof(
+        let post_info = ReceiptRegistry::submit_proof(
             RuntimeOrigin::signed(ALICE),
             GOERLI_CHAIN,
-            serialized_proof.into()
-        ));
-        let balance_after = balance_of_user(&ALICE);
+            serialized_proof.into(),
+        )
+        .unwrap();
 
         let transaction_receipt_hash = proof.transaction_receipt_hash;
         let block_number = proof.block_header.number;
@@ -349,19 +398,98 @@ pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_verify_proo
                 block_number,
                 transaction_receipt_hash
             )),
-            Some(proof.transaction_receipt.receipt.logs)
+            Some(proof.transaction_receipt.receipt.logs.clone())
         );
         assert_eq!(
             ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, transaction_receipt_hash),
             Some(())
         );
-        assert_eq!(balance_before + PROOF_REWARD, balance_after);
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), PROOF_REWARD);
+        assert_eq!(ReceiptRegistry::relayers(ALICE).unwrap().proofs_accepted, 1);
+
+        // Only the logs of the watched contract are passed to the downstream pallets.
+        let logs = proof.transaction_receipt.receipt.logs;
+        assert_eq!(
+            ProvedReceiptsRecorder::proved(),
+            vec![(
+                GOERLI_CHAIN,
+                block_number,
+                transaction_receipt_hash,
+                vec![logs[0].clone(), logs[2].clone()]
+            )]
+        );
+        // The weight of the handlers is charged to the call, within its declared weight.
+        assert_eq!(
+            post_info.actual_weight,
+            Some(Weight::from_parts(6 + 2 * PROVED_LOG_WEIGHT, 0))
+        );
+        assert!(post_info.actual_weight.unwrap().all_lte(
+            pallet_receipt_registry::Call::<Test>::submit_proof {
+                typed_chain_id: GOERLI_CHAIN,
+                event_proof: vec![],
+            }
+            .get_dispatch_info()
+            .weight
+        ));
+
+        let in_range = |from, to, address, topic| {
+            ReceiptRegistry::receipts_in_range(GOERLI_CHAIN, from, to, address, topic).unwrap()
+        };
+        assert_eq!(
+            in_range(block_number - 1, block_number, Some(address), None),
+            vec![(
+                block_number,
+                transaction_receipt_hash,
+                vec![logs[0].clone(), logs[2].clone()]
+            )]
+        );
+        assert_eq!(
+            in_range(block_number, block_number, None, Some(logs[1].topics[0])),
+            vec![(
+                block_number,
+                transaction_receipt_hash,
+                vec![logs[1].clone()]
+            )]
+        );
+        assert_eq!(
+            in_range(
+                block_number,
+                block_number,
+                Some(address),
+                Some(logs[1].topics[0])
+            ),
+            vec![]
+        );
+        assert_eq!(in_range(0, block_number - 1, None, None), vec![]);
+
+        assert_eq!(
+            ReceiptRegistry::receipts_for_contract(
+                GOERLI_CHAIN,
+                address,
+                block_number - 1,
+                block_number + 1
+            )
+            .unwrap(),
+            vec![(block_number, transaction_receipt_hash)]
+        );
+        assert_eq!(
+            ReceiptRegistry::receipts_for_contract(
+                GOERLI_CHAIN,
+                H160::from_slice(&[1u8; 20]),
+                block_number,
+                block_number
+            )
+            .unwrap(),
+            vec![]
+        );
     });
 }
 
 #[test]
 pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_but_not_in_watch_contract() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
             validate_updates: true,
             verify_bls_signatures: true,
@@ -422,6 +550,8 @@ pub fn test_submit_proof_processed_receipts_hash_do_not_contains_key_but_not_in_
 #[test]
 pub fn test_submit_proof_processed_receipts_hash_contains_key() {
     new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
         let (headers, _updates, init_input) = get_test_data(Some(InitOptions {
             validate_updates: true,
             verify_bls_signatures: false,
@@ -446,7 +576,6 @@ pub fn test_submit_proof_processed_receipts_hash_contains_key() {
         ));
 
         assert_eq!(ReceiptRegistry::proof_deposit(GOERLI_CHAIN), PROOF_DEPOSIT);
-        assert_eq!(ReceiptRegistry::proof_reward(GOERLI_CHAIN), PROOF_REWARD);
 
         let address = H160(hex_literal::hex!(
             "228612206ba22b5af70b6812cb722dfe508a83ef"
@@ -463,6 +592,7 @@ pub fn test_submit_proof_processed_receipts_hash_contains_key() {
                 .to_vec(),
             vec![address]
         );
+        create_reward_pool(address, 10 * PROOF_REWARD, PROOF_REWARD);
 
         let block_header = headers[0][0].clone();
         let block_header = block_header_convert(block_header);
@@ -483,13 +613,11 @@ pub fn test_submit_proof_processed_receipts_hash_contains_key() {
         let serialized_proof = serde_json::to_string(&proof).unwrap();
 
         // first submit_proof
-        let balance_before = balance_of_user(&ALICE);
         assert_ok!(ReceiptRegistry::submit_proof(
             RuntimeOrigin::signed(ALICE),
             GOERLI_CHAIN,
             serialized_proof.clone().into()
         ));
-        let balance_after = balance_of_user(&ALICE);
 
         let transaction_receipt_hash: H256 = proof.transaction_receipt_hash;
         let block_number = proof.block_header.number;
@@ -505,16 +633,14 @@ pub fn test_submit_proof_processed_receipts_hash_contains_key() {
             ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, transaction_receipt_hash),
             Some(())
         );
-        assert_eq!(balance_before + PROOF_REWARD, balance_after);
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), PROOF_REWARD);
 
         // second time
-        let balance_before = balance_of_user(&ALICE);
         assert_ok!(ReceiptRegistry::submit_proof(
             RuntimeOrigin::signed(ALICE),
             GOERLI_CHAIN,
             serialized_proof.clone().into()
         ));
-        let balance_after = balance_of_user(&ALICE);
 
         assert_eq!(
             ReceiptRegistry::processed_receipts((
@@ -528,7 +654,16 @@ pub fn test_submit_proof_processed_receipts_hash_contains_key() {
             ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, transaction_receipt_hash),
             Some(())
         );
-        assert_eq!(balance_before - PROOF_DEPOSIT, balance_after);
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), PROOF_REWARD);
+        assert_eq!(bond_of_relayer(&ALICE), RELAYER_BOND - PROOF_DEPOSIT);
+        assert_eq!(ReceiptRegistry::relayers(ALICE).unwrap().proofs_rejected, 1);
+        // Downstream pallets are only notified of the first proof.
+        assert_eq!(ProvedReceiptsRecorder::proved().len(), 1);
+
+        let balance_before = balance_of_user(&ALICE);
+        assert_ok!(ReceiptRegistry::claim_rewards(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(balance_before + PROOF_REWARD, balance_of_user(&ALICE));
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), 0);
     });
 }
 
@@ -573,10 +708,6 @@ pub fn update_proof_fee() {
             ReceiptRegistry::proof_deposit(GOERLI_CHAIN),
             Default::default()
         );
-        assert_eq!(
-            ReceiptRegistry::proof_reward(GOERLI_CHAIN),
-            Default::default()
-        );
 
         assert_ok!(ReceiptRegistry::update_proof_fee(
             RuntimeOrigin::root(),
@@ -589,3 +720,1807 @@ pub fn update_proof_fee() {
         assert_eq!(ReceiptRegistry::proof_reward(GOERLI_CHAIN), 2);
     });
 }
+
+#[test]
+pub fn test_submit_log_proof() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        const PROOF_DEPOSIT: u128 = 1;
+        const PROOF_REWARD: u128 = 2;
+        assert_ok!(ReceiptRegistry::update_proof_fee(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            PROOF_DEPOSIT,
+            PROOF_REWARD
+        ));
+
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+        // Enough for a single reward.
+        create_reward_pool(address, PROOF_REWARD, PROOF_REWARD);
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+
+        let log_proof = |log_index: u32, abi: Option<EventAbi>| {
+            let proof = LogProof {
+                event_proof: EventProof {
+                    block_header: block_header.clone(),
+                    block_hash,
+                    transaction_receipt: receipts[0].clone(),
+                    transaction_receipt_hash: H256::hash(&receipts[0]),
+                    merkle_proof_of_receipt: create_proof(&receipts, 0),
+                },
+                log_index,
+                abi,
+            };
+            serde_json::to_vec(&proof).unwrap()
+        };
+
+        // The second log of the receipt is emitted by a contract we don't watch.
+        assert_err!(
+            ReceiptRegistry::submit_log_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                log_proof(1, None)
+            ),
+            Error::<Test>::LogNotFromWatchedContract
+        );
+
+        assert_err!(
+            ReceiptRegistry::submit_log_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                log_proof(4, None)
+            ),
+            Error::<Test>::LogIndexOutOfBounds
+        );
+
+        // The signature of the event doesn't match the first topic of the log.
+        let wrong_abi = EventAbi {
+            signature: b"Transfer(address,address,uint256)".to_vec(),
+            params: vec![],
+        };
+        assert_err!(
+            ReceiptRegistry::submit_log_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                log_proof(0, Some(wrong_abi))
+            ),
+            Error::<Test>::LogDecodeFail
+        );
+
+        // The types match the signature, but the data is not where the `indexed` flags say.
+        let invoked = |indexed: &[bool]| {
+            EventAbi::new(b"Invoked(address,address,uint256,bytes)".to_vec(), indexed).unwrap()
+        };
+        assert_err!(
+            ReceiptRegistry::submit_log_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                log_proof(0, Some(invoked(&[true, true, false, true])))
+            ),
+            Error::<Test>::LogDecodeFail
+        );
+
+        assert_ok!(ReceiptRegistry::submit_log_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            log_proof(2, None)
+        ));
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), PROOF_REWARD);
+
+        let receipt_hash = H256::hash(&receipts[0]);
+        assert_eq!(
+            ReceiptRegistry::processed_logs((
+                GOERLI_CHAIN,
+                block_header.number,
+                receipt_hash,
+                2,
+                None
+            )),
+            Some(DecodedLog {
+                log: receipts[0].receipt.logs[2].clone(),
+                params: None,
+            })
+        );
+        assert_eq!(
+            ReceiptRegistry::processed_logs((
+                GOERLI_CHAIN,
+                block_header.number,
+                receipt_hash,
+                0,
+                None
+            )),
+            None
+        );
+
+        // Proving the same log again is slashed from the bond.
+        assert_ok!(ReceiptRegistry::submit_log_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            log_proof(2, None)
+        ));
+        assert_eq!(bond_of_relayer(&ALICE), RELAYER_BOND - PROOF_DEPOSIT);
+
+        // The pool is empty, so a new log is stored without being rewarded or slashed.
+        assert_ok!(ReceiptRegistry::submit_log_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            log_proof(0, None)
+        ));
+        assert!(ReceiptRegistry::processed_logs((
+            GOERLI_CHAIN,
+            block_header.number,
+            receipt_hash,
+            0,
+            None
+        ))
+        .is_some());
+
+        // Decoding the same log with an ABI is stored next to it, neither rewarded nor slashed.
+        let abi = invoked(&[true, true, true, false]);
+        assert_ok!(ReceiptRegistry::submit_log_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            log_proof(0, Some(abi.clone()))
+        ));
+        assert_eq!(
+            ReceiptRegistry::processed_logs((
+                GOERLI_CHAIN,
+                block_header.number,
+                receipt_hash,
+                0,
+                Some(abi.id())
+            ))
+            .unwrap()
+            .params,
+            Some(vec![
+                AbiValue::Address(H160(hex_literal::hex!(
+                    "cf4cdbc0dbd6f5f3133709951ea9eb1064d0bc32"
+                ))),
+                AbiValue::Address(H160(hex_literal::hex!(
+                    "9831c60e7cbe37cb8b49de96006f60975125036b"
+                ))),
+                AbiValue::Uint(U256::from(10_000_000_000_000_000u64)),
+                AbiValue::Bytes(vec![]),
+            ])
+        );
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), PROOF_REWARD);
+        assert_eq!(bond_of_relayer(&ALICE), RELAYER_BOND - PROOF_DEPOSIT);
+        assert_eq!(
+            ReceiptRegistry::reward_pools(GOERLI_CHAIN, address)
+                .unwrap()
+                .balance,
+            0
+        );
+
+        let mut abi_ids =
+            ReceiptRegistry::decoded_logs(GOERLI_CHAIN, block_header.number, receipt_hash, 0)
+                .into_iter()
+                .map(|(abi_id, decoded_log)| {
+                    assert_eq!(decoded_log.log, receipts[0].receipt.logs[0]);
+                    abi_id
+                })
+                .collect::<Vec<_>>();
+        abi_ids.sort();
+        assert_eq!(abi_ids, vec![None, Some(abi.id())]);
+        assert_eq!(
+            ReceiptRegistry::decoded_logs(GOERLI_CHAIN, block_header.number, receipt_hash, 1),
+            vec![]
+        );
+
+        // Larger proofs weigh more.
+        let weight = |log_proof| {
+            pallet_receipt_registry::Call::<Test>::submit_log_proof {
+                typed_chain_id: GOERLI_CHAIN,
+                log_proof,
+            }
+            .get_dispatch_info()
+            .weight
+        };
+        assert_eq!(
+            weight(vec![0; 1000]),
+            weight(vec![]) + LOG_PROOF_BYTE_WEIGHT * 1000
+        );
+    });
+}
+
+#[test]
+pub fn test_submit_ancestry_proof() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+
+        let ancestry_proof = |ancestry: Vec<types::BlockHeader>| {
+            let proof = AncestryProof {
+                event_proof: EventProof {
+                    block_header: block_header.clone(),
+                    block_hash,
+                    transaction_receipt: receipts[0].clone(),
+                    transaction_receipt_hash: H256::hash(&receipts[0]),
+                    merkle_proof_of_receipt: create_proof(&receipts, 0),
+                },
+                ancestry,
+            };
+            serde_json::to_vec(&proof).unwrap()
+        };
+        let descendants = headers[0][1..4]
+            .iter()
+            .cloned()
+            .map(block_header_convert)
+            .collect::<Vec<_>>();
+
+        assert_err!(
+            ReceiptRegistry::submit_ancestry_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                ancestry_proof(descendants.clone())
+            ),
+            Error::<Test>::AncestryTooLong
+        );
+
+        // Only the initial header is finalized by the light client.
+        assert_err!(
+            ReceiptRegistry::submit_ancestry_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                ancestry_proof(descendants[..1].to_vec())
+            ),
+            Error::<Test>::HeaderHashDoesNotExist
+        );
+
+        assert_ok!(ReceiptRegistry::submit_ancestry_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            ancestry_proof(vec![])
+        ));
+        assert_eq!(
+            ReceiptRegistry::processed_receipts((
+                GOERLI_CHAIN,
+                block_header.number,
+                H256::hash(&receipts[0])
+            )),
+            Some(receipts[0].receipt.logs.clone())
+        );
+    });
+}
+
+#[test]
+pub fn test_submit_ancestry_proof_of_unfinalized_block() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        // The light client starts from a later header, so the block of the receipt is only
+        // linked to it by the parent hashes of its descendants.
+        let (headers, _updates, mut init_input) = get_test_data(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+        let anchor = headers[0][2].clone();
+        init_input.finalized_execution_header = anchor.clone();
+        assert_ok!(Eth2Client::init(
+            RuntimeOrigin::signed(ALICE.clone()),
+            GOERLI_CHAIN,
+            Box::new(init_input.map_into())
+        ));
+        assert_eq!(Eth2Client::last_block_number(GOERLI_CHAIN), anchor.number);
+
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+        let receipt_hash = H256::hash(&receipts[0]);
+        let event_proof = || EventProof {
+            block_header: block_header.clone(),
+            block_hash,
+            transaction_receipt: receipts[0].clone(),
+            transaction_receipt_hash: receipt_hash,
+            merkle_proof_of_receipt: create_proof(&receipts, 0),
+        };
+        let ancestry = headers[0][1..=2]
+            .iter()
+            .cloned()
+            .map(block_header_convert)
+            .collect::<Vec<_>>();
+
+        // The block itself can't be proven directly.
+        assert_err!(
+            ReceiptRegistry::submit_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                serde_json::to_vec(&event_proof()).unwrap()
+            ),
+            Error::<Test>::HeaderHashDoesNotExist
+        );
+
+        // A broken link is rejected even though the anchor is finalized.
+        let mut broken = ancestry.clone();
+        broken[0].parent_hash = H256([1; 32]);
+        assert_err!(
+            ReceiptRegistry::submit_ancestry_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                serde_json::to_vec(&AncestryProof {
+                    event_proof: event_proof(),
+                    ancestry: broken,
+                })
+                .unwrap()
+            ),
+            Error::<Test>::VerifyProofFail
+        );
+
+        assert_ok!(ReceiptRegistry::submit_ancestry_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            serde_json::to_vec(&AncestryProof {
+                event_proof: event_proof(),
+                ancestry,
+            })
+            .unwrap()
+        ));
+        assert_eq!(
+            ReceiptRegistry::processed_receipts((GOERLI_CHAIN, block_header.number, receipt_hash)),
+            Some(receipts[0].receipt.logs.clone())
+        );
+        assert_eq!(ReceiptRegistry::relayers(ALICE).unwrap().proofs_accepted, 1);
+    });
+}
+
+#[test]
+pub fn test_relayer_registration_and_rewards() {
+    new_test_ext().execute_with(|| {
+        assert_err!(
+            ReceiptRegistry::submit_proof(RuntimeOrigin::signed(BOB), GOERLI_CHAIN, vec![1]),
+            Error::<Test>::NotRegisteredRelayer
+        );
+        assert_err!(
+            ReceiptRegistry::register_relayer(RuntimeOrigin::signed(BOB), RELAYER_BOND - 1),
+            Error::<Test>::BondTooLow
+        );
+
+        let balance_before = balance_of_user(&BOB);
+        register_relayer(&BOB);
+        assert_eq!(balance_of_user(&BOB), balance_before - RELAYER_BOND);
+        assert_eq!(System::account(&BOB).data.reserved, RELAYER_BOND);
+
+        // Topping up the bond keeps the statistics.
+        assert_ok!(ReceiptRegistry::register_relayer(
+            RuntimeOrigin::signed(BOB),
+            1
+        ));
+        assert_eq!(bond_of_relayer(&BOB), RELAYER_BOND + 1);
+
+        assert_err!(
+            ReceiptRegistry::claim_rewards(RuntimeOrigin::signed(BOB)),
+            Error::<Test>::NoRewards
+        );
+
+        let balance_before = balance_of_user(&BOB);
+        assert_ok!(ReceiptRegistry::unregister_relayer(RuntimeOrigin::signed(
+            BOB
+        )));
+        assert_eq!(balance_of_user(&BOB), balance_before + RELAYER_BOND + 1);
+        assert_eq!(ReceiptRegistry::relayers(&BOB), None);
+        assert_err!(
+            ReceiptRegistry::unregister_relayer(RuntimeOrigin::signed(BOB)),
+            Error::<Test>::NotRegisteredRelayer
+        );
+    });
+}
+
+#[test]
+pub fn test_reward_pools() {
+    new_test_ext().execute_with(|| {
+        let address = H160::from_slice(&[1u8; 20]);
+
+        assert_err!(
+            ReceiptRegistry::fund_reward_pool(
+                RuntimeOrigin::signed(BOB),
+                GOERLI_CHAIN,
+                address,
+                10
+            ),
+            Error::<Test>::ContractNotWatched
+        );
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+
+        let balance_before = balance_of_user(&BOB);
+        create_reward_pool(address, 10, 2);
+        assert_eq!(balance_of_user(&BOB), balance_before - 10);
+
+        // Anyone can top up the pool, but only the owner can configure it.
+        assert_ok!(ReceiptRegistry::fund_reward_pool(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            address,
+            5
+        ));
+        assert_err!(
+            ReceiptRegistry::set_receipt_reward(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                address,
+                1
+            ),
+            Error::<Test>::NotRewardPoolOwner
+        );
+        assert_err!(
+            ReceiptRegistry::withdraw_reward_pool(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                address,
+                1
+            ),
+            Error::<Test>::NotRewardPoolOwner
+        );
+
+        let pool = ReceiptRegistry::reward_pools(GOERLI_CHAIN, address).unwrap();
+        assert_eq!(pool.owner, BOB);
+        assert_eq!(pool.balance, 15);
+        assert_eq!(pool.reward, 2);
+
+        assert_err!(
+            ReceiptRegistry::withdraw_reward_pool(
+                RuntimeOrigin::signed(BOB),
+                GOERLI_CHAIN,
+                address,
+                16
+            ),
+            Error::<Test>::InsufficientPoolBalance
+        );
+        let balance_before = balance_of_user(&BOB);
+        assert_ok!(ReceiptRegistry::withdraw_reward_pool(
+            RuntimeOrigin::signed(BOB),
+            GOERLI_CHAIN,
+            address,
+            15
+        ));
+        assert_eq!(balance_of_user(&BOB), balance_before + 15);
+        assert_eq!(
+            ReceiptRegistry::reward_pools(GOERLI_CHAIN, address)
+                .unwrap()
+                .balance,
+            0
+        );
+
+        assert_err!(
+            ReceiptRegistry::set_receipt_reward(
+                RuntimeOrigin::signed(BOB),
+                MAINNET_CHAIN,
+                address,
+                1
+            ),
+            Error::<Test>::NoRewardPool
+        );
+    });
+}
+
+#[test]
+pub fn test_chain_registration() {
+    new_test_ext().execute_with(|| {
+        const CHAIN: TypedChainId = TypedChainId::Evm(10);
+        let address = H160::from_slice(&[1u8; 20]);
+
+        assert_err!(
+            ReceiptRegistry::register_chain(
+                RuntimeOrigin::root(),
+                GOERLI_CHAIN,
+                Default::default()
+            ),
+            Error::<Test>::ChainAlreadyRegistered
+        );
+        // Contracts couldn't tell both chains apart.
+        assert_err!(
+            ReceiptRegistry::register_chain(
+                RuntimeOrigin::root(),
+                TypedChainId::Substrate(5),
+                Default::default()
+            ),
+            Error::<Test>::ChainIdAlreadyRegistered
+        );
+        assert_eq!(ReceiptRegistry::registered_chain(5), Some(GOERLI_CHAIN));
+        assert_err!(
+            ReceiptRegistry::update_watching_address(RuntimeOrigin::root(), CHAIN, address, true),
+            Error::<Test>::ChainNotRegistered
+        );
+        assert_err!(
+            ReceiptRegistry::update_proof_fee(RuntimeOrigin::root(), CHAIN, 1, 3),
+            Error::<Test>::ChainNotRegistered
+        );
+
+        let settings = ChainSettings {
+            enabled: false,
+            default_reward: 3,
+            ..Default::default()
+        };
+        assert_ok!(ReceiptRegistry::register_chain(
+            RuntimeOrigin::root(),
+            CHAIN,
+            settings.clone()
+        ));
+        assert_eq!(
+            ReceiptRegistry::registered_chains(CHAIN),
+            Some(settings.clone())
+        );
+        assert_err!(
+            ReceiptRegistry::update_watching_address(RuntimeOrigin::root(), CHAIN, address, true),
+            Error::<Test>::ChainDisabled
+        );
+
+        let settings = ChainSettings {
+            enabled: true,
+            ..settings
+        };
+        assert_ok!(ReceiptRegistry::update_chain_settings(
+            RuntimeOrigin::root(),
+            CHAIN,
+            settings.clone()
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            CHAIN,
+            address,
+            true
+        ));
+        assert_ok!(ReceiptRegistry::update_proof_fee(
+            RuntimeOrigin::root(),
+            CHAIN,
+            1,
+            3
+        ));
+        assert_eq!(ReceiptRegistry::proof_deposit(CHAIN), 1);
+        assert_eq!(ReceiptRegistry::proof_reward(CHAIN), 3);
+
+        // New pools start with the default reward of the chain.
+        assert_ok!(ReceiptRegistry::fund_reward_pool(
+            RuntimeOrigin::signed(BOB),
+            CHAIN,
+            address,
+            10
+        ));
+        assert_eq!(
+            ReceiptRegistry::reward_pools(CHAIN, address)
+                .unwrap()
+                .reward,
+            3
+        );
+
+        assert_ok!(ReceiptRegistry::deregister_chain(
+            RuntimeOrigin::root(),
+            CHAIN
+        ));
+        assert_eq!(ReceiptRegistry::registered_chains(CHAIN), None);
+        assert_eq!(ReceiptRegistry::watched_contracts(CHAIN), None);
+        assert_err!(
+            ReceiptRegistry::update_chain_settings(RuntimeOrigin::root(), CHAIN, settings),
+            Error::<Test>::ChainNotRegistered
+        );
+
+        // The chain id is free again.
+        assert_ok!(ReceiptRegistry::register_chain(
+            RuntimeOrigin::root(),
+            TypedChainId::Substrate(10),
+            Default::default()
+        ));
+        assert_eq!(
+            ReceiptRegistry::registered_chain(10),
+            Some(TypedChainId::Substrate(10))
+        );
+    });
+}
+
+#[test]
+pub fn test_chain_settings_are_enforced() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+        let receipt_hash = H256::hash(&receipts[0]);
+        let proof = serde_json::to_vec(&EventProof {
+            block_header: block_header.clone(),
+            block_hash,
+            transaction_receipt: receipts[0].clone(),
+            transaction_receipt_hash: receipt_hash,
+            merkle_proof_of_receipt: create_proof(&receipts, 0),
+        })
+        .unwrap();
+
+        // The proven block is the head of the light client.
+        for (settings, error) in [
+            (
+                ChainSettings {
+                    paused: true,
+                    ..Default::default()
+                },
+                Error::<Test>::ChainPaused,
+            ),
+            (
+                ChainSettings {
+                    enabled: false,
+                    ..Default::default()
+                },
+                Error::<Test>::ChainDisabled,
+            ),
+            (
+                ChainSettings {
+                    min_confirmations: 1,
+                    ..Default::default()
+                },
+                Error::<Test>::NotEnoughConfirmations,
+            ),
+            (
+                ChainSettings {
+                    retention: Some(0),
+                    ..Default::default()
+                },
+                Error::<Test>::BlockOutsideRetention,
+            ),
+        ] {
+            assert_ok!(ReceiptRegistry::update_chain_settings(
+                RuntimeOrigin::root(),
+                GOERLI_CHAIN,
+                settings
+            ));
+            assert_err!(
+                ReceiptRegistry::submit_proof(
+                    RuntimeOrigin::signed(ALICE),
+                    GOERLI_CHAIN,
+                    proof.clone()
+                ),
+                error
+            );
+        }
+
+        assert_ok!(ReceiptRegistry::update_chain_settings(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            ChainSettings {
+                retention: Some(1),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(ReceiptRegistry::submit_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            proof
+        ));
+        assert_eq!(
+            ReceiptRegistry::prune_cursor(GOERLI_CHAIN),
+            Some(block_header.number)
+        );
+
+        // Still within the retention.
+        ReceiptRegistry::on_idle(1, Weight::MAX);
+        assert_eq!(
+            ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash),
+            Some(())
+        );
+
+        assert_ok!(ReceiptRegistry::update_chain_settings(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            ChainSettings {
+                retention: Some(0),
+                ..Default::default()
+            }
+        ));
+        ReceiptRegistry::on_idle(1, Weight::MAX);
+        assert_eq!(
+            ReceiptRegistry::processed_receipts((GOERLI_CHAIN, block_header.number, receipt_hash)),
+            None
+        );
+        assert_eq!(
+            ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash),
+            None
+        );
+        assert_eq!(
+            ReceiptRegistry::contract_receipts((GOERLI_CHAIN, address, block_header.number)),
+            vec![]
+        );
+        assert_eq!(
+            ReceiptRegistry::prune_cursor(GOERLI_CHAIN),
+            Some(block_header.number + 1)
+        );
+        // Every chain has been pruned, the next pruning starts from the first one.
+        assert_eq!(ReceiptRegistry::prune_chain_cursor(), None);
+    });
+}
+
+#[test]
+pub fn test_callbacks() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+        let merkle_proof_of_receipt = create_proof(&receipts, 0);
+        let proof = EventProof {
+            block_header,
+            block_hash,
+            transaction_receipt: receipts[0].clone(),
+            transaction_receipt_hash: H256::hash(&receipts[0]),
+            merkle_proof_of_receipt,
+        };
+        let logs = proof.transaction_receipt.receipt.logs.clone();
+        let (topic, failing_topic) = (logs[0].topics[0], logs[0].topics[1]);
+
+        const WEIGHT_LIMIT: u64 = 2 * CALLBACK_WEIGHT;
+        const DEPOSIT: u128 = 10_000;
+        let register = |owner: AccountId32, topic, selector, weight_limit| {
+            ReceiptRegistry::register_callback(
+                RuntimeOrigin::signed(owner.clone()),
+                GOERLI_CHAIN,
+                address,
+                topic,
+                CallbackTarget::Ink {
+                    contract: owner,
+                    selector,
+                },
+                Weight::from_parts(weight_limit, 0),
+                DEPOSIT,
+            )
+        };
+
+        assert_err!(
+            register(BOB, topic, [1u8; 4], WEIGHT_LIMIT),
+            Error::<Test>::ContractNotWatched
+        );
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+        // Only the owner of the reward pool of the contract can register callbacks.
+        assert_err!(
+            register(BOB, topic, [1u8; 4], WEIGHT_LIMIT),
+            Error::<Test>::NotRewardPoolOwner
+        );
+        create_reward_pool(address, 10, 0);
+        assert_err!(
+            register(ALICE, topic, [1u8; 4], WEIGHT_LIMIT),
+            Error::<Test>::NotRewardPoolOwner
+        );
+        assert_err!(
+            register(BOB, topic, [1u8; 4], 100 * WEIGHT_LIMIT),
+            Error::<Test>::CallbackWeightTooHigh
+        );
+        assert_ok!(register(BOB, topic, [1u8; 4], WEIGHT_LIMIT));
+        assert_ok!(register(BOB, failing_topic, FAILING_SELECTOR, WEIGHT_LIMIT));
+        assert_eq!(System::account(&BOB).data.reserved, 2 * DEPOSIT);
+
+        let post_info = ReceiptRegistry::submit_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            serde_json::to_string(&proof).unwrap().into(),
+        )
+        .unwrap();
+
+        // Every watched log having a registered topic is passed to its callback, up to
+        // `MaxCallbacksPerReceipt` calls.
+        let block_number = proof.block_header.number;
+        let receipt_hash = proof.transaction_receipt_hash;
+        let calls = |selector, topic| {
+            logs.iter()
+                .filter(|log| log.address == address && log.topics.contains(&topic))
+                .map(|log| {
+                    (
+                        selector,
+                        GOERLI_CHAIN,
+                        block_number,
+                        receipt_hash,
+                        log.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let (ok_calls, mut failed_calls) = (
+            calls([1u8; 4], topic),
+            calls(FAILING_SELECTOR, failing_topic),
+        );
+        assert_eq!((ok_calls.len(), failed_calls.len()), (2, 2));
+        // The logs come in order, each with its two callbacks, so the last one is skipped.
+        assert_eq!(MaxCallbacksPerReceipt::get(), 3);
+        failed_calls.truncate(1);
+        let mut recorded = CallbackRecorder::calls();
+        recorded.sort_by_key(|call| call.0);
+        assert_eq!(recorded, [ok_calls.clone(), failed_calls.clone()].concat());
+
+        // The weight of the callbacks is charged to the call, within its declared weight.
+        assert_eq!(
+            post_info.actual_weight,
+            Some(Weight::from_parts(
+                6 + 2 * PROVED_LOG_WEIGHT + 3 * CALLBACK_WEIGHT,
+                0
+            ))
+        );
+        assert!(post_info.actual_weight.unwrap().all_lte(
+            pallet_receipt_registry::Call::<Test>::submit_proof {
+                typed_chain_id: GOERLI_CHAIN,
+                event_proof: vec![],
+            }
+            .get_dispatch_info()
+            .weight
+        ));
+
+        // The changes of the failed callback are reverted, but its weight is still paid for.
+        assert_eq!(
+            frame_support::storage::unhashed::get::<bool>(&[1u8; 4]),
+            Some(true)
+        );
+        assert_eq!(
+            frame_support::storage::unhashed::get::<bool>(&FAILING_SELECTOR),
+            None
+        );
+        let budget = |topic| {
+            ReceiptRegistry::callbacks((GOERLI_CHAIN, address, topic))
+                .unwrap()
+                .budget
+        };
+        let spent = |calls: &Vec<_>| calls.len() as u128 * CALLBACK_WEIGHT as u128;
+        assert_eq!(budget(topic), DEPOSIT - spent(&ok_calls));
+        assert_eq!(budget(failing_topic), DEPOSIT - spent(&failed_calls));
+        assert_eq!(
+            System::account(&BOB).data.reserved,
+            2 * DEPOSIT - spent(&ok_calls) - spent(&failed_calls)
+        );
+        assert!(ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash).is_some());
+
+        assert_err!(
+            ReceiptRegistry::remove_callback(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                address,
+                topic
+            ),
+            Error::<Test>::NotCallbackOwner
+        );
+        assert_ok!(ReceiptRegistry::remove_callback(
+            RuntimeOrigin::signed(BOB),
+            GOERLI_CHAIN,
+            address,
+            topic
+        ));
+        assert_eq!(
+            ReceiptRegistry::callbacks((GOERLI_CHAIN, address, topic)),
+            None
+        );
+        assert_eq!(
+            System::account(&BOB).data.reserved,
+            DEPOSIT - spent(&failed_calls)
+        );
+        assert_err!(
+            ReceiptRegistry::remove_callback(
+                RuntimeOrigin::signed(BOB),
+                GOERLI_CHAIN,
+                address,
+                topic
+            ),
+            Error::<Test>::NoCallback
+        );
+    });
+}
+
+#[test]
+pub fn test_challenge_period() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        const PROOF_DEPOSIT: u128 = 5;
+        const PROOF_REWARD: u128 = 2;
+        const CHALLENGE_PERIOD: u32 = 2;
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+        assert_ok!(ReceiptRegistry::update_chain_settings(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            ChainSettings {
+                proof_deposit: PROOF_DEPOSIT,
+                challenge_period: CHALLENGE_PERIOD,
+                ..Default::default()
+            }
+        ));
+        create_reward_pool(address, 10 * PROOF_REWARD, PROOF_REWARD);
+        // Keeps the bond above the minimum after the slashes.
+        assert_ok!(ReceiptRegistry::register_relayer(
+            RuntimeOrigin::signed(ALICE),
+            2 * PROOF_DEPOSIT
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+        let receipt_hash = H256::hash(&receipts[0]);
+        let proof = serde_json::to_vec(&EventProof {
+            block_header,
+            block_hash,
+            transaction_receipt: receipts[0].clone(),
+            transaction_receipt_hash: receipt_hash,
+            merkle_proof_of_receipt: create_proof(&receipts, 0),
+        })
+        .unwrap();
+        let submit = || {
+            ReceiptRegistry::submit_proof(RuntimeOrigin::signed(ALICE), GOERLI_CHAIN, proof.clone())
+        };
+
+        System::set_block_number(1);
+        // Storing the receipt later is charged to the proof.
+        let post_info = submit().unwrap();
+        assert_eq!(
+            post_info.actual_weight,
+            Some(Weight::from_parts(6, 0).saturating_add(ReceiptRegistry::max_store_weight()))
+        );
+
+        // The receipt is pending, and nothing is stored or paid yet.
+        let pending = ReceiptRegistry::pending_receipts(GOERLI_CHAIN, receipt_hash).unwrap();
+        let challenge_end = 1 + CHALLENGE_PERIOD as u64;
+        assert_eq!(pending.relayer, ALICE);
+        assert_eq!(pending.contracts, vec![address]);
+        assert_eq!(pending.challenge_end, challenge_end);
+        assert_eq!(
+            ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash),
+            None
+        );
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), 0);
+        assert_eq!(ProvedReceiptsRecorder::proved(), vec![]);
+
+        // Proving the pending receipt again is slashed as a duplicate.
+        let bond = bond_of_relayer(&ALICE);
+        assert_ok!(submit());
+        assert_eq!(bond_of_relayer(&ALICE), bond - PROOF_DEPOSIT);
+
+        // The block is still finalized, so only the privileged origin can drop the receipt.
+        assert_err!(
+            ReceiptRegistry::dispute_receipt(
+                RuntimeOrigin::signed(BOB),
+                GOERLI_CHAIN,
+                receipt_hash
+            ),
+            Error::<Test>::ReceiptNotDisputable
+        );
+        assert_err!(
+            ReceiptRegistry::unregister_relayer(RuntimeOrigin::signed(ALICE)),
+            Error::<Test>::RelayerHasPendingReceipts
+        );
+        assert_ok!(ReceiptRegistry::dispute_receipt(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            receipt_hash
+        ));
+        assert_eq!(
+            ReceiptRegistry::pending_receipts(GOERLI_CHAIN, receipt_hash),
+            None
+        );
+        assert_eq!(ReceiptRegistry::pending_proofs(ALICE), 0);
+        assert_eq!(bond_of_relayer(&ALICE), bond - 2 * PROOF_DEPOSIT);
+        assert_eq!(ReceiptRegistry::relayers(ALICE).unwrap().proofs_rejected, 2);
+        assert_err!(
+            ReceiptRegistry::dispute_receipt(RuntimeOrigin::root(), GOERLI_CHAIN, receipt_hash),
+            Error::<Test>::NoPendingReceipt
+        );
+
+        // Proven again later, the receipt is stored once its own period ends.
+        System::set_block_number(2);
+        assert_ok!(submit());
+        for block_number in 1..=challenge_end {
+            System::set_block_number(block_number);
+            ReceiptRegistry::on_initialize(block_number);
+            assert_eq!(
+                ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash),
+                None
+            );
+        }
+        System::set_block_number(challenge_end + 1);
+        ReceiptRegistry::on_initialize(challenge_end + 1);
+
+        assert_eq!(
+            ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash),
+            Some(())
+        );
+        assert_eq!(
+            ReceiptRegistry::pending_receipts(GOERLI_CHAIN, receipt_hash),
+            None
+        );
+        assert_eq!(ReceiptRegistry::pending_proofs(ALICE), 0);
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), PROOF_REWARD);
+        assert_eq!(ReceiptRegistry::relayers(ALICE).unwrap().proofs_accepted, 1);
+        assert_eq!(ProvedReceiptsRecorder::proved().len(), 1);
+
+        // Single logs would skip the challenge period.
+        let log_proof = serde_json::to_vec(&LogProof {
+            event_proof: EventProof {
+                block_header: block_header_convert(headers[0][0].clone()),
+                block_hash,
+                transaction_receipt: receipts[0].clone(),
+                transaction_receipt_hash: receipt_hash,
+                merkle_proof_of_receipt: create_proof(&receipts, 0),
+            },
+            log_index: 0,
+            abi: None,
+        })
+        .unwrap();
+        assert_err!(
+            ReceiptRegistry::submit_log_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                log_proof
+            ),
+            Error::<Test>::ChainHasChallengePeriod
+        );
+    });
+}
+
+#[test]
+pub fn test_pending_receipts_carried_over() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        const PROOF_REWARD: u128 = 2;
+        let addresses = [
+            H160(hex_literal::hex!(
+                "228612206ba22b5af70b6812cb722dfe508a83ef"
+            )),
+            H160(hex_literal::hex!(
+                "5a94dc6cc85fda49d8e9a8b85dde8629025c42be"
+            )),
+            H160(hex_literal::hex!(
+                "b696ecc2e1e1435d8e9ef26ac26255e5ae559927"
+            )),
+        ];
+        for address in addresses {
+            assert_ok!(ReceiptRegistry::update_watching_address(
+                RuntimeOrigin::root(),
+                GOERLI_CHAIN,
+                address,
+                true
+            ));
+            create_reward_pool(address, 10 * PROOF_REWARD, PROOF_REWARD);
+        }
+        assert_ok!(ReceiptRegistry::update_chain_settings(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            ChainSettings {
+                challenge_period: 1,
+                ..Default::default()
+            }
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+
+        // One receipt more than `MaxPendingReceiptsPerBlock` ends its challenge period at block 2.
+        System::set_block_number(1);
+        let receipt_hashes = [0, 2, 4].map(|index| {
+            let proof = serde_json::to_vec(&EventProof {
+                block_header: block_header.clone(),
+                block_hash,
+                transaction_receipt: receipts[index].clone(),
+                transaction_receipt_hash: H256::hash(&receipts[index]),
+                merkle_proof_of_receipt: create_proof(&receipts, index),
+            })
+            .unwrap();
+            assert_ok!(ReceiptRegistry::submit_proof(
+                RuntimeOrigin::signed(ALICE),
+                GOERLI_CHAIN,
+                proof
+            ));
+            H256::hash(&receipts[index])
+        });
+        assert_eq!(ReceiptRegistry::pending_proofs(ALICE), 3);
+        let stored = || {
+            receipt_hashes
+                .iter()
+                .filter(|hash| {
+                    ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, **hash).is_some()
+                })
+                .count()
+        };
+
+        System::set_block_number(2);
+        ReceiptRegistry::on_initialize(2);
+        assert_eq!(stored(), MaxPendingReceiptsPerBlock::get() as usize);
+        assert_eq!(ReceiptRegistry::challenge_queue_cursor(), Some(2));
+
+        // The last receipt is stored in the next block.
+        System::set_block_number(3);
+        ReceiptRegistry::on_initialize(3);
+        assert_eq!(stored(), 3);
+        assert_eq!(ReceiptRegistry::challenge_queue_cursor(), None);
+        assert_eq!(ReceiptRegistry::pending_proofs(ALICE), 0);
+        assert_eq!(ReceiptRegistry::pending_rewards(ALICE), 3 * PROOF_REWARD);
+    });
+}
+
+#[test]
+pub fn test_dispute_ancestry_proven_receipt() {
+    use frame_support::{storage_alias, Blake2_128Concat};
+
+    #[storage_alias]
+    type FinalizedExecutionBlocks =
+        StorageDoubleMap<Eth2Client, Blake2_128Concat, TypedChainId, Blake2_128Concat, u64, H256>;
+
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        // The block of the receipt isn't known to the light client, only its descendant is.
+        let (headers, _updates, mut init_input) = get_test_data(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+        let anchor = headers[0][2].clone();
+        init_input.finalized_execution_header = anchor.clone();
+        assert_ok!(Eth2Client::init(
+            RuntimeOrigin::signed(ALICE.clone()),
+            GOERLI_CHAIN,
+            Box::new(init_input.map_into())
+        ));
+
+        const PROOF_DEPOSIT: u128 = 5;
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+        assert_ok!(ReceiptRegistry::update_chain_settings(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            ChainSettings {
+                proof_deposit: PROOF_DEPOSIT,
+                challenge_period: 2,
+                ..Default::default()
+            }
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+        let receipt_hash = H256::hash(&receipts[0]);
+        let ancestry = headers[0][1..=2]
+            .iter()
+            .cloned()
+            .map(block_header_convert)
+            .collect::<Vec<_>>();
+        let anchor_hash = H256::hash(ancestry[1].clone());
+
+        System::set_block_number(1);
+        assert_ok!(ReceiptRegistry::submit_ancestry_proof(
+            RuntimeOrigin::signed(ALICE),
+            GOERLI_CHAIN,
+            serde_json::to_vec(&AncestryProof {
+                event_proof: EventProof {
+                    block_header: block_header.clone(),
+                    block_hash,
+                    transaction_receipt: receipts[0].clone(),
+                    transaction_receipt_hash: receipt_hash,
+                    merkle_proof_of_receipt: create_proof(&receipts, 0),
+                },
+                ancestry,
+            })
+            .unwrap()
+        ));
+        let pending = ReceiptRegistry::pending_receipts(GOERLI_CHAIN, receipt_hash).unwrap();
+        assert_eq!(pending.anchor, (anchor.number, anchor_hash));
+
+        // The block of the receipt itself not being finalized doesn't make the proof wrong.
+        assert_err!(
+            ReceiptRegistry::dispute_receipt(
+                RuntimeOrigin::signed(BOB),
+                GOERLI_CHAIN,
+                receipt_hash
+            ),
+            Error::<Test>::ReceiptNotDisputable
+        );
+
+        // Neither does the anchor being pruned from the light client.
+        FinalizedExecutionBlocks::remove(GOERLI_CHAIN, anchor.number);
+        assert_err!(
+            ReceiptRegistry::dispute_receipt(
+                RuntimeOrigin::signed(BOB),
+                GOERLI_CHAIN,
+                receipt_hash
+            ),
+            Error::<Test>::ReceiptNotDisputable
+        );
+
+        // Another block finalized in place of the anchor does.
+        FinalizedExecutionBlocks::insert(GOERLI_CHAIN, anchor.number, H256([1; 32]));
+        let bond = bond_of_relayer(&ALICE);
+        assert_ok!(ReceiptRegistry::dispute_receipt(
+            RuntimeOrigin::signed(BOB),
+            GOERLI_CHAIN,
+            receipt_hash
+        ));
+        assert_eq!(
+            ReceiptRegistry::pending_receipts(GOERLI_CHAIN, receipt_hash),
+            None
+        );
+        assert_eq!(bond_of_relayer(&ALICE), bond - PROOF_DEPOSIT);
+    });
+}
+
+#[test]
+pub fn test_pause() {
+    new_test_ext().execute_with(|| {
+        register_relayer(&ALICE);
+
+        let (headers, _updates, _init_input) = get_test_context(Some(InitOptions {
+            validate_updates: true,
+            verify_bls_signatures: true,
+            hashes_gc_threshold: 7100,
+            trusted_signer: Some([2u8; 32]),
+        }));
+
+        let address = H160(hex_literal::hex!(
+            "228612206ba22b5af70b6812cb722dfe508a83ef"
+        ));
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            address,
+            true
+        ));
+
+        let block_header = block_header_convert(headers[0][0].clone());
+        let block_hash = H256::hash(block_header.clone());
+        let receipts = common::load_receipts(include_str!("./data/goerli/receipts_8652100.json"));
+        let receipt_hash = H256::hash(&receipts[0]);
+        let proof = serde_json::to_vec(&EventProof {
+            block_header,
+            block_hash,
+            transaction_receipt: receipts[0].clone(),
+            transaction_receipt_hash: receipt_hash,
+            merkle_proof_of_receipt: create_proof(&receipts, 0),
+        })
+        .unwrap();
+        let submit = || {
+            ReceiptRegistry::submit_proof(RuntimeOrigin::signed(ALICE), GOERLI_CHAIN, proof.clone())
+        };
+
+        assert_err!(
+            ReceiptRegistry::pause(RuntimeOrigin::signed(ALICE), None),
+            frame_support::sp_runtime::DispatchError::BadOrigin
+        );
+        assert_err!(
+            ReceiptRegistry::pause(RuntimeOrigin::root(), Some(TypedChainId::Evm(10))),
+            Error::<Test>::ChainNotRegistered
+        );
+
+        // Lifting the pause of every chain keeps the chains paused on their own.
+        assert_ok!(ReceiptRegistry::pause(RuntimeOrigin::root(), None));
+        assert_ok!(ReceiptRegistry::pause(
+            RuntimeOrigin::root(),
+            Some(GOERLI_CHAIN)
+        ));
+        assert!(ReceiptRegistry::is_paused(MAINNET_CHAIN));
+        assert_err!(submit(), Error::<Test>::RegistryPaused);
+
+        assert_ok!(ReceiptRegistry::unpause(RuntimeOrigin::root(), None));
+        assert!(!ReceiptRegistry::is_paused(MAINNET_CHAIN));
+        assert!(ReceiptRegistry::is_paused(GOERLI_CHAIN));
+        assert_err!(submit(), Error::<Test>::ChainPaused);
+
+        assert_ok!(ReceiptRegistry::unpause(
+            RuntimeOrigin::root(),
+            Some(GOERLI_CHAIN)
+        ));
+        assert!(!ReceiptRegistry::is_paused(GOERLI_CHAIN));
+        assert_ok!(submit());
+        assert_eq!(
+            ReceiptRegistry::processed_receipts_hash(GOERLI_CHAIN, receipt_hash),
+            Some(())
+        );
+    });
+}
+
+#[test]
+pub fn test_pause_on_finality_stall() {
+    new_test_ext().execute_with(|| {
+        let _ = get_test_context(None);
+        let stall = <Test as pallet_receipt_registry::Config>::MaxFinalityStall::get() as u64;
+        let head = Eth2Client::last_block_number(GOERLI_CHAIN);
+
+        let run_to = |to: u64| {
+            for block_number in System::block_number() + 1..=to {
+                System::set_block_number(block_number);
+                ReceiptRegistry::on_initialize(block_number);
+            }
+        };
+
+        run_to(1);
+        assert_eq!(
+            ReceiptRegistry::finality_progress(GOERLI_CHAIN),
+            Some((head, 1))
+        );
+        run_to(stall);
+        assert!(!ReceiptRegistry::is_paused(GOERLI_CHAIN));
+
+        run_to(stall + 1);
+        assert!(ReceiptRegistry::is_paused(GOERLI_CHAIN));
+        assert_eq!(ReceiptRegistry::finality_progress(GOERLI_CHAIN), None);
+        // Chains without an initialized light client are left alone.
+        assert!(!ReceiptRegistry::is_paused(MAINNET_CHAIN));
+        assert_eq!(ReceiptRegistry::finality_progress(MAINNET_CHAIN), None);
+
+        // Once unpaused, the light client has `MaxFinalityStall` blocks again to make progress.
+        assert_ok!(ReceiptRegistry::unpause(
+            RuntimeOrigin::root(),
+            Some(GOERLI_CHAIN)
+        ));
+        run_to(2 * stall + 1);
+        assert!(!ReceiptRegistry::is_paused(GOERLI_CHAIN));
+        run_to(2 * stall + 2);
+        assert!(ReceiptRegistry::is_paused(GOERLI_CHAIN));
+    });
+}
+
+#[test]
+pub fn test_finality_checks_take_turns() {
+    new_test_ext().execute_with(|| {
+        let _ = get_test_context(None);
+        for typed_chain_id in [TypedChainId::Evm(10), TypedChainId::Evm(11)] {
+            assert_ok!(ReceiptRegistry::register_chain(
+                RuntimeOrigin::root(),
+                typed_chain_id,
+                Default::default()
+            ));
+        }
+
+        // Four chains are checked two by two, so the cursor comes back every other block.
+        let mut cursors = Vec::new();
+        for block_number in 1..=4 {
+            System::set_block_number(block_number);
+            ReceiptRegistry::on_initialize(block_number);
+            cursors.push(ReceiptRegistry::finality_check_cursor().unwrap());
+        }
+        assert_ne!(cursors[0], cursors[1]);
+        assert_eq!(cursors[0], cursors[2]);
+        assert_eq!(cursors[1], cursors[3]);
+        let (_, since) = ReceiptRegistry::finality_progress(GOERLI_CHAIN).unwrap();
+        assert!(since <= 2);
+    });
+}
+
+#[test]
+pub fn test_migration_to_v1() {
+    use frame_support::{storage_alias, Blake2_128Concat};
+
+    #[storage_alias]
+    type WatchedContracts = StorageMap<ReceiptRegistry, Blake2_128Concat, TypedChainId, Vec<H160>>;
+    #[storage_alias]
+    type ProofDeposit = StorageMap<ReceiptRegistry, Blake2_128Concat, TypedChainId, u128>;
+    #[storage_alias]
+    type ProofReward = StorageMap<ReceiptRegistry, Blake2_128Concat, TypedChainId, u128>;
+
+    new_test_ext().execute_with(|| {
+        const WATCHED_CHAIN: TypedChainId = TypedChainId::Evm(10);
+        const FEE_CHAIN: TypedChainId = TypedChainId::Evm(11);
+        const REWARD_CHAIN: TypedChainId = TypedChainId::Evm(12);
+
+        WatchedContracts::insert(WATCHED_CHAIN, vec![H160::from_slice(&[1u8; 20])]);
+        ProofDeposit::insert(FEE_CHAIN, 7);
+        ProofDeposit::insert(MAINNET_CHAIN, 8);
+        ProofReward::insert(FEE_CHAIN, 9);
+        ProofReward::insert(REWARD_CHAIN, 10);
+        StorageVersion::new(0).put::<ReceiptRegistry>();
+
+        pallet_receipt_registry::migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(StorageVersion::get::<ReceiptRegistry>(), 1);
+        assert_eq!(
+            ReceiptRegistry::registered_chains(WATCHED_CHAIN),
+            Some(Default::default())
+        );
+        assert_eq!(
+            ReceiptRegistry::registered_chains(FEE_CHAIN),
+            Some(ChainSettings {
+                proof_deposit: 7,
+                default_reward: 9,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            ReceiptRegistry::registered_chains(REWARD_CHAIN),
+            Some(ChainSettings {
+                default_reward: 10,
+                ..Default::default()
+            })
+        );
+        // Chains registered at genesis keep their settings.
+        assert_eq!(ReceiptRegistry::proof_deposit(MAINNET_CHAIN), 0);
+        assert_eq!(ProofDeposit::iter().count(), 0);
+        assert_eq!(ProofReward::iter().count(), 0);
+    });
+}
+
+#[test]
+pub fn test_migration_to_v2() {
+    use frame_support::{pallet_prelude::NMapKey, storage_alias, Blake2_128Concat};
+
+    #[storage_alias]
+    type ProcessedReceipts = StorageNMap<
+        ReceiptRegistry,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>,
+            NMapKey<Blake2_128Concat, u64>,
+            NMapKey<Blake2_128Concat, H256>,
+        ),
+        Vec<types::Log>,
+    >;
+
+    #[storage_alias]
+    type ContractReceipts = StorageNMap<
+        ReceiptRegistry,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>,
+            NMapKey<Blake2_128Concat, H160>,
+            NMapKey<Blake2_128Concat, u64>,
+        ),
+        Vec<H256>,
+    >;
+
+    new_test_ext().execute_with(|| {
+        let watched = H160::from_slice(&[1u8; 20]);
+        let other = H160::from_slice(&[2u8; 20]);
+        let log = |address| types::Log {
+            address,
+            topics: vec![],
+            data: vec![],
+        };
+
+        assert_ok!(ReceiptRegistry::update_watching_address(
+            RuntimeOrigin::root(),
+            GOERLI_CHAIN,
+            watched,
+            true
+        ));
+        let receipt_hash = H256([1u8; 32]);
+        ProcessedReceipts::insert(
+            (GOERLI_CHAIN, 10, receipt_hash),
+            vec![log(watched), log(other)],
+        );
+        ProcessedReceipts::insert((GOERLI_CHAIN, 11, H256([2u8; 32])), vec![log(other)]);
+        StorageVersion::new(1).put::<ReceiptRegistry>();
+
+        pallet_receipt_registry::migrations::v2::MigrateToV2::<Test>::on_runtime_upgrade();
+
+        // The receipts are indexed in the following blocks.
+        assert_eq!(StorageVersion::get::<ReceiptRegistry>(), 2);
+        assert_eq!(
+            ReceiptRegistry::receipts_for_contract(GOERLI_CHAIN, watched, 0, 20).unwrap(),
+            vec![]
+        );
+
+        // A receipt proven after the upgrade is indexed once.
+        let new_hash = H256([3u8; 32]);
+        ProcessedReceipts::insert((GOERLI_CHAIN, 12, new_hash), vec![log(watched)]);
+        ContractReceipts::insert((GOERLI_CHAIN, watched, 12), vec![new_hash]);
+
+        ReceiptRegistry::on_idle(1, Weight::MAX);
+        assert_eq!(
+            ReceiptRegistry::receipts_for_contract(GOERLI_CHAIN, watched, 0, 20).unwrap(),
+            vec![(10, receipt_hash), (12, new_hash)]
+        );
+        assert_eq!(
+            ReceiptRegistry::receipts_for_contract(GOERLI_CHAIN, other, 0, 20).unwrap(),
+            vec![]
+        );
+    });
+}
+
+#[test]
+pub fn test_migration_to_v3() {
+    use frame_support::{pallet_prelude::*, storage_alias, Blake2_128Concat};
+
+    #[derive(Encode, Decode)]
+    struct OldChainSettings {
+        enabled: bool,
+        paused: bool,
+        proof_deposit: u128,
+        default_reward: u128,
+        retention: Option<u64>,
+        min_confirmations: u64,
+    }
+
+    #[storage_alias]
+    type RegisteredChains =
+        StorageMap<ReceiptRegistry, Blake2_128Concat, TypedChainId, OldChainSettings>;
+
+    new_test_ext().execute_with(|| {
+        RegisteredChains::insert(
+            GOERLI_CHAIN,
+            OldChainSettings {
+                enabled: true,
+                paused: true,
+                proof_deposit: 7,
+                default_reward: 8,
+                retention: Some(9),
+                min_confirmations: 10,
+            },
+        );
+        StorageVersion::new(2).put::<ReceiptRegistry>();
+
+        pallet_receipt_registry::migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+
+        assert_eq!(StorageVersion::get::<ReceiptRegistry>(), 3);
+        assert_eq!(
+            ReceiptRegistry::registered_chains(GOERLI_CHAIN),
+            Some(ChainSettings {
+                enabled: true,
+                paused: true,
+                proof_deposit: 7,
+                default_reward: 8,
+                retention: Some(9),
+                min_confirmations: 10,
+                challenge_period: 0,
+            })
+        );
+        assert_eq!(
+            ReceiptRegistry::registered_chains(MAINNET_CHAIN),
+            Some(Default::default())
+        );
+    });
+}
+
+#[test]
+pub fn test_receipt_range_limits() {
+    use frame_support::{
+        pallet_prelude::NMapKey, sp_runtime::DispatchError, storage_alias, Blake2_128Concat,
+    };
+    use pallet_receipt_registry::{MAX_BLOCK_RANGE, MAX_RANGE_RECEIPTS};
+
+    #[storage_alias]
+    type ProcessedReceipts = StorageNMap<
+        ReceiptRegistry,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>,
+            NMapKey<Blake2_128Concat, u64>,
+            NMapKey<Blake2_128Concat, H256>,
+        ),
+        Vec<types::Log>,
+    >;
+
+    #[storage_alias]
+    type ContractReceipts = StorageNMap<
+        ReceiptRegistry,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>,
+            NMapKey<Blake2_128Concat, H160>,
+            NMapKey<Blake2_128Concat, u64>,
+        ),
+        Vec<H256>,
+    >;
+
+    new_test_ext().execute_with(|| {
+        let address = H160::from_slice(&[1u8; 20]);
+        let log = types::Log {
+            address,
+            topics: vec![],
+            data: vec![],
+        };
+        let for_contract = |from, to| {
+            ReceiptRegistry::receipts_for_contract(GOERLI_CHAIN, address, from, to)
+                .map_err(DispatchError::from)
+        };
+        // Filtered by a topic none of the logs have.
+        let in_range = |from, to| {
+            ReceiptRegistry::receipts_in_range(GOERLI_CHAIN, from, to, None, Some(H256([9u8; 32])))
+                .map_err(DispatchError::from)
+        };
+
+        assert_eq!(for_contract(0, MAX_BLOCK_RANGE - 1), Ok(vec![]));
+        assert_eq!(
+            for_contract(0, MAX_BLOCK_RANGE),
+            Err(Error::<Test>::RangeTooLarge.into())
+        );
+        assert_eq!(
+            in_range(0, u64::MAX),
+            Err(Error::<Test>::RangeTooLarge.into())
+        );
+
+        // Every receipt of the range counts, not only the ones returned.
+        let hashes = (0..MAX_RANGE_RECEIPTS)
+            .map(|i| {
+                let mut hash = [0u8; 32];
+                hash[..8].copy_from_slice(&(i as u64).to_be_bytes());
+                H256(hash)
+            })
+            .collect::<Vec<_>>();
+        for hash in &hashes {
+            ProcessedReceipts::insert((GOERLI_CHAIN, 1, *hash), vec![log.clone()]);
+        }
+        ContractReceipts::insert((GOERLI_CHAIN, address, 1), hashes);
+        assert_eq!(
+            for_contract(0, 10).map(|receipts| receipts.len()),
+            Ok(MAX_RANGE_RECEIPTS)
+        );
+        assert_eq!(in_range(0, 10), Ok(vec![]));
+
+        let extra = H256([0xff; 32]);
+        ProcessedReceipts::insert((GOERLI_CHAIN, 2, extra), vec![log]);
+        ContractReceipts::insert((GOERLI_CHAIN, address, 2), vec![extra]);
+        assert_eq!(
+            for_contract(0, 10),
+            Err(Error::<Test>::TooManyReceipts.into())
+        );
+        assert_eq!(in_range(0, 10), Err(Error::<Test>::TooManyReceipts.into()));
+        assert_eq!(for_contract(2, 10), Ok(vec![(2, extra)]));
+    });
+}
+
+#[test]
+pub fn test_pruning_resumes_at_the_chain_it_stopped_at() {
+    use frame_support::{
+        pallet_prelude::NMapKey, storage_alias, weights::RuntimeDbWeight, Blake2_128Concat,
+    };
+
+    #[storage_alias]
+    type ProcessedReceipts = StorageNMap<
+        ReceiptRegistry,
+        (
+            NMapKey<Blake2_128Concat, TypedChainId>,
+            NMapKey<Blake2_128Concat, u64>,
+            NMapKey<Blake2_128Concat, H256>,
+        ),
+        Vec<types::Log>,
+    >;
+
+    #[storage_alias]
+    type PruneCursor = StorageMap<ReceiptRegistry, Blake2_128Concat, TypedChainId, u64>;
+
+    new_test_ext().execute_with(|| {
+        let chains = [TypedChainId::Evm(10), TypedChainId::Evm(11)];
+        let receipt_hash = H256([1u8; 32]);
+        let log = |address: u8| types::Log {
+            address: H160::from_slice(&[address; 20]),
+            topics: vec![],
+            data: vec![],
+        };
+        let store = |typed_chain_id| {
+            ProcessedReceipts::insert((typed_chain_id, 0, receipt_hash), vec![log(1), log(2)]);
+            PruneCursor::insert(typed_chain_id, 0);
+        };
+        let stored = |typed_chain_id| {
+            ReceiptRegistry::processed_receipts((typed_chain_id, 0, receipt_hash)).is_some()
+        };
+        for typed_chain_id in chains {
+            assert_ok!(ReceiptRegistry::register_chain(
+                RuntimeOrigin::root(),
+                typed_chain_id,
+                ChainSettings {
+                    retention: Some(0),
+                    ..Default::default()
+                }
+            ));
+            store(typed_chain_id);
+        }
+
+        // Enough to prune the receipt of one chain, whatever the order of the chains.
+        MockDbWeight::set(RuntimeDbWeight { read: 1, write: 1 });
+        let limit = Weight::from_parts(22, 0);
+        ReceiptRegistry::on_idle(1, limit);
+        let (pruned, stopped_at) = if stored(chains[0]) {
+            (chains[1], chains[0])
+        } else {
+            (chains[0], chains[1])
+        };
+        assert!(!stored(pruned));
+        assert!(stored(stopped_at));
+        assert_eq!(ReceiptRegistry::prune_chain_cursor(), Some(stopped_at));
+
+        // The chain pruned first keeps getting receipts, but doesn't starve the other one.
+        store(pruned);
+        ReceiptRegistry::on_idle(2, limit);
+        assert!(!stored(stopped_at));
+    });
+}
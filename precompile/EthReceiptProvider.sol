@@ -8,24 +8,37 @@ pragma solidity >=0.8.0;
  *      0x0000000000000000000000000000000000009999, to retrieve log information of a specified
  *      contract from a given block on a specified chain.
  *
+ *      Chains are identified by their chain id, and resolved to the chain registered with this id
+ *      whatever its kind (EVM, Substrate, ...). Calls revert if no chain, or chains of several
+ *      kinds, are registered with the id.
  */
 interface EthReceiptProvider {
-    
+
+    /**
+     * @notice A log emitted on a specified chain.
+     *
+     * @param contract_addr The address of the contract that emitted the log.
+     * @param topics The topics of the log.
+     * @param data The data of the log.
+     */
+    struct Log {
+        address contract_addr;
+        bytes32[] topics;
+        bytes data;
+    }
+
     /**
      * @notice Fetches logs of a specified contract from a given block on a specified chain.
      *
-     * @dev This function returns log information in the form of topics and data arrays for a 
-     *      specified contract from a given block on a specified chain. The log information is 
-     *      returned as two separate arrays, where each entry corresponds to a log event.
+     * @dev Reverts if the chain id does not fit in uint32 or the block number in uint64.
      *
      * @param chain_id The ID of the chain from which to retrieve log information.
      * @param block_number The number of the block from which to retrieve log information.
      * @param receipt_hash The hash of the receipt for which to retrieve log information.
      * @param contract_addr The address of the contract for which to retrieve log information.
      *
-     * @return Two separate arrays:
-     *          - The first array contains arrays of bytes32 values, each representing a log topic.
-     *          - The second array contains arrays of uint values, each representing log data.
+     * @return found True if the receipt has been proven.
+     * @return logs The logs of the contract in the receipt, empty if the receipt is not found.
      */
     function logs_for_receipt(
         uint256 chain_id,
@@ -34,8 +47,125 @@ interface EthReceiptProvider {
         address contract_addr
     )
         external
+        view
+        returns (
+            bool found,
+            Log[] memory logs
+        );
+
+    /**
+     * @notice Checks whether a receipt has been proven on a specified chain.
+     *
+     * @param chain_id The ID of the chain the receipt belongs to.
+     * @param receipt_hash The hash of the receipt.
+     *
+     * @return True if the receipt has been proven.
+     */
+    function isReceiptProved(
+        uint32 chain_id,
+        bytes32 receipt_hash
+    ) external view returns (bool);
+
+    /**
+     * @notice Fetches the contracts watched on a specified chain.
+     *
+     * @param chain_id The ID of the chain.
+     *
+     * @return The addresses of the watched contracts.
+     */
+    function watchedContracts(
+        uint32 chain_id
+    ) external view returns (address[] memory);
+
+    /**
+     * @notice Fetches the proven receipts with logs of a watched contract in a range of blocks.
+     *
+     * @dev The range is inclusive and may span at most 1024 blocks holding at most 1024 receipts.
+     *
+     * @param chain_id The ID of the chain.
+     * @param contract_addr The address of the watched contract.
+     * @param from_block The first block of the range.
+     * @param to_block The last block of the range.
+     *
+     * @return Two arrays of the same length:
+     *          - The block numbers of the receipts.
+     *          - The hashes of the receipts.
+     */
+    function receiptsForContract(
+        uint32 chain_id,
+        address contract_addr,
+        uint64 from_block,
+        uint64 to_block
+    )
+        external
+        view
         returns (
-            bytes32 [][] calldata,
-            uint[][] calldata
+            uint64[] memory,
+            bytes32[] memory
         );
-}
\ No newline at end of file
+
+    /**
+     * @notice Fetches the logs of a watched contract having a topic in a range of blocks.
+     *
+     * @dev The range is inclusive and may span at most 1024 blocks holding at most 1024 receipts.
+     *      Each entry of the returned arrays corresponds to a log event.
+     *
+     * @param chain_id The ID of the chain.
+     * @param contract_addr The address of the watched contract.
+     * @param topic The topic the logs must have, e.g. the event signature.
+     * @param from_block The first block of the range.
+     * @param to_block The last block of the range.
+     *
+     * @return Three arrays of the same length:
+     *          - The block numbers of the receipts containing the logs.
+     *          - The hashes of the receipts containing the logs.
+     *          - The logs.
+     */
+    function logsByTopic(
+        uint32 chain_id,
+        address contract_addr,
+        bytes32 topic,
+        uint64 from_block,
+        uint64 to_block
+    )
+        external
+        view
+        returns (
+            uint64[] memory,
+            bytes32[] memory,
+            Log[] memory
+        );
+
+    /**
+     * @notice Submits a proof that a receipt has been included in a block of a specified chain.
+     *
+     * @dev The proof is submitted on behalf of the calling contract, which has to be a
+     *      registered relayer. Rewards are accrued to the calling contract, and its bond is
+     *      slashed if the proof doesn't prove anything new. Reverts if the proof is rejected.
+     *
+     * @param chain_id The ID of the chain the receipt belongs to.
+     * @param event_proof The JSON encoded proof, as built by the relayer.
+     *
+     * @return True if the proof has been accepted.
+     */
+    function submitProof(
+        uint32 chain_id,
+        bytes calldata event_proof
+    ) external returns (bool);
+
+    /**
+     * @notice Registers the calling contract as a relayer, reserving a bond from its balance.
+     *
+     * @param bond The amount to add to the bond of the calling contract.
+     *
+     * @return True if the contract has been registered.
+     */
+    function registerRelayer(uint256 bond) external returns (bool);
+
+    /**
+     * @notice Transfers the rewards accrued by the calling contract to its balance.
+     *
+     * @return True if the rewards have been transferred.
+     */
+    function claimRewards() external returns (bool);
+}
@@ -2,6 +2,12 @@
 pragma solidity ^0.8.0;
 
 interface EthReceiptProvider {
+    struct Log {
+        address contract_addr;
+        bytes32[] topics;
+        bytes data;
+    }
+
     function logs_for_receipt(
         uint256 chain_id,
         uint256 block_number,
@@ -9,9 +15,10 @@ interface EthReceiptProvider {
         address contract_addr
     )
         external
+        view
         returns (
-            bytes32 [][] calldata,
-            uint[][] calldata
+            bool found,
+            Log[] memory logs
         );
 }
 
@@ -48,18 +55,20 @@ contract DogOwner {
     ) 
         public 
     {
-        (bytes32[][] memory topics, uint[][] memory _data) = ethReceiptProvider.logs_for_receipt(
+        (bool found, EthReceiptProvider.Log[] memory logs) = ethReceiptProvider.logs_for_receipt(
             chain_id,
             block_number,
             receipt_hash,
             dogContractAddress
         );
+        require(found, "receipt is not proven");
 
-        for (uint i = 0; i < topics.length; i++) {
-            for (uint j = 0; j < topics[i].length; j++) {
-                if (topics[i][j] == keccak256("Bark(string)")) {
+        for (uint i = 0; i < logs.length; i++) {
+            bytes32[] memory topics = logs[i].topics;
+            for (uint j = 0; j < topics.length; j++) {
+                if (topics[j] == keccak256("Bark(string)")) {
                     emit Response("Bad boy");
-                } else if (topics[i][j] == keccak256("TailWag(string)")) {
+                } else if (topics[j] == keccak256("TailWag(string)")) {
                     emit Response("Good boy");
                 }
             }
@@ -1,20 +1,49 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use fp_evm::PrecompileOutput;
+use fp_evm::{PrecompileFailure, PrecompileOutput};
 use frame_support::{
+    dispatch::{DispatchResult, Dispatchable, GetDispatchInfo, PostDispatchInfo},
     inherent::Vec,
+    sp_runtime::{traits::UniqueSaturatedInto, DispatchError},
     sp_std::{fmt::Debug, marker::PhantomData},
+    traits::Get,
+    weights::Weight,
 };
-use pallet_evm::{Precompile, PrecompileHandle};
+use pallet_evm::{AddressMapping, GasWeightMapping, Precompile, PrecompileHandle, Runner};
+use pallet_receipt_registry::{BalanceOf, CallbackDispatcher, CallbackTarget};
 use precompile_utils::{
-    succeed, Address, EvmDataWriter, EvmResult, FunctionModifier, PrecompileHandleExt,
+    revert, succeed, Address, Bytes, EvmDataWriter, EvmResult, FunctionModifier,
+    PrecompileHandleExt, RuntimeHelper,
 };
-use sp_core::H256;
+use sp_core::{H160, H256, U256};
+use webb_proposals::TypedChainId;
+
+pub use pallet_receipt_registry::{MAX_BLOCK_RANGE, MAX_RANGE_RECEIPTS};
+
+/// Gas charged per log returned to the caller, as for the `LOG` opcodes.
+pub const LOG_GAS_COST: u64 = 375;
+
+/// Gas charged per byte of topics and data returned to the caller, as for the `LOG` opcodes.
+pub const LOG_BYTE_GAS_COST: u64 = 8;
+
+/// Gas charged per byte of topics and data of a stored receipt read by a query, whether or not
+/// its logs end up being returned.
+pub const READ_BYTE_GAS_COST: u64 = 3;
+
+/// ABI representation of a log, `(address contractAddress, bytes32[] topics, bytes data)`.
+pub type EvmLog = (Address, Vec<H256>, Bytes);
 
 #[precompile_utils::generate_function_selector]
 #[derive(Debug, PartialEq)]
 pub enum Action {
     LogsForReceipt = "logs_for_receipt(uint256,uint256,bytes32,address)",
+    IsReceiptProved = "isReceiptProved(uint32,bytes32)",
+    WatchedContracts = "watchedContracts(uint32)",
+    ReceiptsForContract = "receiptsForContract(uint32,address,uint64,uint64)",
+    LogsByTopic = "logsByTopic(uint32,address,bytes32,uint64,uint64)",
+    SubmitProof = "submitProof(uint32,bytes)",
+    RegisterRelayer = "registerRelayer(uint256)",
+    ClaimRewards = "claimRewards()",
 }
 
 /// A precompile to wrap the functionality from chain
@@ -23,6 +52,11 @@ pub struct EthReceiptPrecompile<Runtime>(PhantomData<Runtime>);
 impl<Runtime> Precompile for EthReceiptPrecompile<Runtime>
 where
     Runtime: pallet_receipt_registry::Config + pallet_evm::Config + frame_system::Config,
+    <Runtime as frame_system::Config>::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo>
+        + GetDispatchInfo
+        + From<pallet_receipt_registry::Call<Runtime>>,
+    <<Runtime as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin:
+        From<Option<Runtime::AccountId>>,
 {
     fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
         log::trace!(target: "eth-receipt-provider-precompile::execute", "In eth-receipt-provider precompile");
@@ -30,12 +64,26 @@ where
         let selector = handle.read_selector()?;
 
         handle.check_function_modifier(match selector {
-            Action::LogsForReceipt => FunctionModifier::NonPayable,
+            Action::LogsForReceipt
+            | Action::IsReceiptProved
+            | Action::WatchedContracts
+            | Action::ReceiptsForContract
+            | Action::LogsByTopic => FunctionModifier::View,
+            Action::SubmitProof | Action::RegisterRelayer | Action::ClaimRewards => {
+                FunctionModifier::NonPayable
+            }
         })?;
 
         match selector {
-            // Dispatchables
             Action::LogsForReceipt => Self::logs_for_receipt(handle),
+            Action::IsReceiptProved => Self::is_receipt_proved(handle),
+            Action::WatchedContracts => Self::watched_contracts(handle),
+            Action::ReceiptsForContract => Self::receipts_for_contract(handle),
+            Action::LogsByTopic => Self::logs_by_topic(handle),
+            // Dispatchables
+            Action::SubmitProof => Self::submit_proof(handle),
+            Action::RegisterRelayer => Self::register_relayer(handle),
+            Action::ClaimRewards => Self::claim_rewards(handle),
         }
     }
 }
@@ -43,16 +91,20 @@ where
 impl<Runtime> EthReceiptPrecompile<Runtime>
 where
     Runtime: pallet_receipt_registry::Config + pallet_evm::Config + frame_system::Config,
+    <Runtime as frame_system::Config>::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo>
+        + GetDispatchInfo
+        + From<pallet_receipt_registry::Call<Runtime>>,
+    <<Runtime as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin:
+        From<Option<Runtime::AccountId>>,
 {
-    // The dispatchable wrappers are next. They dispatch a Substrate inner Call.
-    fn logs_for_receipt(handle: &impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+    fn logs_for_receipt(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
         const TARGET: &str = "eth-receipt-provider-precompile::logs_for_receipt";
 
         let mut input = handle.read_input()?;
         input.expect_arguments(4)?;
 
-        let chain_id = input.read::<u32>()?;
-        let block_number = input.read::<u64>()?;
+        let chain_id = input.read::<U256>()?;
+        let block_number = input.read::<U256>()?;
         let receipt_hash = input.read::<H256>()?;
         let contract_address = input.read::<Address>()?;
 
@@ -61,38 +113,356 @@ where
             "logs_for_receipt with receipt hash: {receipt_hash:?} and contract address: {contract_address:?}",
         );
 
+        let chain_id =
+            u32::try_from(chain_id).map_err(|_| revert("chain id does not fit in uint32"))?;
+        let block_number = u64::try_from(block_number)
+            .map_err(|_| revert("block number does not fit in uint64"))?;
         let contract_address = types::H160(contract_address.0 .0);
+        let typed_chain_id = Self::typed_chain_id(handle, chain_id)?;
+        Self::ensure_not_paused(handle, typed_chain_id)?;
 
-        // TODO: we need to calculate db read cost and provide it to the handle, but it hard to define for log data. E.g.:
-        // handle.record_db_read::<Runtime>(
-        //     <(webb_proposals::TypedChainId, u64, types::H256)>::max_encoded_len()
-        // )?;
-
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
         let data = if let Some(data) =
             pallet_receipt_registry::Pallet::<Runtime>::processed_receipts((
-                webb_proposals::TypedChainId::Evm(chain_id),
+                typed_chain_id,
                 block_number,
                 types::H256(receipt_hash.0),
             )) {
             data
         } else {
-            return Ok(succeed(EvmDataWriter::new().write(false).build()));
+            return Ok(succeed(
+                EvmDataWriter::new()
+                    .write(false)
+                    .write(Vec::<EvmLog>::new())
+                    .build(),
+            ));
         };
+        Self::record_read_cost(handle, &data)?;
 
-        let (topics, data): (Vec<_>, Vec<_>) = data
+        let logs = data
             .into_iter()
             .filter(|log| log.address == contract_address)
-            .map(|log| {
-                let topics = log
-                    .topics
-                    .into_iter()
-                    .map(|topic| sp_core::H256(topic.0))
-                    .collect::<Vec<_>>();
-                (topics, log.data)
-            })
+            .collect::<Vec<_>>();
+        Self::record_logs_cost(handle, &logs)?;
+
+        let logs = logs.into_iter().map(evm_log).collect::<Vec<_>>();
+        Ok(succeed(
+            EvmDataWriter::new().write(true).write(logs).build(),
+        ))
+    }
+
+    fn is_receipt_proved(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mut input = handle.read_input()?;
+        input.expect_arguments(2)?;
+
+        let chain_id = input.read::<u32>()?;
+        let receipt_hash = input.read::<H256>()?;
+        let typed_chain_id = Self::typed_chain_id(handle, chain_id)?;
+        Self::ensure_not_paused(handle, typed_chain_id)?;
+
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let proved = pallet_receipt_registry::Pallet::<Runtime>::processed_receipts_hash(
+            typed_chain_id,
+            types::H256(receipt_hash.0),
+        )
+        .is_some();
+
+        Ok(succeed(EvmDataWriter::new().write(proved).build()))
+    }
+
+    fn watched_contracts(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mut input = handle.read_input()?;
+        input.expect_arguments(1)?;
+
+        let chain_id = input.read::<u32>()?;
+        let typed_chain_id = Self::typed_chain_id(handle, chain_id)?;
+
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        let contracts =
+            pallet_receipt_registry::Pallet::<Runtime>::watched_contracts(typed_chain_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|address| Address(H160(address.0)))
+                .collect::<Vec<_>>();
+
+        Ok(succeed(EvmDataWriter::new().write(contracts).build()))
+    }
+
+    fn receipts_for_contract(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mut input = handle.read_input()?;
+        input.expect_arguments(4)?;
+
+        let chain_id = input.read::<u32>()?;
+        let contract_address = input.read::<Address>()?;
+        let from = input.read::<u64>()?;
+        let to = input.read::<u64>()?;
+        Self::record_range_cost(handle, from, to)?;
+        let typed_chain_id = Self::typed_chain_id(handle, chain_id)?;
+        Self::ensure_not_paused(handle, typed_chain_id)?;
+
+        let (block_numbers, receipt_hashes): (Vec<_>, Vec<_>) =
+            pallet_receipt_registry::Pallet::<Runtime>::receipts_for_contract(
+                typed_chain_id,
+                types::H160(contract_address.0 .0),
+                from,
+                to,
+            )
+            .map_err(|error| RangeError::from(error).0)?
+            .into_iter()
+            .map(|(block_number, receipt_hash)| (block_number, H256(receipt_hash.0)))
             .unzip();
+
+        Ok(succeed(
+            EvmDataWriter::new()
+                .write(block_numbers)
+                .write(receipt_hashes)
+                .build(),
+        ))
+    }
+
+    fn logs_by_topic(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mut input = handle.read_input()?;
+        input.expect_arguments(5)?;
+
+        let chain_id = input.read::<u32>()?;
+        let contract_address = input.read::<Address>()?;
+        let topic = input.read::<H256>()?;
+        let from = input.read::<u64>()?;
+        let to = input.read::<u64>()?;
+        Self::record_range_cost(handle, from, to)?;
+        let typed_chain_id = Self::typed_chain_id(handle, chain_id)?;
+        Self::ensure_not_paused(handle, typed_chain_id)?;
+
+        let receipts = pallet_receipt_registry::Pallet::<Runtime>::receipts_in_range_metered(
+            typed_chain_id,
+            from,
+            to,
+            Some(types::H160(contract_address.0 .0)),
+            Some(types::H256(topic.0)),
+            |logs| {
+                handle
+                    .record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())
+                    .map_err(|error| RangeError(error.into()))?;
+                Self::record_read_cost(handle, logs).map_err(RangeError)
+            },
+        )
+        .map_err(|RangeError(failure)| failure)?;
+
+        let mut block_numbers = Vec::new();
+        let mut receipt_hashes = Vec::new();
+        let mut evm_logs = Vec::new();
+        for (block_number, receipt_hash, logs) in receipts {
+            Self::record_logs_cost(handle, &logs)?;
+            for log in logs {
+                block_numbers.push(block_number);
+                receipt_hashes.push(H256(receipt_hash.0));
+                evm_logs.push(evm_log(log));
+            }
+        }
+
         Ok(succeed(
-            EvmDataWriter::new().write(topics).write(data).build(),
+            EvmDataWriter::new()
+                .write(block_numbers)
+                .write(receipt_hashes)
+                .write(evm_logs)
+                .build(),
         ))
     }
+
+    // The dispatchable wrappers are next. They dispatch a Substrate inner Call on behalf of the
+    // calling contract, which has to be a registered relayer to submit proofs.
+    fn submit_proof(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mut input = handle.read_input()?;
+        input.expect_arguments(2)?;
+
+        let chain_id = input.read::<u32>()?;
+        let event_proof = input.read::<Bytes>()?;
+        let typed_chain_id = Self::typed_chain_id(handle, chain_id)?;
+        Self::ensure_not_paused(handle, typed_chain_id)?;
+
+        let call = pallet_receipt_registry::Call::<Runtime>::submit_proof {
+            typed_chain_id,
+            event_proof: event_proof.0,
+        };
+        Self::dispatch(handle, call)
+    }
+
+    fn register_relayer(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mut input = handle.read_input()?;
+        input.expect_arguments(1)?;
+
+        let bond = input.read::<U256>()?;
+        let bond = u128::try_from(bond)
+            .ok()
+            .and_then(|bond| BalanceOf::<Runtime>::try_from(bond).ok())
+            .ok_or_else(|| revert("bond out of bounds"))?;
+
+        let call = pallet_receipt_registry::Call::<Runtime>::register_relayer { bond };
+        Self::dispatch(handle, call)
+    }
+
+    fn claim_rewards(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let call = pallet_receipt_registry::Call::<Runtime>::claim_rewards {};
+        Self::dispatch(handle, call)
+    }
+
+    fn dispatch(
+        handle: &mut impl PrecompileHandle,
+        call: pallet_receipt_registry::Call<Runtime>,
+    ) -> EvmResult<PrecompileOutput> {
+        let origin = Runtime::AddressMapping::into_account_id(handle.context().caller);
+        RuntimeHelper::<Runtime>::try_dispatch(handle, Some(origin).into(), call)?;
+
+        Ok(succeed(EvmDataWriter::new().write(true).build()))
+    }
+
+    /// Resolves the chain id given by the caller to the registered chain with this id, whatever
+    /// its kind.
+    fn typed_chain_id(
+        handle: &mut impl PrecompileHandle,
+        chain_id: u32,
+    ) -> EvmResult<TypedChainId> {
+        handle.record_cost(
+            RuntimeHelper::<Runtime>::db_read_gas_cost()
+                .saturating_mul(pallet_receipt_registry::CHAIN_KINDS.len() as u64),
+        )?;
+        pallet_receipt_registry::Pallet::<Runtime>::registered_chain(chain_id)
+            .ok_or_else(|| revert("chain not registered"))
+    }
+
+    /// Reverts if proofs of the chain are paused, since its receipts can't be trusted then.
+    fn ensure_not_paused(
+        handle: &mut impl PrecompileHandle,
+        typed_chain_id: TypedChainId,
+    ) -> EvmResult {
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost().saturating_mul(2))?;
+        if pallet_receipt_registry::Pallet::<Runtime>::is_paused(typed_chain_id) {
+            return Err(revert("chain paused"));
+        }
+        Ok(())
+    }
+
+    /// Rejects ranges larger than [`MAX_BLOCK_RANGE`] and records one storage read per block of
+    /// the range.
+    fn record_range_cost(handle: &mut impl PrecompileHandle, from: u64, to: u64) -> EvmResult {
+        let blocks = to.saturating_sub(from);
+        if blocks >= MAX_BLOCK_RANGE {
+            return Err(revert("block range too large"));
+        }
+        if from <= to {
+            handle.record_cost(
+                RuntimeHelper::<Runtime>::db_read_gas_cost().saturating_mul(blocks + 1),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Records the cost of decoding the stored `logs` of a receipt, before any filtering.
+    fn record_read_cost(handle: &mut impl PrecompileHandle, logs: &[types::Log]) -> EvmResult {
+        handle.record_cost(READ_BYTE_GAS_COST.saturating_mul(logs_size(logs)))?;
+        Ok(())
+    }
+
+    /// Records the cost of returning `logs`, proportional to their count and size.
+    fn record_logs_cost(handle: &mut impl PrecompileHandle, logs: &[types::Log]) -> EvmResult {
+        let bytes = logs_size(logs);
+        handle.record_cost(
+            LOG_GAS_COST
+                .saturating_mul(logs.len() as u64)
+                .saturating_add(LOG_BYTE_GAS_COST.saturating_mul(bytes)),
+        )?;
+        Ok(())
+    }
+}
+
+/// Calls the EVM callbacks of the receipt registry from the `Caller` address, usually the one of
+/// the precompile so that contracts can check where the logs come from.
+///
+/// The callback receives `(uint32 chainId, uint64 blockNumber, bytes32 receiptHash, Log log)`.
+pub struct EvmCallbackDispatcher<Runtime, Caller>(PhantomData<(Runtime, Caller)>);
+
+impl<Runtime, Caller> CallbackDispatcher<Runtime::AccountId>
+    for EvmCallbackDispatcher<Runtime, Caller>
+where
+    Runtime: pallet_evm::Config,
+    Caller: Get<H160>,
+{
+    fn dispatch(
+        _owner: &Runtime::AccountId,
+        target: &CallbackTarget<Runtime::AccountId>,
+        typed_chain_id: TypedChainId,
+        block_number: u64,
+        receipt_hash: types::H256,
+        log: &types::Log,
+        weight_limit: Weight,
+    ) -> Option<(Weight, DispatchResult)> {
+        let CallbackTarget::Evm { contract, selector } = target else {
+            return None;
+        };
+
+        let input = EvmDataWriter::new_with_selector(u32::from_be_bytes(*selector))
+            .write(typed_chain_id.underlying_chain_id())
+            .write(block_number)
+            .write(H256(receipt_hash.0))
+            .write(evm_log(log.clone()))
+            .build();
+        let result = Runtime::Runner::call(
+            Caller::get(),
+            *contract,
+            input,
+            U256::zero(),
+            Runtime::GasWeightMapping::weight_to_gas(weight_limit),
+            None,
+            None,
+            None,
+            Vec::new(),
+            false,
+            false,
+            Some(weight_limit),
+            None,
+            Runtime::config(),
+        );
+
+        Some(match result {
+            Ok(info) => {
+                let used_gas: u64 = info.used_gas.standard.unique_saturated_into();
+                let weight = Runtime::GasWeightMapping::gas_to_weight(used_gas, true);
+                if info.exit_reason.is_succeed() {
+                    (weight, Ok(()))
+                } else {
+                    (weight, Err(DispatchError::Other("EVM callback reverted")))
+                }
+            }
+            Err(err) => (err.weight, Err(err.error.into())),
+        })
+    }
+}
+
+/// Failure of a range query, reverting with the errors of the registry helpers.
+struct RangeError(PrecompileFailure);
+
+impl<Runtime: pallet_receipt_registry::Config> From<pallet_receipt_registry::Error<Runtime>>
+    for RangeError
+{
+    fn from(error: pallet_receipt_registry::Error<Runtime>) -> Self {
+        RangeError(match error {
+            pallet_receipt_registry::Error::RangeTooLarge => revert("block range too large"),
+            _ => revert("too many receipts"),
+        })
+    }
+}
+
+fn evm_log(log: types::Log) -> EvmLog {
+    (
+        Address(H160(log.address.0)),
+        log.topics.into_iter().map(|topic| H256(topic.0)).collect(),
+        Bytes(log.data),
+    )
+}
+
+/// Number of bytes of topics and data in `logs`.
+fn logs_size(logs: &[types::Log]) -> u64 {
+    logs.iter()
+        .map(|log| log.topics.len() * 32 + log.data.len())
+        .sum::<usize>() as u64
 }
@@ -0,0 +1,224 @@
+use fp_evm::{IsPrecompileResult, Precompile, PrecompileHandle, PrecompileResult, PrecompileSet};
+use frame_support::{
+    parameter_types, sp_io,
+    sp_runtime::{
+        testing::Header,
+        traits::{BlakeTwo256, IdentityLookup},
+        AccountId32,
+    },
+    sp_std::marker::PhantomData,
+    traits::{ConstU64, GenesisBuild},
+    weights::{IdentityFee, Weight},
+    PalletId,
+};
+use frame_system::EnsureRoot;
+use pallet_evm::{AddressMapping, EnsureAddressNever, EnsureAddressRoot, HashedAddressMapping};
+use pallet_evm_eth_receipt_provider::{EthReceiptPrecompile, EvmCallbackDispatcher};
+use sp_core::{H160, H256, U256};
+use webb_proposals::TypedChainId;
+
+pub type AccountId = AccountId32;
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+/// Address the precompile is mounted at.
+pub fn precompile_address() -> H160 {
+    H160::from_low_u64_be(0x9999)
+}
+
+/// Contract calling the precompile in the tests.
+pub fn caller() -> H160 {
+    H160::repeat_byte(0xAA)
+}
+
+/// Precompile set made of the receipt provider only.
+#[derive(Default)]
+pub struct TestPrecompileSet<R>(PhantomData<R>);
+
+impl<R> PrecompileSet for TestPrecompileSet<R>
+where
+    EthReceiptPrecompile<R>: Precompile,
+{
+    fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+        (handle.code_address() == precompile_address())
+            .then(|| EthReceiptPrecompile::<R>::execute(handle))
+    }
+
+    fn is_precompile(&self, address: H160, _gas: u64) -> IsPrecompileResult {
+        IsPrecompileResult::Answer {
+            is_precompile: address == precompile_address(),
+            extra_cost: 0,
+        }
+    }
+}
+
+pub fn precompiles() -> TestPrecompileSet<Test> {
+    PrecompilesValue::get()
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    let _ = pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(
+            <Test as pallet_evm::Config>::AddressMapping::into_account_id(caller()),
+            10u128.pow(18),
+        )],
+    }
+    .assimilate_storage(&mut storage);
+    let _ = pallet_receipt_registry::GenesisConfig::<Test> {
+        chains: vec![(TypedChainId::Evm(5), Default::default())],
+    }
+    .assimilate_storage(&mut storage);
+
+    storage.into()
+}
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for Test {
+    type AccountData = pallet_balances::AccountData<u128>;
+    type AccountId = AccountId;
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockHashCount = BlockHashCount;
+    type BlockLength = ();
+    type BlockNumber = u64;
+    type BlockWeights = ();
+    type RuntimeCall = RuntimeCall;
+    type DbWeight = ();
+    type RuntimeEvent = RuntimeEvent;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type Header = Header;
+    type Index = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type MaxConsumers = frame_support::traits::ConstU32<16>;
+    type OnKilledAccount = ();
+    type OnNewAccount = ();
+    type OnSetCode = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type PalletInfo = PalletInfo;
+    type SS58Prefix = SS58Prefix;
+    type SystemWeightInfo = ();
+    type Version = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+    type Balance = u128;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ExistentialDeposit;
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type HoldIdentifier = ();
+    type FreezeIdentifier = ();
+    type MaxHolds = ();
+    type MaxFreezes = ();
+    type WeightInfo = ();
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub BlockGasLimit: U256 = U256::from(u64::MAX);
+    pub PrecompilesValue: TestPrecompileSet<Test> = Default::default();
+    pub WeightPerGas: Weight = Weight::from_parts(1, 0);
+}
+
+impl pallet_evm::Config for Test {
+    type FeeCalculator = ();
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type CallOrigin = EnsureAddressRoot<AccountId>;
+    type WithdrawOrigin = EnsureAddressNever<AccountId>;
+    type AddressMapping = HashedAddressMapping<BlakeTwo256>;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type PrecompilesType = TestPrecompileSet<Self>;
+    type PrecompilesValue = PrecompilesValue;
+    type ChainId = ();
+    type BlockGasLimit = BlockGasLimit;
+    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type OnChargeTransaction = ();
+    type OnCreate = ();
+    type FindAuthor = ();
+    type Timestamp = Timestamp;
+    type GasLimitPovSizeRatio = ConstU64<4>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const StoragePricePerByte: u128 = 1;
+    pub const Eth2ClientPalletId: PalletId = PalletId(*b"py/eth2c");
+    pub const MaxAncestryLength: u32 = 2;
+    pub const MaxWatchedContracts: u32 = 100;
+    pub const MinRelayerBond: u128 = 100;
+    pub const RewardPoolLowThreshold: u32 = 3;
+    pub const MaxOnReceiptProvedWeight: Weight = Weight::zero();
+    pub const MaxCallbackWeight: Weight = Weight::from_parts(10_000_000, 0);
+    pub const MaxCallbacksPerReceipt: u32 = 2;
+    pub const MaxFinalityStall: u32 = 10;
+    pub const MaxFinalityChecksPerBlock: u32 = 2;
+    pub const MaxPendingReceiptsPerBlock: u32 = 2;
+    pub CallbackCaller: H160 = precompile_address();
+}
+
+impl pallet_eth2_light_client::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type StoragePricePerByte = StoragePricePerByte;
+    type PalletId = Eth2ClientPalletId;
+    type Currency = Balances;
+}
+
+impl pallet_receipt_registry::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = Eth2ClientPalletId;
+    type Currency = Balances;
+    type PrivilegedOrigin = EnsureRoot<AccountId>;
+    type MaxWatchedContracts = MaxWatchedContracts;
+    type MaxAncestryLength = MaxAncestryLength;
+    type MinRelayerBond = MinRelayerBond;
+    type RewardPoolLowThreshold = RewardPoolLowThreshold;
+    type OnReceiptProved = ();
+    type MaxOnReceiptProvedWeight = MaxOnReceiptProvedWeight;
+    type CallbackDispatcher = EvmCallbackDispatcher<Self, CallbackCaller>;
+    type WeightToFee = IdentityFee<u128>;
+    type MaxCallbackWeight = MaxCallbackWeight;
+    type MaxCallbacksPerReceipt = MaxCallbacksPerReceipt;
+    type MaxFinalityStall = MaxFinalityStall;
+    type MaxFinalityChecksPerBlock = MaxFinalityChecksPerBlock;
+    type MaxPendingReceiptsPerBlock = MaxPendingReceiptsPerBlock;
+}
+
+// Configure a mock runtime to test the precompile.
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Event<T>},
+        Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+        Evm: pallet_evm::{Pallet, Call, Storage, Event<T>},
+        Eth2Client: pallet_eth2_light_client::{Pallet, Call, Storage, Event<T>},
+        ReceiptRegistry: pallet_receipt_registry::{Pallet, Call, Storage, Config<T>, Event<T>},
+    }
+);
@@ -0,0 +1,715 @@
+use ethers::abi::{self, ParamType, Token};
+use fp_evm::{Context, Precompile};
+use frame_support::{assert_ok, pallet_prelude::NMapKey, storage_alias, Blake2_128Concat};
+use pallet_evm::AddressMapping;
+use pallet_evm_eth_receipt_provider::{
+    Action, EthReceiptPrecompile, EvmLog, LOG_BYTE_GAS_COST, LOG_GAS_COST, MAX_BLOCK_RANGE,
+    READ_BYTE_GAS_COST,
+};
+use precompile_utils::{testing::*, Address, Bytes, EvmDataWriter};
+use sp_core::{H160, H256, U256};
+use webb_proposals::TypedChainId;
+
+mod mock;
+
+use mock::{
+    caller, new_test_ext, precompile_address, precompiles, ReceiptRegistry, RuntimeOrigin, Test,
+};
+
+const CHAIN_ID: u32 = 5;
+const GOERLI_CHAIN: TypedChainId = TypedChainId::Evm(CHAIN_ID);
+
+#[storage_alias]
+type ProcessedReceipts = StorageNMap<
+    ReceiptRegistry,
+    (
+        NMapKey<Blake2_128Concat, TypedChainId>,
+        NMapKey<Blake2_128Concat, u64>,
+        NMapKey<Blake2_128Concat, types::H256>,
+    ),
+    Vec<types::Log>,
+>;
+
+#[storage_alias]
+type ProcessedReceiptsHash = StorageDoubleMap<
+    ReceiptRegistry,
+    Blake2_128Concat,
+    TypedChainId,
+    Blake2_128Concat,
+    types::H256,
+    (),
+>;
+
+#[storage_alias]
+type ContractReceipts = StorageNMap<
+    ReceiptRegistry,
+    (
+        NMapKey<Blake2_128Concat, TypedChainId>,
+        NMapKey<Blake2_128Concat, types::H160>,
+        NMapKey<Blake2_128Concat, u64>,
+    ),
+    Vec<types::H256>,
+>;
+
+fn watch(address: H160) {
+    assert_ok!(ReceiptRegistry::update_watching_address(
+        RuntimeOrigin::root(),
+        GOERLI_CHAIN,
+        types::H160(address.0),
+        true
+    ));
+}
+
+fn log(address: H160, topics: Vec<H256>, data: Vec<u8>) -> types::Log {
+    types::Log {
+        address: types::H160(address.0),
+        topics: topics
+            .into_iter()
+            .map(|topic| types::H256(topic.0))
+            .collect(),
+        data,
+    }
+}
+
+/// Calls the precompile and returns its raw output.
+fn call(input: Vec<u8>) -> Vec<u8> {
+    let mut handle = MockHandle::new(
+        precompile_address(),
+        Context {
+            address: precompile_address(),
+            caller: caller(),
+            apparent_value: U256::zero(),
+        },
+    );
+    handle.input = input;
+    EthReceiptPrecompile::<Test>::execute(&mut handle)
+        .expect("call succeeds")
+        .output
+}
+
+/// ABI type of a log, `(address contractAddress, bytes32[] topics, bytes data)`.
+fn log_type() -> ParamType {
+    ParamType::Tuple(vec![
+        ParamType::Address,
+        ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+        ParamType::Bytes,
+    ])
+}
+
+fn uint_token(value: U256) -> Token {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    Token::Uint(ethers::types::U256::from_big_endian(&bytes))
+}
+
+fn log_token(address: H160, topics: Vec<H256>, data: Vec<u8>) -> Token {
+    Token::Tuple(vec![
+        Token::Address(address.0.into()),
+        Token::Array(
+            topics
+                .into_iter()
+                .map(|topic| Token::FixedBytes(topic.0.to_vec()))
+                .collect(),
+        ),
+        Token::Bytes(data),
+    ])
+}
+
+/// Stores a receipt the way the registry does once its proof is accepted.
+fn prove_receipt(block_number: u64, receipt_hash: H256, logs: Vec<types::Log>) {
+    let receipt_hash = types::H256(receipt_hash.0);
+    let watched = ReceiptRegistry::watched_contracts(GOERLI_CHAIN).unwrap_or_default();
+    for address in watched {
+        if logs.iter().any(|log| log.address == address) {
+            ContractReceipts::append((GOERLI_CHAIN, address, block_number), receipt_hash);
+        }
+    }
+    ProcessedReceipts::insert((GOERLI_CHAIN, block_number, receipt_hash), logs);
+    ProcessedReceiptsHash::insert(GOERLI_CHAIN, receipt_hash, ());
+}
+
+#[test]
+fn test_logs_for_receipt() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(1);
+        let topic = H256::repeat_byte(2);
+        prove_receipt(
+            10,
+            H256::repeat_byte(3),
+            vec![
+                log(contract, vec![topic], vec![1, 2]),
+                log(H160::repeat_byte(4), vec![], vec![3]),
+            ],
+        );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::LogsForReceipt)
+                    .write(CHAIN_ID)
+                    .write(10u64)
+                    .write(H256::repeat_byte(3))
+                    .write(Address(contract))
+                    .build(),
+            )
+            .expect_cost(
+                READ_BYTE_GAS_COST * (32 + 2 + 1) + LOG_GAS_COST + LOG_BYTE_GAS_COST * (32 + 2),
+            )
+            .expect_no_logs()
+            .execute_returns(
+                EvmDataWriter::new()
+                    .write(true)
+                    .write(vec![(Address(contract), vec![topic], Bytes(vec![1, 2]))])
+                    .build(),
+            );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::LogsForReceipt)
+                    .write(CHAIN_ID)
+                    .write(11u64)
+                    .write(H256::repeat_byte(3))
+                    .write(Address(contract))
+                    .build(),
+            )
+            .execute_returns(
+                EvmDataWriter::new()
+                    .write(false)
+                    .write(Vec::<EvmLog>::new())
+                    .build(),
+            );
+    });
+}
+
+#[test]
+fn test_is_receipt_proved() {
+    new_test_ext().execute_with(|| {
+        prove_receipt(10, H256::repeat_byte(3), vec![]);
+
+        for (chain_id, receipt_hash, proved) in [
+            (CHAIN_ID, H256::repeat_byte(3), true),
+            (CHAIN_ID, H256::repeat_byte(4), false),
+        ] {
+            precompiles()
+                .prepare_test(
+                    caller(),
+                    precompile_address(),
+                    EvmDataWriter::new_with_selector(Action::IsReceiptProved)
+                        .write(chain_id)
+                        .write(receipt_hash)
+                        .build(),
+                )
+                .expect_no_logs()
+                .execute_returns(EvmDataWriter::new().write(proved).build());
+        }
+    });
+}
+
+#[test]
+fn test_chain_ids_resolve_to_registered_chains() {
+    new_test_ext().execute_with(|| {
+        let is_receipt_proved = |chain_id: u32| {
+            precompiles().prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::IsReceiptProved)
+                    .write(chain_id)
+                    .write(H256::repeat_byte(3))
+                    .build(),
+            )
+        };
+
+        is_receipt_proved(7).execute_reverts(|output| output == b"chain not registered");
+
+        // Chains of any kind are found by their chain id.
+        let substrate_chain = TypedChainId::Substrate(7);
+        assert_ok!(ReceiptRegistry::register_chain(
+            RuntimeOrigin::root(),
+            substrate_chain,
+            Default::default()
+        ));
+        ProcessedReceiptsHash::insert(substrate_chain, types::H256([3; 32]), ());
+        is_receipt_proved(7).execute_returns(EvmDataWriter::new().write(true).build());
+
+        // The chain id is ambiguous once chains of two kinds are registered with it.
+        assert_ok!(ReceiptRegistry::register_chain(
+            RuntimeOrigin::root(),
+            TypedChainId::Evm(7),
+            Default::default()
+        ));
+        is_receipt_proved(7).execute_reverts(|output| output == b"chain not registered");
+    });
+}
+
+#[test]
+fn test_watched_contracts() {
+    new_test_ext().execute_with(|| {
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::WatchedContracts)
+                    .write(CHAIN_ID)
+                    .build(),
+            )
+            .execute_returns(EvmDataWriter::new().write(Vec::<Address>::new()).build());
+
+        watch(H160::repeat_byte(1));
+        watch(H160::repeat_byte(2));
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::WatchedContracts)
+                    .write(CHAIN_ID)
+                    .build(),
+            )
+            .expect_no_logs()
+            .execute_returns(
+                EvmDataWriter::new()
+                    .write(vec![
+                        Address(H160::repeat_byte(1)),
+                        Address(H160::repeat_byte(2)),
+                    ])
+                    .build(),
+            );
+    });
+}
+
+#[test]
+fn test_receipts_for_contract() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(1);
+        watch(contract);
+        prove_receipt(
+            10,
+            H256::repeat_byte(3),
+            vec![log(contract, vec![], vec![])],
+        );
+        prove_receipt(
+            12,
+            H256::repeat_byte(4),
+            vec![log(contract, vec![], vec![])],
+        );
+        prove_receipt(
+            12,
+            H256::repeat_byte(5),
+            vec![log(H160::repeat_byte(2), vec![], vec![])],
+        );
+        prove_receipt(
+            20,
+            H256::repeat_byte(6),
+            vec![log(contract, vec![], vec![])],
+        );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::ReceiptsForContract)
+                    .write(CHAIN_ID)
+                    .write(Address(contract))
+                    .write(10u64)
+                    .write(15u64)
+                    .build(),
+            )
+            .expect_no_logs()
+            .execute_returns(
+                EvmDataWriter::new()
+                    .write(vec![10u64, 12])
+                    .write(vec![H256::repeat_byte(3), H256::repeat_byte(4)])
+                    .build(),
+            );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::ReceiptsForContract)
+                    .write(CHAIN_ID)
+                    .write(Address(contract))
+                    .write(0u64)
+                    .write(MAX_BLOCK_RANGE)
+                    .build(),
+            )
+            .execute_reverts(|output| output == b"block range too large");
+    });
+}
+
+#[test]
+fn test_logs_by_topic() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(1);
+        let topic = H256::repeat_byte(7);
+        watch(contract);
+        prove_receipt(
+            10,
+            H256::repeat_byte(3),
+            vec![
+                log(contract, vec![topic, H256::repeat_byte(8)], vec![1]),
+                log(contract, vec![H256::repeat_byte(8)], vec![2]),
+                log(H160::repeat_byte(2), vec![topic], vec![3]),
+            ],
+        );
+        prove_receipt(
+            11,
+            H256::repeat_byte(4),
+            vec![log(contract, vec![], vec![])],
+        );
+        prove_receipt(
+            12,
+            H256::repeat_byte(5),
+            vec![log(contract, vec![topic], vec![4])],
+        );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::LogsByTopic)
+                    .write(CHAIN_ID)
+                    .write(Address(contract))
+                    .write(topic)
+                    .write(10u64)
+                    .write(12u64)
+                    .build(),
+            )
+            .expect_no_logs()
+            .execute_returns(
+                EvmDataWriter::new()
+                    .write(vec![10u64, 12])
+                    .write(vec![H256::repeat_byte(3), H256::repeat_byte(5)])
+                    .write(vec![
+                        (
+                            Address(contract),
+                            vec![topic, H256::repeat_byte(8)],
+                            Bytes(vec![1]),
+                        ),
+                        (Address(contract), vec![topic], Bytes(vec![4])),
+                    ])
+                    .build(),
+            );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::LogsByTopic)
+                    .write(CHAIN_ID)
+                    .write(Address(contract))
+                    .write(topic)
+                    .write(0u64)
+                    .write(MAX_BLOCK_RANGE)
+                    .build(),
+            )
+            .execute_reverts(|output| output == b"block range too large");
+    });
+}
+
+#[test]
+fn test_logs_cost_scales_with_payload() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(1);
+        let topic = H256::repeat_byte(7);
+        watch(contract);
+        prove_receipt(
+            10,
+            H256::repeat_byte(3),
+            vec![log(contract, vec![topic], vec![])],
+        );
+        prove_receipt(
+            11,
+            H256::repeat_byte(4),
+            vec![
+                log(contract, vec![topic], vec![0; 100]),
+                log(contract, vec![topic, H256::repeat_byte(8)], vec![0; 1000]),
+            ],
+        );
+        // Read and decoded by the range query even though none of its logs match.
+        prove_receipt(
+            12,
+            H256::repeat_byte(5),
+            vec![log(contract, vec![H256::repeat_byte(9)], vec![0; 500])],
+        );
+
+        let logs_for_receipt = |block_number: u64, receipt_hash: H256, cost: u64| {
+            precompiles()
+                .prepare_test(
+                    caller(),
+                    precompile_address(),
+                    EvmDataWriter::new_with_selector(Action::LogsForReceipt)
+                        .write(CHAIN_ID)
+                        .write(block_number)
+                        .write(receipt_hash)
+                        .write(Address(contract))
+                        .build(),
+                )
+                .expect_cost(cost)
+                .execute_some();
+        };
+        logs_for_receipt(
+            10,
+            H256::repeat_byte(3),
+            (READ_BYTE_GAS_COST + LOG_BYTE_GAS_COST) * 32 + LOG_GAS_COST,
+        );
+        logs_for_receipt(
+            11,
+            H256::repeat_byte(4),
+            (READ_BYTE_GAS_COST + LOG_BYTE_GAS_COST) * (32 + 100 + 64 + 1000) + 2 * LOG_GAS_COST,
+        );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::LogsByTopic)
+                    .write(CHAIN_ID)
+                    .write(Address(contract))
+                    .write(topic)
+                    .write(10u64)
+                    .write(12u64)
+                    .build(),
+            )
+            .expect_cost(
+                READ_BYTE_GAS_COST * (32 + 32 + 100 + 64 + 1000 + 32 + 500)
+                    + 3 * LOG_GAS_COST
+                    + LOG_BYTE_GAS_COST * (32 + 32 + 100 + 64 + 1000),
+            )
+            .execute_some();
+    });
+}
+
+#[test]
+fn test_logs_for_receipt_abi_round_trip() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(1);
+        let topics = vec![H256::repeat_byte(7), H256::repeat_byte(8)];
+        let data = (0..100).collect::<Vec<u8>>();
+        prove_receipt(
+            10,
+            H256::repeat_byte(3),
+            vec![
+                log(contract, topics.clone(), data.clone()),
+                log(H160::repeat_byte(2), vec![], vec![1]),
+                log(contract, vec![], vec![]),
+            ],
+        );
+        prove_receipt(11, H256::repeat_byte(4), vec![]);
+
+        let output_type = [ParamType::Bool, ParamType::Array(Box::new(log_type()))];
+        let logs_for_receipt = |chain_id: U256, block_number: U256, receipt_hash: H256| {
+            let input = abi::encode(&[
+                uint_token(chain_id),
+                uint_token(block_number),
+                Token::FixedBytes(receipt_hash.0.to_vec()),
+                Token::Address(contract.0.into()),
+            ]);
+            let mut call_data = (Action::LogsForReceipt as u32).to_be_bytes().to_vec();
+            call_data.extend(input);
+            abi::decode(&output_type, &call(call_data)).expect("output is ABI encoded")
+        };
+
+        assert_eq!(
+            logs_for_receipt(CHAIN_ID.into(), 10u64.into(), H256::repeat_byte(3)),
+            vec![
+                Token::Bool(true),
+                Token::Array(vec![
+                    log_token(contract, topics, data),
+                    log_token(contract, vec![], vec![]),
+                ]),
+            ]
+        );
+        // A proven receipt without logs of the contract.
+        assert_eq!(
+            logs_for_receipt(CHAIN_ID.into(), 11u64.into(), H256::repeat_byte(4)),
+            vec![Token::Bool(true), Token::Array(vec![])]
+        );
+        assert_eq!(
+            logs_for_receipt(CHAIN_ID.into(), 12u64.into(), H256::repeat_byte(4)),
+            vec![Token::Bool(false), Token::Array(vec![])]
+        );
+    });
+}
+
+#[test]
+fn test_logs_for_receipt_rejects_out_of_range_ids() {
+    new_test_ext().execute_with(|| {
+        for (chain_id, block_number, reason) in [
+            (
+                U256::from(u32::MAX) + 1,
+                U256::from(10),
+                &b"chain id does not fit in uint32"[..],
+            ),
+            (
+                U256::from(CHAIN_ID),
+                U256::from(u64::MAX) + 1,
+                &b"block number does not fit in uint64"[..],
+            ),
+        ] {
+            precompiles()
+                .prepare_test(
+                    caller(),
+                    precompile_address(),
+                    EvmDataWriter::new_with_selector(Action::LogsForReceipt)
+                        .write(chain_id)
+                        .write(block_number)
+                        .write(H256::repeat_byte(3))
+                        .write(Address(H160::repeat_byte(1)))
+                        .build(),
+                )
+                .execute_reverts(|output| output == reason);
+        }
+    });
+}
+
+#[test]
+fn test_logs_by_topic_abi_round_trip() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(1);
+        let topic = H256::repeat_byte(7);
+        watch(contract);
+        prove_receipt(
+            10,
+            H256::repeat_byte(3),
+            vec![log(contract, vec![topic], vec![1, 2, 3])],
+        );
+        prove_receipt(
+            12,
+            H256::repeat_byte(5),
+            vec![log(contract, vec![H256::repeat_byte(8), topic], vec![])],
+        );
+
+        let input = abi::encode(&[
+            Token::Uint(CHAIN_ID.into()),
+            Token::Address(contract.0.into()),
+            Token::FixedBytes(topic.0.to_vec()),
+            Token::Uint(10.into()),
+            Token::Uint(12.into()),
+        ]);
+        let mut call_data = (Action::LogsByTopic as u32).to_be_bytes().to_vec();
+        call_data.extend(input);
+
+        let output = abi::decode(
+            &[
+                ParamType::Array(Box::new(ParamType::Uint(64))),
+                ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+                ParamType::Array(Box::new(log_type())),
+            ],
+            &call(call_data),
+        )
+        .expect("output is ABI encoded");
+
+        assert_eq!(
+            output,
+            vec![
+                Token::Array(vec![Token::Uint(10.into()), Token::Uint(12.into())]),
+                Token::Array(vec![
+                    Token::FixedBytes(H256::repeat_byte(3).0.to_vec()),
+                    Token::FixedBytes(H256::repeat_byte(5).0.to_vec()),
+                ]),
+                Token::Array(vec![
+                    log_token(contract, vec![topic], vec![1, 2, 3]),
+                    log_token(contract, vec![H256::repeat_byte(8), topic], vec![]),
+                ]),
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_relaying_from_a_contract() {
+    new_test_ext().execute_with(|| {
+        let account = <Test as pallet_evm::Config>::AddressMapping::into_account_id(caller());
+        let reverts_with = |input: Vec<u8>, error: &'static str| {
+            precompiles()
+                .prepare_test(caller(), precompile_address(), input)
+                .execute_reverts(|output| {
+                    std::str::from_utf8(output).map_or(false, |output| output.contains(error))
+                });
+        };
+
+        reverts_with(
+            EvmDataWriter::new_with_selector(Action::SubmitProof)
+                .write(CHAIN_ID)
+                .write(Bytes(b"{}".to_vec()))
+                .build(),
+            "NotRegisteredRelayer",
+        );
+        reverts_with(
+            EvmDataWriter::new_with_selector(Action::RegisterRelayer)
+                .write(U256::from(99))
+                .build(),
+            "BondTooLow",
+        );
+
+        precompiles()
+            .prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::RegisterRelayer)
+                    .write(U256::from(100))
+                    .build(),
+            )
+            .execute_returns(EvmDataWriter::new().write(true).build());
+        assert_eq!(ReceiptRegistry::relayers(&account).unwrap().bond, 100);
+
+        // The proof is checked on behalf of the contract, now a registered relayer.
+        reverts_with(
+            EvmDataWriter::new_with_selector(Action::SubmitProof)
+                .write(CHAIN_ID)
+                .write(Bytes(b"{}".to_vec()))
+                .build(),
+            "DeserializeFail",
+        );
+        reverts_with(
+            EvmDataWriter::new_with_selector(Action::ClaimRewards).build(),
+            "NoRewards",
+        );
+    });
+}
+
+#[test]
+fn test_paused_chain_reverts() {
+    new_test_ext().execute_with(|| {
+        prove_receipt(10, H256::repeat_byte(3), vec![]);
+        let precompiles = precompiles();
+        let is_receipt_proved = || {
+            precompiles.prepare_test(
+                caller(),
+                precompile_address(),
+                EvmDataWriter::new_with_selector(Action::IsReceiptProved)
+                    .write(CHAIN_ID)
+                    .write(H256::repeat_byte(3))
+                    .build(),
+            )
+        };
+
+        for typed_chain_id in [Some(GOERLI_CHAIN), None] {
+            assert_ok!(ReceiptRegistry::pause(
+                RuntimeOrigin::root(),
+                typed_chain_id
+            ));
+            is_receipt_proved().execute_reverts(|output| output == b"chain paused");
+            precompiles
+                .prepare_test(
+                    caller(),
+                    precompile_address(),
+                    EvmDataWriter::new_with_selector(Action::SubmitProof)
+                        .write(CHAIN_ID)
+                        .write(Bytes(b"{}".to_vec()))
+                        .build(),
+                )
+                .execute_reverts(|output| output == b"chain paused");
+
+            assert_ok!(ReceiptRegistry::unpause(
+                RuntimeOrigin::root(),
+                typed_chain_id
+            ));
+            is_receipt_proved().execute_returns(EvmDataWriter::new().write(true).build());
+        }
+    });
+}
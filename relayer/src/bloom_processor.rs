@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use ethers::providers::{Http, Middleware, Provider};
 use futures::future::join_all;
+use merkle_generator::ReceiptsTrie;
 use types::{BlockHeaderWithTransaction, TransactionReceipt, H160, H256};
 
 use crate::common::*;
@@ -125,6 +126,10 @@ impl BloomProcessor {
                 }
                 let receipts = receipt_data.unwrap();
 
+                // The trie is built lazily, only if the block has an event we are interested in.
+                let mut receipts_trie = None;
+                let mut receipts_root_mismatch = false;
+
                 // We need to validate that the bloom filter contains the watch addresses as they might be false positives
                 let mut created_proof = false;
                 for (i, receipt) in receipts.iter().enumerate() {
@@ -148,14 +153,24 @@ impl BloomProcessor {
                             continue;
                         }
 
-                        if let Ok(proof) = build_receipt_proof(block_hash, &block, &receipts, i) {
+                        let receipts_trie =
+                            receipts_trie.get_or_insert_with(|| ReceiptsTrie::new(&receipts));
+                        if receipts_trie.root() != block.header.receipts_root {
+                            log::warn!(target: TARGET, "Receipts root mismatch for block {}, will retry", block_height);
+                            receipts_root_mismatch = true;
+                            break;
+                        }
+
+                        if let Ok(proof) =
+                            build_receipt_proof(block_hash, &block, &receipts, receipts_trie, i)
+                        {
                             created_proof = true;
                             merkle_proofs.push(proof);
                         }
                     }
                 }
 
-                if !created_proof {
+                if !created_proof && !receipts_root_mismatch {
                     log::info!(target: TARGET, "false positive bloom filter for block {}", block_height);
                     if let Err(e) = self.db.mark_block_processed(block_height) {
                         log::warn!(target: TARGET, "Error while marking block {} as processed: {}", block_height, e);
@@ -241,18 +256,10 @@ fn build_receipt_proof(
     block_hash: H256,
     block: &BlockHeaderWithTransaction,
     receipts: &[TransactionReceipt],
+    receipts_trie: &ReceiptsTrie,
     receipt_index: usize,
 ) -> eyre::Result<types::EventProof, eyre::Error> {
-    use merkle_generator::IterativeTrie;
-
-    let mut trie = merkle_generator::PatriciaTrie::new();
-
-    for (index, receipt) in receipts.iter().enumerate() {
-        let key = alloy_rlp::encode(index);
-        trie.insert(key, alloy_rlp::encode(receipt));
-    }
-
-    let merkle_proof = trie.merkle_proof(alloy_rlp::encode(receipt_index));
+    let merkle_proof = receipts_trie.merkle_proof(receipt_index);
     let event_proof = types::EventProof {
         block_header: block.header.clone(),
         block_hash,
@@ -0,0 +1,322 @@
+//! Registry of the networks the relayer can follow, see `chains.toml` at the repository root.
+
+use std::{collections::BTreeMap, path::Path};
+
+use eyre::Result;
+use serde::{Deserialize, Deserializer};
+
+/// Networks bundled with the relayer.
+const BUNDLED_CHAINS: &str = include_str!("../../chains.toml");
+
+/// Kind of `TypedChainId` the chain id of a network is wrapped in on the GGX side.
+///
+/// Only EVM chains are supported: the relayer follows their finality on the beacon chain with
+/// Helios, and proves receipts of their execution layer.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainType {
+    #[default]
+    Evm,
+}
+
+/// How the finality of a network is followed.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConsensusType {
+    /// Ethereum beacon chain, followed with Helios.
+    Beacon,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Genesis {
+    pub time: u64,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub validators_root: Vec<u8>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Fork {
+    pub epoch: u64,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub fork_version: Vec<u8>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ForkSchedule {
+    pub genesis: Fork,
+    pub altair: Fork,
+    pub bellatrix: Fork,
+    pub capella: Fork,
+    /// Not used by Helios yet.
+    pub deneb: Option<Fork>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSpec {
+    pub chain_id: u32,
+    #[serde(default)]
+    pub chain_type: ChainType,
+    pub consensus: ConsensusType,
+    /// Used if the Helios config of the network doesn't set it.
+    pub execution_rpc: Option<String>,
+    /// Used if the Helios config of the network doesn't set it.
+    pub consensus_rpc: Option<String>,
+    /// Needed by Helios for networks it doesn't know.
+    pub genesis: Option<Genesis>,
+    /// Needed by Helios for networks it doesn't know.
+    pub forks: Option<ForkSchedule>,
+}
+
+impl ChainSpec {
+    /// Adds the defaults of the network to its section of a Helios config file, keeping what the
+    /// file already sets.
+    pub fn helios_config_with_defaults(
+        &self,
+        helios_config: &str,
+        network: &str,
+    ) -> Result<String> {
+        let mut helios_config: toml::Table = toml::from_str(helios_config)?;
+        let section = helios_config
+            .entry(network)
+            .or_insert_with(|| toml::Value::Table(Default::default()))
+            .as_table_mut()
+            .ok_or_else(|| eyre::eyre!("Helios config of {network} is not a table"))?;
+
+        for (key, rpc) in [
+            ("execution_rpc", &self.execution_rpc),
+            ("consensus_rpc", &self.consensus_rpc),
+        ] {
+            if let Some(rpc) = rpc {
+                section
+                    .entry(key)
+                    .or_insert_with(|| toml::Value::String(rpc.clone()));
+            }
+        }
+
+        if let Some(genesis) = &self.genesis {
+            let genesis_time = i64::try_from(genesis.time)
+                .map_err(|_| eyre::eyre!("Genesis time of {network} is out of range"))?;
+            section.entry("chain").or_insert_with(|| {
+                toml::Value::Table(toml::Table::from_iter([
+                    (
+                        "chain_id".to_string(),
+                        toml::Value::Integer(self.chain_id.into()),
+                    ),
+                    (
+                        "genesis_time".to_string(),
+                        toml::Value::Integer(genesis_time),
+                    ),
+                    (
+                        "genesis_root".to_string(),
+                        toml::Value::String(to_hex(&genesis.validators_root)),
+                    ),
+                ]))
+            });
+        }
+
+        if let Some(forks) = &self.forks {
+            section.entry("forks").or_insert_with(|| {
+                toml::Value::Table(toml::Table::from_iter(
+                    [
+                        ("genesis", &forks.genesis),
+                        ("altair", &forks.altair),
+                        ("bellatrix", &forks.bellatrix),
+                        ("capella", &forks.capella),
+                    ]
+                    .map(|(name, fork)| {
+                        // Unscheduled forks have the epoch `FAR_FUTURE_EPOCH`, `u64::MAX`, which
+                        // TOML integers can't hold. Any epoch past `i64::MAX` is as unreachable.
+                        let epoch = i64::try_from(fork.epoch).unwrap_or(i64::MAX);
+                        (
+                            name.to_string(),
+                            toml::Value::Table(toml::Table::from_iter([
+                                ("epoch".to_string(), toml::Value::Integer(epoch)),
+                                (
+                                    "fork_version".to_string(),
+                                    toml::Value::String(to_hex(&fork.fork_version)),
+                                ),
+                            ])),
+                        )
+                    }),
+                ))
+            });
+        }
+
+        Ok(toml::to_string(&helios_config)?)
+    }
+}
+
+/// Networks by name.
+#[derive(Debug, Clone)]
+pub struct ChainRegistry {
+    chains: BTreeMap<String, ChainSpec>,
+}
+
+impl ChainRegistry {
+    /// Loads the bundled networks, extended or overridden by the ones of the file at `path`.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut registry = Self::parse(BUNDLED_CHAINS)?;
+        if let Some(path) = path {
+            let content = std::fs::read_to_string(path)?;
+            registry.chains.extend(Self::parse(&content)?.chains);
+        }
+        Ok(registry)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let chains = toml::from_str(content)?;
+        Ok(Self { chains })
+    }
+
+    pub fn get(&self, network: &str) -> Result<&ChainSpec> {
+        self.chains.get(network).ok_or_else(|| {
+            let known = self.chains.keys().cloned().collect::<Vec<_>>().join(", ");
+            eyre::eyre!("Unknown network name {network}, known networks: {known}")
+        })
+    }
+}
+
+fn hex_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    hex::decode(value.trim_start_matches("0x")).map_err(serde::de::Error::custom)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_networks() {
+        let registry = ChainRegistry::load(None).unwrap();
+        for (network, chain_id) in [
+            ("mainnet", 1),
+            ("goerli", 5),
+            ("sepolia", 11155111),
+            ("holesky", 17000),
+        ] {
+            let chain = registry.get(network).unwrap();
+            assert_eq!(chain.chain_id, chain_id);
+            assert_eq!(chain.chain_type, ChainType::Evm);
+            assert_eq!(chain.consensus, ConsensusType::Beacon);
+            let forks = chain.forks.as_ref().unwrap();
+            assert_eq!(forks.genesis.fork_version.len(), 4);
+            assert!(forks.altair.epoch <= forks.bellatrix.epoch);
+            assert!(forks.bellatrix.epoch <= forks.capella.epoch);
+            assert_eq!(chain.genesis.as_ref().unwrap().validators_root.len(), 32);
+        }
+        assert!(registry.get("devnet").is_err());
+    }
+
+    #[test]
+    fn custom_networks() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            br#"
+            [devnet]
+            chain_id = 3151908
+            consensus = "beacon"
+            execution_rpc = "http://127.0.0.1:8545"
+
+            [goerli]
+            chain_id = 5
+            consensus = "beacon"
+            consensus_rpc = "http://127.0.0.1:4000"
+            "#,
+        )
+        .unwrap();
+
+        let registry = ChainRegistry::load(Some(file.path())).unwrap();
+        let devnet = registry.get("devnet").unwrap();
+        assert_eq!(devnet.chain_id, 3151908);
+        assert_eq!(
+            devnet.execution_rpc.as_deref(),
+            Some("http://127.0.0.1:8545")
+        );
+        assert_eq!(devnet.forks, None);
+        assert_eq!(
+            registry.get("goerli").unwrap().consensus_rpc.as_deref(),
+            Some("http://127.0.0.1:4000")
+        );
+        assert_eq!(registry.get("sepolia").unwrap().chain_id, 11155111);
+
+        assert!(ChainRegistry::parse("[devnet]\nchain_id = 1\nconsensus = \"pow\"").is_err());
+        // Only EVM chains are supported.
+        assert!(ChainRegistry::parse(
+            "[devnet]\nchain_id = 1\nchain_type = \"substrate\"\nconsensus = \"beacon\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn helios_config_keeps_its_values() {
+        let registry = ChainRegistry::load(None).unwrap();
+        let holesky = registry.get("holesky").unwrap();
+        let helios_config = holesky
+            .helios_config_with_defaults(
+                r#"
+                [holesky]
+                consensus_rpc = "http://127.0.0.1:4000"
+                execution_rpc = "http://127.0.0.1:8545"
+
+                [sepolia]
+                consensus_rpc = "http://unstable.sepolia.beacon-api.nimbus.team"
+                "#,
+                "holesky",
+            )
+            .unwrap();
+
+        let helios_config: toml::Table = toml::from_str(&helios_config).unwrap();
+        let section = helios_config["holesky"].as_table().unwrap();
+        assert_eq!(
+            section["consensus_rpc"].as_str(),
+            Some("http://127.0.0.1:4000")
+        );
+        assert_eq!(section["chain"]["chain_id"].as_integer(), Some(17000));
+        assert_eq!(
+            section["chain"]["genesis_root"].as_str(),
+            Some("0x9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1")
+        );
+        assert_eq!(section["forks"]["capella"]["epoch"].as_integer(), Some(256));
+        assert_eq!(
+            section["forks"]["capella"]["fork_version"].as_str(),
+            Some("0x04017000")
+        );
+        assert!(section["forks"].get("deneb").is_none());
+        assert!(helios_config["sepolia"].get("chain").is_none());
+
+        // Unscheduled forks are kept, at an epoch that is never reached.
+        let mut devnet = holesky.clone();
+        devnet.forks.as_mut().unwrap().capella.epoch = u64::MAX;
+        let helios_config = devnet.helios_config_with_defaults("", "devnet").unwrap();
+        let helios_config: toml::Table = toml::from_str(&helios_config).unwrap();
+        assert_eq!(
+            helios_config["devnet"]["forks"]["capella"]["epoch"].as_integer(),
+            Some(i64::MAX)
+        );
+        assert_eq!(
+            helios_config["devnet"]["forks"]["bellatrix"]["epoch"].as_integer(),
+            Some(0)
+        );
+
+        devnet.genesis.as_mut().unwrap().time = u64::MAX;
+        assert!(devnet.helios_config_with_defaults("", "devnet").is_err());
+
+        // Networks without a section get one with the defaults of the registry.
+        let goerli = registry.get("goerli").unwrap();
+        let helios_config = goerli.helios_config_with_defaults("", "goerli").unwrap();
+        let helios_config: toml::Table = toml::from_str(&helios_config).unwrap();
+        assert_eq!(
+            helios_config["goerli"]["consensus_rpc"].as_str(),
+            Some("http://testing.prater.beacon-api.nimbus.team")
+        );
+    }
+}
@@ -17,7 +17,6 @@ use crate::{
     config::Config,
     consts::{BLOCK_AMOUNT_TO_STORE, SLEEP_DURATION},
     db::DB,
-    network_name_to_id,
     substrate_client::SubstrateClient,
 };
 
@@ -41,6 +40,7 @@ impl Client {
         db: DB,
         term: Arc<AtomicBool>,
         substrate_client: SubstrateClient,
+        chain_id: u32,
     ) -> Result<Self> {
         let helios_config = prepare_config(&config);
         let block_rpc =
@@ -61,7 +61,7 @@ impl Client {
             db,
             term,
             substrate_client,
-            chain_id: network_name_to_id(&config.network)?,
+            chain_id,
             blocks_to_store: config.blocks_to_store.unwrap_or(BLOCK_AMOUNT_TO_STORE),
             watched_addresses: None,
         })
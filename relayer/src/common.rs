@@ -1,4 +1,5 @@
 use std::{
+    path::PathBuf,
     process::exit,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -8,7 +9,7 @@ use std::{
 
 use types::{BlockHeaderWithTransaction, Bloom, TransactionReceipt, TxType, H160, H256, U256};
 
-use crate::config::Config;
+use crate::{chains::ChainSpec, config::Config};
 
 pub fn convert_ethers_receipt(
     receipt: ethers::types::TransactionReceipt,
@@ -90,6 +91,17 @@ pub fn convert_ethers_block(
     })
 }
 
+/// Writes the Helios config completed with the defaults of the network next to the database and
+/// returns its path.
+pub fn write_helios_config(config: &Config, chain: &ChainSpec) -> eyre::Result<PathBuf> {
+    let helios_config = std::fs::read_to_string(&config.helios_config_path)?;
+    let helios_config = chain.helios_config_with_defaults(&helios_config, &config.network)?;
+    let path = config.database.join("helios.toml");
+    std::fs::write(&path, helios_config)?;
+
+    Ok(path)
+}
+
 pub fn prepare_config(config: &Config) -> helios::config::Config {
     let helios_config: helios::config::Config = helios::config::Config::from_file(
         &config.helios_config_path,
@@ -14,6 +14,9 @@ pub struct Config {
     pub substrate_config_path: PathBuf,
     #[arg(long)]
     pub helios_config_path: PathBuf,
+    /// Networks added to or replacing the bundled ones, see `chains.toml`.
+    #[arg(long)]
+    pub chains_config_path: Option<PathBuf>,
     #[arg(long)]
     pub server_host: Option<String>,
     #[arg(long)]
@@ -6,6 +6,7 @@ use eyre::Result;
 use tokio::fs;
 
 mod bloom_processor;
+mod chains;
 mod client;
 pub(crate) mod common;
 mod config;
@@ -13,6 +14,7 @@ pub(crate) mod consts;
 mod db;
 mod substrate_client;
 
+use chains::ChainRegistry;
 use config::Config;
 use db::DB;
 use substrate_client::SubstrateClient;
@@ -21,7 +23,7 @@ use substrate_client::SubstrateClient;
 async fn main() -> Result<()> {
     env_logger::init();
 
-    let config = Config::parse();
+    let mut config = Config::parse();
     let term = Arc::new(AtomicBool::new(false));
     signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&term))?;
 
@@ -32,14 +34,19 @@ async fn main() -> Result<()> {
     let db = DB::new(&config.database)?;
     db.create_tables()?;
 
-    let chain_id: u32 = network_name_to_id(&config.network)?;
-    let substrate_client = SubstrateClient::new(&config.substrate_config_path, chain_id).await?;
+    let chains = ChainRegistry::load(config.chains_config_path.as_deref())?;
+    let chain = chains.get(&config.network)?;
+    let chain_id = chain.chain_id;
+    config.helios_config_path = common::write_helios_config(&config, chain)?;
+    let substrate_client =
+        SubstrateClient::new(&config.substrate_config_path, chain_id, chain.chain_type).await?;
 
     let mut client = Client::new(
         config.clone(),
         db.clone(),
         term.clone(),
         substrate_client.clone(),
+        chain_id,
     )?;
     let mut bloom_processor =
         bloom_processor::BloomProcessor::new(db.clone(), config, term, substrate_client, chain_id)?;
@@ -59,12 +66,3 @@ async fn main() -> Result<()> {
     }
     Ok(())
 }
-
-fn network_name_to_id(network_name: &str) -> Result<u32> {
-    match network_name {
-        "mainnet" => Ok(1),
-        "goerli" => Ok(5),
-        "sepolia" => Ok(11155111),
-        _ => Err(eyre::eyre!("Unknown network name {}", network_name)),
-    }
-}
@@ -8,7 +8,7 @@ use subxt_signer::{
 };
 use types::H160;
 
-use crate::consts::UPDATE_WATCHED_ADDRESSES_INTERVAL;
+use crate::{chains::ChainType, consts::UPDATE_WATCHED_ADDRESSES_INTERVAL};
 
 use self::ggxchain::runtime_types::webb_proposals::header::TypedChainId;
 
@@ -17,12 +17,17 @@ pub struct SubstrateClient {
     api: OnlineClient<PolkadotConfig>,
     keypair: Keypair,
     chain_id: u32,
+    chain_type: ChainType,
 
     watched_addresses: HashMap<u32, (Duration, Vec<H160>)>,
 }
 
 impl SubstrateClient {
-    pub async fn new(substrate_config_path: &Path, chain_id: u32) -> Result<Self> {
+    pub async fn new(
+        substrate_config_path: &Path,
+        chain_id: u32,
+        chain_type: ChainType,
+    ) -> Result<Self> {
         let file_content = std::fs::read_to_string(substrate_config_path)?;
         let config: SubstrateConfig = toml::from_str(&file_content)?;
         let api = OnlineClient::<PolkadotConfig>::from_url(&config.ws_url)
@@ -43,16 +48,24 @@ impl SubstrateClient {
             api,
             keypair,
             chain_id,
+            chain_type,
             watched_addresses: HashMap::new(),
         })
     }
 
+    fn typed_chain_id(&self, chain_id: u32) -> TypedChainId {
+        match self.chain_type {
+            ChainType::Evm => TypedChainId::Evm(chain_id),
+        }
+    }
+
     pub async fn send_event_proof(&self, event_proof: types::EventProof, nonce: u64) -> Result<()> {
         // TODO: Ideally we should check if the proof isn't already submitted
         // but let's skip this for now
 
+        // The Merkle proof is serialized as the RLP of its compact form, see `CompactMerkleProof`
         let tx = ggxchain::tx().eth_receipt_registry().submit_proof(
-            TypedChainId::Evm(self.chain_id),
+            self.typed_chain_id(self.chain_id),
             serde_json::to_vec(&event_proof)?,
         );
         let mut tx_progress = self
@@ -176,7 +189,7 @@ impl SubstrateClient {
 
         let query = ggxchain::storage()
             .eth_receipt_registry()
-            .watched_contracts(TypedChainId::Evm(chain_id));
+            .watched_contracts(self.typed_chain_id(chain_id));
         let result: Vec<H160> = self
             .api
             .storage()
@@ -197,7 +210,7 @@ impl SubstrateClient {
     pub async fn last_known_block_block_number(&self, chain_id: u32) -> Result<u64> {
         let query = ggxchain::storage()
             .eth2_client()
-            .finalized_execution_header(TypedChainId::Evm(chain_id));
+            .finalized_execution_header(self.typed_chain_id(chain_id));
 
         let result = self.api.storage().at_latest().await?.fetch(&query).await?;
         result
@@ -209,7 +222,7 @@ impl SubstrateClient {
         let query = ggxchain::storage()
             .eth_receipt_registry()
             .processed_receipts_hash(
-                TypedChainId::Evm(chain_id),
+                self.typed_chain_id(chain_id),
                 subxt::utils::Static(receipt_hash),
             );
 
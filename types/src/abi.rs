@@ -0,0 +1,627 @@
+use alloc::vec::Vec;
+use keccak_hash::keccak;
+use parity_scale_codec::Encode;
+
+use crate::{Log, H160, H256, U256};
+
+/// Solidity type of an event parameter. Only the elementary types are supported, which covers
+/// the vast majority of events emitted by token and bridge contracts.
+#[derive(
+    Debug,
+    PartialEq,
+    Clone,
+    scale_info::TypeInfo,
+    parity_scale_codec::Encode,
+    parity_scale_codec::Decode,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AbiType {
+    Address,
+    Bool,
+    /// `uintN`, where the value is the bit size `N`.
+    Uint(u16),
+    /// `intN`, where the value is the bit size `N`.
+    Int(u16),
+    /// `bytesN`, where the value is the byte size `N`.
+    FixedBytes(u8),
+    Bytes,
+    String,
+}
+
+impl AbiType {
+    fn is_dynamic(&self) -> bool {
+        matches!(self, AbiType::Bytes | AbiType::String)
+    }
+
+    /// Parses a type as written in a canonical event signature, e.g. `uint256` or `bytes32`.
+    fn parse(name: &[u8]) -> Result<Self, AbiError> {
+        let size = |prefix: &[u8]| -> Result<u16, AbiError> {
+            let digits = &name[prefix.len()..];
+            // Canonical signatures always spell out the size, without leading zeros.
+            if digits.is_empty() || digits[0] == b'0' || digits.len() > 3 {
+                return Err(AbiError::UnsupportedType);
+            }
+            let mut size = 0u16;
+            for digit in digits {
+                if !digit.is_ascii_digit() {
+                    return Err(AbiError::UnsupportedType);
+                }
+                size = size * 10 + u16::from(digit - b'0');
+            }
+            Ok(size)
+        };
+
+        let kind = match name {
+            b"address" => AbiType::Address,
+            b"bool" => AbiType::Bool,
+            b"bytes" => AbiType::Bytes,
+            b"string" => AbiType::String,
+            _ if name.starts_with(b"uint") => {
+                let bits = size(b"uint")?;
+                bit_size_to_bytes(bits).map_err(|_| AbiError::UnsupportedType)?;
+                AbiType::Uint(bits)
+            }
+            _ if name.starts_with(b"int") => {
+                let bits = size(b"int")?;
+                bit_size_to_bytes(bits).map_err(|_| AbiError::UnsupportedType)?;
+                AbiType::Int(bits)
+            }
+            _ if name.starts_with(b"bytes") => match size(b"bytes")? {
+                bytes @ 1..=32 => AbiType::FixedBytes(bytes as u8),
+                _ => return Err(AbiError::UnsupportedType),
+            },
+            _ => return Err(AbiError::UnsupportedType),
+        };
+        Ok(kind)
+    }
+}
+
+/// A single event parameter.
+#[derive(
+    Debug,
+    PartialEq,
+    Clone,
+    scale_info::TypeInfo,
+    parity_scale_codec::Encode,
+    parity_scale_codec::Decode,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AbiParam {
+    pub kind: AbiType,
+    /// Indexed parameters are stored in the log topics, the rest is ABI-encoded in the log data.
+    pub indexed: bool,
+}
+
+/// Description of a Solidity event, used to decode a [`Log`] into typed values.
+#[derive(
+    Debug,
+    PartialEq,
+    Clone,
+    scale_info::TypeInfo,
+    parity_scale_codec::Encode,
+    parity_scale_codec::Decode,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventAbi {
+    /// Canonical event signature, e.g. `Transfer(address,address,uint256)`. Its keccak hash must
+    /// be equal to the first topic of the log.
+    pub signature: Vec<u8>,
+    /// Parameters in the order of declaration. Their types have to match the signature.
+    pub params: Vec<AbiParam>,
+}
+
+/// A decoded event parameter.
+#[derive(
+    Debug,
+    PartialEq,
+    Clone,
+    scale_info::TypeInfo,
+    parity_scale_codec::Encode,
+    parity_scale_codec::Decode,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AbiValue {
+    Address(H160),
+    Bool(bool),
+    Uint(U256),
+    /// Two's complement representation of the signed integer.
+    Int(U256),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(Vec<u8>),
+    /// Indexed dynamic parameters are stored as the keccak hash of their value.
+    Hashed(H256),
+}
+
+/// Error type for decoding a [`Log`] with an [`EventAbi`].
+#[derive(Debug, PartialEq)]
+pub enum AbiError {
+    SignatureMismatch {
+        expected: H256,
+        actual: Option<H256>,
+    },
+    TopicCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The signature is not of the form `Name(type1,type2,...)`.
+    InvalidSignature,
+    /// The signature contains a type that can't be decoded, e.g. an array or a tuple.
+    UnsupportedType,
+    /// The parameters don't have the types declared by the signature.
+    ParamsMismatch,
+    DataTooShort,
+    InvalidOffset,
+    InvalidValue,
+    /// The data is not the canonical encoding of the parameters, e.g. it has trailing bytes.
+    NonCanonicalData,
+}
+
+const WORD: usize = 32;
+
+impl EventAbi {
+    /// Builds the description of an event from its canonical signature, with the types of the
+    /// parameters taken from the signature. `indexed` flags the parameters in the log topics.
+    pub fn new(signature: Vec<u8>, indexed: &[bool]) -> Result<Self, AbiError> {
+        let kinds = parse_signature(&signature)?;
+        if kinds.len() != indexed.len() {
+            return Err(AbiError::ParamsMismatch);
+        }
+        let params = kinds
+            .into_iter()
+            .zip(indexed)
+            .map(|(kind, indexed)| AbiParam {
+                kind,
+                indexed: *indexed,
+            })
+            .collect();
+        Ok(EventAbi { signature, params })
+    }
+
+    /// Hash of the event signature, i.e. the first topic of a log emitted for this event.
+    pub fn topic(&self) -> H256 {
+        H256(keccak(&self.signature).0)
+    }
+
+    /// Identifier of the whole description. Unlike [`Self::topic`], it also covers the `indexed`
+    /// flags, so two decodings of the same log with different ABIs have different ids.
+    pub fn id(&self) -> H256 {
+        H256(keccak(self.encode()).0)
+    }
+
+    /// Decodes the topics and data of the log into the parameters of this event.
+    pub fn decode_log(&self, log: &Log) -> Result<Vec<AbiValue>, AbiError> {
+        let kinds = parse_signature(&self.signature)?;
+        if kinds.len() != self.params.len()
+            || kinds
+                .iter()
+                .zip(self.params.iter())
+                .any(|(kind, param)| *kind != param.kind)
+        {
+            return Err(AbiError::ParamsMismatch);
+        }
+
+        let expected = self.topic();
+        let actual = log.topics.first().copied();
+        if actual != Some(expected) {
+            return Err(AbiError::SignatureMismatch { expected, actual });
+        }
+
+        let indexed = self.params.iter().filter(|param| param.indexed).count();
+        if log.topics.len() != indexed + 1 {
+            return Err(AbiError::TopicCountMismatch {
+                expected: indexed + 1,
+                actual: log.topics.len(),
+            });
+        }
+
+        let mut topics = log.topics[1..].iter();
+        let mut head = 0;
+        // Dynamic values are encoded one after another after the heads of all the parameters.
+        let mut tail = WORD * (self.params.len() - indexed);
+        let mut values = Vec::with_capacity(self.params.len());
+        for param in self.params.iter() {
+            let value = if param.indexed {
+                let topic = topics.next().expect("topic count checked above; qed");
+                if param.kind.is_dynamic() {
+                    AbiValue::Hashed(*topic)
+                } else {
+                    decode_word(&param.kind, &topic.0)?
+                }
+            } else {
+                let word = word_at(&log.data, head)?;
+                head += WORD;
+                if param.kind.is_dynamic() {
+                    if word_to_usize(word)? != tail {
+                        return Err(AbiError::NonCanonicalData);
+                    }
+                    let value = decode_dynamic(&param.kind, &log.data, word)?;
+                    if let AbiValue::Bytes(bytes) | AbiValue::String(bytes) = &value {
+                        tail += WORD + bytes.len().div_ceil(WORD) * WORD;
+                    }
+                    value
+                } else {
+                    decode_word(&param.kind, word)?
+                }
+            };
+            values.push(value);
+        }
+        if log.data.len() != tail {
+            return Err(AbiError::NonCanonicalData);
+        }
+        Ok(values)
+    }
+}
+
+/// Parses the types of the parameters from a canonical event signature.
+fn parse_signature(signature: &[u8]) -> Result<Vec<AbiType>, AbiError> {
+    let open = signature
+        .iter()
+        .position(|byte| *byte == b'(')
+        .ok_or(AbiError::InvalidSignature)?;
+    let (name, params) = signature.split_at(open);
+    let params = params
+        .strip_prefix(b"(")
+        .and_then(|params| params.strip_suffix(b")"))
+        .ok_or(AbiError::InvalidSignature)?;
+    if name.is_empty()
+        || name[0].is_ascii_digit()
+        || !name
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || *byte == b'_' || *byte == b'$')
+    {
+        return Err(AbiError::InvalidSignature);
+    }
+
+    if params.is_empty() {
+        return Ok(Vec::new());
+    }
+    params
+        .split(|byte| *byte == b',')
+        .map(AbiType::parse)
+        .collect()
+}
+
+fn word_at(data: &[u8], offset: usize) -> Result<&[u8; WORD], AbiError> {
+    let end = offset.checked_add(WORD).ok_or(AbiError::InvalidOffset)?;
+    data.get(offset..end)
+        .map(|word| word.try_into().expect("slice is exactly one word; qed"))
+        .ok_or(AbiError::DataTooShort)
+}
+
+fn word_to_usize(word: &[u8; WORD]) -> Result<usize, AbiError> {
+    if word[..WORD - 8].iter().any(|byte| *byte != 0) {
+        return Err(AbiError::InvalidOffset);
+    }
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&word[WORD - 8..]);
+    usize::try_from(u64::from_be_bytes(bytes)).map_err(|_| AbiError::InvalidOffset)
+}
+
+fn decode_word(kind: &AbiType, word: &[u8; WORD]) -> Result<AbiValue, AbiError> {
+    match kind {
+        AbiType::Address => {
+            if word[..12].iter().any(|byte| *byte != 0) {
+                return Err(AbiError::InvalidValue);
+            }
+            Ok(AbiValue::Address(H160::from_slice(&word[12..])))
+        }
+        AbiType::Bool => match (&word[..WORD - 1], word[WORD - 1]) {
+            (padding, value @ (0 | 1)) if padding.iter().all(|byte| *byte == 0) => {
+                Ok(AbiValue::Bool(value == 1))
+            }
+            _ => Err(AbiError::InvalidValue),
+        },
+        AbiType::Uint(bits) => {
+            let padding = WORD - bit_size_to_bytes(*bits)?;
+            if word[..padding].iter().any(|byte| *byte != 0) {
+                return Err(AbiError::InvalidValue);
+            }
+            Ok(AbiValue::Uint(U256(*word)))
+        }
+        AbiType::Int(bits) => {
+            let padding = WORD - bit_size_to_bytes(*bits)?;
+            // The padding has to be a sign extension of the value.
+            let sign = if word[padding] & 0x80 == 0 {
+                0x00
+            } else {
+                0xff
+            };
+            if word[..padding].iter().any(|byte| *byte != sign) {
+                return Err(AbiError::InvalidValue);
+            }
+            Ok(AbiValue::Int(U256(*word)))
+        }
+        AbiType::FixedBytes(size) => {
+            let size = *size as usize;
+            if size == 0 || size > WORD || word[size..].iter().any(|byte| *byte != 0) {
+                return Err(AbiError::InvalidValue);
+            }
+            Ok(AbiValue::FixedBytes(word[..size].to_vec()))
+        }
+        AbiType::Bytes | AbiType::String => Err(AbiError::InvalidValue),
+    }
+}
+
+fn decode_dynamic(kind: &AbiType, data: &[u8], head: &[u8; WORD]) -> Result<AbiValue, AbiError> {
+    let offset = word_to_usize(head)?;
+    let length = word_to_usize(word_at(data, offset)?)?;
+    let start = offset + WORD;
+    let end = start.checked_add(length).ok_or(AbiError::InvalidOffset)?;
+    let bytes = data.get(start..end).ok_or(AbiError::DataTooShort)?.to_vec();
+    match kind {
+        AbiType::Bytes => Ok(AbiValue::Bytes(bytes)),
+        AbiType::String => Ok(AbiValue::String(bytes)),
+        _ => Err(AbiError::InvalidValue),
+    }
+}
+
+fn bit_size_to_bytes(bits: u16) -> Result<usize, AbiError> {
+    if bits == 0 || bits > 256 || bits % 8 != 0 {
+        return Err(AbiError::InvalidValue);
+    }
+    Ok(bits as usize / 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use super::*;
+
+    fn transfer_abi() -> EventAbi {
+        EventAbi {
+            signature: b"Transfer(address,address,uint256)".to_vec(),
+            params: vec![
+                AbiParam {
+                    kind: AbiType::Address,
+                    indexed: true,
+                },
+                AbiParam {
+                    kind: AbiType::Address,
+                    indexed: true,
+                },
+                AbiParam {
+                    kind: AbiType::Uint(256),
+                    indexed: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn decode_erc20_transfer() {
+        let abi = transfer_abi();
+        assert_eq!(
+            abi.topic(),
+            H256(hex!(
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+            ))
+        );
+
+        let log = Log {
+            address: H160(hex!("514910771af9ca656af840dff83e8264ecf986ca")),
+            topics: vec![
+                abi.topic(),
+                H256(hex!(
+                    "0000000000000000000000001111111111111111111111111111111111111111"
+                )),
+                H256(hex!(
+                    "0000000000000000000000002222222222222222222222222222222222222222"
+                )),
+            ],
+            data: hex!("00000000000000000000000000000000000000000000000000000000000003e8").to_vec(),
+        };
+
+        assert_eq!(
+            abi.decode_log(&log).unwrap(),
+            vec![
+                AbiValue::Address(H160([0x11; 20])),
+                AbiValue::Address(H160([0x22; 20])),
+                AbiValue::Uint(U256::from(1000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_dynamic_parameters() {
+        let abi = EventAbi {
+            signature: b"Message(string,bytes,bool)".to_vec(),
+            params: vec![
+                AbiParam {
+                    kind: AbiType::String,
+                    indexed: true,
+                },
+                AbiParam {
+                    kind: AbiType::Bytes,
+                    indexed: false,
+                },
+                AbiParam {
+                    kind: AbiType::Bool,
+                    indexed: false,
+                },
+            ],
+        };
+        let hashed = H256(keccak(b"indexed").0);
+        let log = Log {
+            address: H160::new([1; 20]),
+            topics: vec![abi.topic(), hashed],
+            data: hex!(
+                "0000000000000000000000000000000000000000000000000000000000000040"
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "0000000000000000000000000000000000000000000000000000000000000003"
+                "0102030000000000000000000000000000000000000000000000000000000000"
+            )
+            .to_vec(),
+        };
+
+        assert_eq!(
+            abi.decode_log(&log).unwrap(),
+            vec![
+                AbiValue::Hashed(hashed),
+                AbiValue::Bytes(vec![1, 2, 3]),
+                AbiValue::Bool(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_signed_integer() {
+        let abi = EventAbi {
+            signature: b"Delta(int8)".to_vec(),
+            params: vec![AbiParam {
+                kind: AbiType::Int(8),
+                indexed: false,
+            }],
+        };
+        let mut log = Log {
+            address: H160::new([1; 20]),
+            topics: vec![abi.topic()],
+            data: [0xff; 32].to_vec(),
+        };
+        assert_eq!(
+            abi.decode_log(&log).unwrap(),
+            vec![AbiValue::Int(U256([0xff; 32]))]
+        );
+
+        // Negative byte without sign extension is not a valid int8.
+        log.data = U256::from(0xff).0.to_vec();
+        assert_eq!(abi.decode_log(&log), Err(AbiError::InvalidValue));
+    }
+
+    #[test]
+    fn decode_errors() {
+        let abi = transfer_abi();
+        let mut log = Log {
+            address: H160::new([1; 20]),
+            topics: vec![H256::zero()],
+            data: vec![],
+        };
+        assert_eq!(
+            abi.decode_log(&log),
+            Err(AbiError::SignatureMismatch {
+                expected: abi.topic(),
+                actual: Some(H256::zero()),
+            })
+        );
+
+        log.topics = vec![abi.topic(), H256::zero()];
+        assert_eq!(
+            abi.decode_log(&log),
+            Err(AbiError::TopicCountMismatch {
+                expected: 3,
+                actual: 2,
+            })
+        );
+
+        log.topics = vec![abi.topic(), H256::zero(), H256::zero()];
+        assert_eq!(abi.decode_log(&log), Err(AbiError::DataTooShort));
+
+        log.topics[1] = H256([0xff; 32]);
+        log.data = [0; 32].to_vec();
+        assert_eq!(abi.decode_log(&log), Err(AbiError::InvalidValue));
+    }
+
+    #[test]
+    fn params_from_signature() {
+        assert_eq!(
+            EventAbi::new(
+                b"Transfer(address,address,uint256)".to_vec(),
+                &[true, true, false]
+            ),
+            Ok(transfer_abi())
+        );
+        assert_eq!(
+            EventAbi::new(b"Ping()".to_vec(), &[]).unwrap().params,
+            vec![]
+        );
+        assert_eq!(
+            EventAbi::new(b"Data(bytes32,int8,string,bool)".to_vec(), &[false; 4])
+                .unwrap()
+                .params
+                .into_iter()
+                .map(|param| param.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                AbiType::FixedBytes(32),
+                AbiType::Int(8),
+                AbiType::String,
+                AbiType::Bool,
+            ]
+        );
+
+        assert_eq!(
+            EventAbi::new(b"Transfer(address,address,uint256)".to_vec(), &[true]),
+            Err(AbiError::ParamsMismatch)
+        );
+        for signature in [&b"Transfer"[..], b"(uint256)", b"Transfer(uint256", b"1a()"] {
+            assert_eq!(
+                EventAbi::new(signature.to_vec(), &[false]),
+                Err(AbiError::InvalidSignature)
+            );
+        }
+        for signature in [
+            &b"A(uint)"[..],
+            b"A(uint7)",
+            b"A(uint264)",
+            b"A(uint0256)",
+            b"A(bytes33)",
+            b"A(uint256[])",
+            b"A((uint256,bool))",
+            b"A(address )",
+        ] {
+            assert_eq!(
+                EventAbi::new(signature.to_vec(), &[false]),
+                Err(AbiError::UnsupportedType)
+            );
+        }
+    }
+
+    #[test]
+    fn reject_params_not_matching_signature() {
+        let mut abi = transfer_abi();
+        let log = Log {
+            address: H160::new([1; 20]),
+            topics: vec![abi.topic(), H256::zero(), H256::zero()],
+            data: U256::from(1000).0.to_vec(),
+        };
+        abi.decode_log(&log).unwrap();
+
+        // The amount would be decoded as a small integer even though it's an uint256.
+        abi.params[2].kind = AbiType::Uint(16);
+        assert_eq!(abi.decode_log(&log), Err(AbiError::ParamsMismatch));
+
+        abi.params.pop();
+        assert_eq!(abi.decode_log(&log), Err(AbiError::ParamsMismatch));
+    }
+
+    #[test]
+    fn reject_non_canonical_data() {
+        let abi = transfer_abi();
+        let mut log = Log {
+            address: H160::new([1; 20]),
+            topics: vec![abi.topic(), H256::zero(), H256::zero()],
+            data: [U256::from(1000).0, [0; 32]].concat(),
+        };
+        assert_eq!(abi.decode_log(&log), Err(AbiError::NonCanonicalData));
+
+        // The value of a dynamic parameter has to follow the heads of the parameters.
+        let abi = EventAbi::new(b"Message(bytes)".to_vec(), &[false]).unwrap();
+        log.topics = vec![abi.topic()];
+        log.data = hex!(
+            "0000000000000000000000000000000000000000000000000000000000000040"
+            "0000000000000000000000000000000000000000000000000000000000000000"
+            "0000000000000000000000000000000000000000000000000000000000000001"
+            "0100000000000000000000000000000000000000000000000000000000000000"
+        )
+        .to_vec();
+        assert_eq!(abi.decode_log(&log), Err(AbiError::NonCanonicalData));
+
+        log.data.drain(32..64);
+        log.data[31] = 0x20;
+        assert_eq!(
+            abi.decode_log(&log).unwrap(),
+            vec![AbiValue::Bytes(vec![1])]
+        );
+    }
+}
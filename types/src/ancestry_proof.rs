@@ -0,0 +1,178 @@
+use alloc::vec::Vec;
+
+use crate::{BlockHeader, EventProof, ValidationError, H256};
+
+/// Proof of a receipt included in a block that is not stored by the light client itself, but is
+/// an ancestor of a stored finalized block.
+///
+/// The proven block is linked to the finalized anchor by the headers of all its descendants up to
+/// the anchor, each one referencing the previous one by `parent_hash`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AncestryProof {
+    /// Proof of inclusion of the receipt in the target block.
+    pub event_proof: EventProof,
+
+    /// Headers of the descendants of the target block, starting with its child and ending with
+    /// the finalized anchor. Empty if the target block is the anchor.
+    pub ancestry: Vec<BlockHeader>,
+}
+
+impl AncestryProof {
+    /// Check that the receipt proof is valid and that the headers form an unbroken chain from
+    /// the target block to the anchor.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.event_proof.validate()?;
+
+        let mut child_of = (
+            self.event_proof.block_header.number,
+            self.event_proof.block_hash,
+        );
+        for header in &self.ancestry {
+            let (number, hash) = child_of;
+            let expected = number
+                .checked_add(1)
+                .ok_or(ValidationError::BlockNumberOverflow { number })?;
+            if header.number != expected {
+                return Err(ValidationError::NonConsecutiveBlockNumber {
+                    expected,
+                    actual: header.number,
+                });
+            }
+            if header.parent_hash != hash {
+                return Err(ValidationError::IncorrectParentHash {
+                    number: header.number,
+                    expected: hash,
+                    actual: header.parent_hash,
+                });
+            }
+            child_of = (header.number, H256::hash(header));
+        }
+        Ok(())
+    }
+
+    /// Number and hash of the block that has to be finalized for the proof to be accepted.
+    pub fn anchor(&self) -> (u64, H256) {
+        match self.ancestry.last() {
+            Some(header) => (header.number, H256::hash(header)),
+            None => (
+                self.event_proof.block_header.number,
+                self.event_proof.block_hash,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        BlockHeader, Bloom, EventProof, MerkleProof, Receipt, TransactionReceipt, TxType,
+        ValidationError, H160, H256,
+    };
+
+    use super::AncestryProof;
+
+    fn header(number: u64, parent_hash: H256) -> BlockHeader {
+        BlockHeader {
+            parent_hash,
+            ommers_hash: H256::zero(),
+            beneficiary: H160::new([0u8; 20]),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: H256::zero(),
+            withdrawals_root: None,
+            logs_bloom: Bloom::new([0; 256]),
+            difficulty: 0.into(),
+            number,
+            gas_limit: 0,
+            gas_used: 0,
+            timestamp: 0,
+            mix_hash: H256::zero(),
+            nonce: 0,
+            base_fee_per_gas: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            extra_data: vec![],
+        }
+    }
+
+    /// Builds a proof for a single receipt block at `number` followed by `descendants` headers.
+    fn ancestry_proof(number: u64, descendants: u64) -> AncestryProof {
+        let transaction_receipt = TransactionReceipt {
+            bloom: Bloom::new([0; 256]),
+            receipt: Receipt {
+                tx_type: TxType::Legacy,
+                success: true,
+                cumulative_gas_used: 21000,
+                logs: vec![],
+            },
+        };
+        let merkle_proof_of_receipt = MerkleProof {
+            proof: vec![],
+            key: alloy_rlp::encode(0usize),
+        };
+        let mut block_header = header(number, H256([7; 32]));
+        block_header.receipts_root = merkle_proof_of_receipt.merkle_root(&transaction_receipt);
+
+        let mut parent_hash = H256::hash(&block_header);
+        let ancestry = (1..=descendants)
+            .map(|offset| {
+                let header = header(number + offset, parent_hash);
+                parent_hash = H256::hash(&header);
+                header
+            })
+            .collect::<Vec<_>>();
+
+        AncestryProof {
+            event_proof: EventProof {
+                block_hash: H256::hash(&block_header),
+                block_header,
+                transaction_receipt_hash: H256::hash(&transaction_receipt),
+                transaction_receipt,
+                merkle_proof_of_receipt,
+            },
+            ancestry,
+        }
+    }
+
+    #[test]
+    fn valid_ancestry() {
+        let proof = ancestry_proof(100, 3);
+        proof.validate().unwrap();
+        assert_eq!(proof.anchor(), (103, H256::hash(&proof.ancestry[2])));
+
+        let proof = ancestry_proof(100, 0);
+        proof.validate().unwrap();
+        assert_eq!(proof.anchor(), (100, proof.event_proof.block_hash));
+    }
+
+    #[test]
+    fn broken_ancestry() {
+        let mut proof = ancestry_proof(100, 3);
+        proof.ancestry[1].parent_hash = H256([1; 32]);
+        assert!(matches!(
+            proof.validate(),
+            Err(ValidationError::IncorrectParentHash { number: 102, .. })
+        ));
+
+        let mut proof = ancestry_proof(100, 3);
+        proof.ancestry.remove(1);
+        assert!(matches!(
+            proof.validate(),
+            Err(ValidationError::NonConsecutiveBlockNumber {
+                expected: 102,
+                actual: 103
+            })
+        ));
+
+        let mut proof = ancestry_proof(u64::MAX, 0);
+        proof.ancestry.push(header(0, proof.event_proof.block_hash));
+        assert!(matches!(
+            proof.validate(),
+            Err(ValidationError::BlockNumberOverflow { number: u64::MAX })
+        ));
+    }
+}
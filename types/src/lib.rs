@@ -5,8 +5,8 @@ extern crate alloc;
 
 mod receipt;
 pub use receipt::{
-    BranchNode, ExtensionNode, Leaf, Log, MerkleProof, MerkleProofNode, Nibbles, Receipt,
-    TransactionReceipt, TxType,
+    BranchNode, CompactMerkleProof, CompactProofError, CompactProofNode, ExtensionNode, Leaf, Log,
+    MerkleProof, MerkleProofNode, Nibbles, NodeRef, Receipt, TransactionReceipt, TxType,
 };
 
 mod primitives;
@@ -18,6 +18,15 @@ pub use block_header::{BlockHeader, BlockHeaderWithTransaction};
 mod bloom;
 pub use bloom::Bloom;
 
+mod abi;
+pub use abi::{AbiError, AbiParam, AbiType, AbiValue, EventAbi};
+
+mod log_proof;
+pub use log_proof::{DecodedLog, LogProof};
+
+mod ancestry_proof;
+pub use ancestry_proof::AncestryProof;
+
 pub(crate) mod encode;
 
 pub mod encoding {
@@ -58,9 +67,35 @@ pub struct EventProof {
 /// Error type for validating `EventProofTransaction`s.
 #[derive(Debug)]
 pub enum ValidationError {
-    IncorrectBodyHash { expected: H256, actual: H256 },
-    IncorrectReceiptHash { expected: H256, actual: H256 },
-    IncorrectReceiptRoot { expected: H256, actual: H256 },
+    IncorrectBodyHash {
+        expected: H256,
+        actual: H256,
+    },
+    IncorrectReceiptHash {
+        expected: H256,
+        actual: H256,
+    },
+    IncorrectReceiptRoot {
+        expected: H256,
+        actual: H256,
+    },
+    LogIndexOutOfBounds {
+        index: u32,
+        logs: usize,
+    },
+    AbiDecode(AbiError),
+    NonConsecutiveBlockNumber {
+        expected: u64,
+        actual: u64,
+    },
+    BlockNumberOverflow {
+        number: u64,
+    },
+    IncorrectParentHash {
+        number: u64,
+        expected: H256,
+        actual: H256,
+    },
 }
 
 impl EventProof {
@@ -0,0 +1,196 @@
+use alloc::vec::Vec;
+
+use crate::{AbiValue, EventAbi, EventProof, Log, ValidationError};
+
+/// Proof that a single log has been emitted in a finalized block.
+///
+/// Wraps an [`EventProof`] of the receipt containing the log and pins the log by its index in the
+/// receipt, so consumers don't have to look through every log of the receipt.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogProof {
+    /// Proof of inclusion of the receipt that contains the log.
+    pub event_proof: EventProof,
+
+    /// Index of the log in the `logs` of the proven receipt.
+    pub log_index: u32,
+
+    /// Optional description of the event. If provided, the log is decoded into typed parameters.
+    pub abi: Option<EventAbi>,
+}
+
+/// A proven log together with its decoded parameters.
+#[derive(
+    Debug,
+    PartialEq,
+    Clone,
+    scale_info::TypeInfo,
+    parity_scale_codec::Encode,
+    parity_scale_codec::Decode,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedLog {
+    pub log: Log,
+    /// Parameters decoded with the [`EventAbi`] of the proof, if one was provided.
+    pub params: Option<Vec<AbiValue>>,
+}
+
+impl LogProof {
+    /// Check that the receipt proof is valid and that the pinned log exists.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.event_proof.validate()?;
+        self.pinned_log()?;
+        Ok(())
+    }
+
+    /// The log pinned by this proof.
+    pub fn log(&self) -> Option<&Log> {
+        self.event_proof
+            .transaction_receipt
+            .receipt
+            .logs
+            .get(self.log_index as usize)
+    }
+
+    /// Decode the pinned log with the provided ABI descriptor.
+    pub fn decode(&self) -> Result<DecodedLog, ValidationError> {
+        let log = self.pinned_log()?;
+        let params = self
+            .abi
+            .as_ref()
+            .map(|abi| abi.decode_log(log))
+            .transpose()
+            .map_err(ValidationError::AbiDecode)?;
+        Ok(DecodedLog {
+            log: log.clone(),
+            params,
+        })
+    }
+
+    fn pinned_log(&self) -> Result<&Log, ValidationError> {
+        self.log().ok_or(ValidationError::LogIndexOutOfBounds {
+            index: self.log_index,
+            logs: self.event_proof.transaction_receipt.receipt.logs.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        AbiParam, AbiType, AbiValue, BlockHeader, Bloom, EventAbi, EventProof, Log, MerkleProof,
+        Receipt, TransactionReceipt, TxType, ValidationError, H160, H256, U256,
+    };
+
+    use super::LogProof;
+
+    fn single_receipt_proof(logs: Vec<Log>) -> EventProof {
+        let transaction_receipt = TransactionReceipt {
+            bloom: Bloom::new([0; 256]),
+            receipt: Receipt {
+                tx_type: TxType::EIP1559,
+                success: true,
+                cumulative_gas_used: 21000,
+                logs,
+            },
+        };
+        // A trie with a single receipt consists only of the leaf, so the proof is empty.
+        let merkle_proof_of_receipt = MerkleProof {
+            proof: vec![],
+            key: alloy_rlp::encode(0usize),
+        };
+        let block_header = BlockHeader {
+            parent_hash: H256::zero(),
+            ommers_hash: H256::zero(),
+            beneficiary: H160::new([0u8; 20]),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: merkle_proof_of_receipt.merkle_root(&transaction_receipt),
+            withdrawals_root: None,
+            logs_bloom: Bloom::new([0; 256]),
+            difficulty: 0.into(),
+            number: 1,
+            gas_limit: 0,
+            gas_used: 0,
+            timestamp: 0,
+            mix_hash: H256::zero(),
+            nonce: 0,
+            base_fee_per_gas: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            extra_data: vec![],
+        };
+
+        EventProof {
+            block_hash: H256::hash(&block_header),
+            block_header,
+            transaction_receipt_hash: H256::hash(&transaction_receipt),
+            transaction_receipt,
+            merkle_proof_of_receipt,
+        }
+    }
+
+    #[test]
+    fn decode_pinned_log() {
+        let abi = EventAbi {
+            signature: b"Deposit(address,uint256)".to_vec(),
+            params: vec![
+                AbiParam {
+                    kind: AbiType::Address,
+                    indexed: true,
+                },
+                AbiParam {
+                    kind: AbiType::Uint(256),
+                    indexed: false,
+                },
+            ],
+        };
+        let other = Log {
+            address: H160::new([1; 20]),
+            topics: vec![H256([1; 32])],
+            data: vec![1, 2, 3],
+        };
+        let deposit = Log {
+            address: H160::new([2; 20]),
+            topics: vec![
+                abi.topic(),
+                H256::from_slice(&[&[0u8; 12][..], &[3u8; 20]].concat()),
+            ],
+            data: U256::from(5).0.to_vec(),
+        };
+
+        let proof = LogProof {
+            event_proof: single_receipt_proof(vec![other, deposit.clone()]),
+            log_index: 1,
+            abi: Some(abi),
+        };
+        proof.validate().unwrap();
+
+        let decoded = proof.decode().unwrap();
+        assert_eq!(decoded.log, deposit);
+        assert_eq!(
+            decoded.params,
+            Some(vec![
+                AbiValue::Address(H160::new([3; 20])),
+                AbiValue::Uint(U256::from(5)),
+            ])
+        );
+    }
+
+    #[test]
+    fn log_index_out_of_bounds() {
+        let proof = LogProof {
+            event_proof: single_receipt_proof(vec![]),
+            log_index: 0,
+            abi: None,
+        };
+
+        assert!(matches!(
+            proof.validate(),
+            Err(ValidationError::LogIndexOutOfBounds { index: 0, logs: 0 })
+        ));
+    }
+}
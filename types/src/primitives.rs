@@ -1,11 +1,24 @@
 use alloc::vec::Vec;
-use alloy_rlp::{Encodable, RlpEncodableWrapper};
+use alloy_rlp::{Encodable, RlpDecodableWrapper, RlpEncodableWrapper};
 use keccak_hash::keccak;
 use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
 
 #[derive(
-    Debug, RlpEncodableWrapper, PartialEq, Clone, Encode, Decode, TypeInfo, Copy, MaxEncodedLen,
+    Debug,
+    RlpEncodableWrapper,
+    RlpDecodableWrapper,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Clone,
+    Encode,
+    Decode,
+    TypeInfo,
+    Copy,
+    MaxEncodedLen,
 )]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct H256(pub [u8; 32]);
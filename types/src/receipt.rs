@@ -1,9 +1,11 @@
+mod compact_merkle_proof;
 mod log;
 mod receipt_merkle_proof;
 mod transaction_receipt;
 mod trie;
 mod tx_type;
 
+pub use compact_merkle_proof::{CompactMerkleProof, CompactProofError, CompactProofNode};
 pub use log::Log;
 pub use receipt_merkle_proof::{MerkleProof, MerkleProofNode};
 pub use transaction_receipt::{Receipt, TransactionReceipt};
@@ -12,5 +14,6 @@ pub use trie::{
     extension::ExtensionNode,
     leaf::{Leaf, LeafEncoder},
     nibble::Nibbles,
+    node_ref::NodeRef,
 };
 pub use tx_type::TxType;
@@ -0,0 +1,554 @@
+use alloc::{boxed::Box, vec::Vec};
+use alloy_rlp::{BufMut, Decodable, Encodable, Header};
+
+use crate::H256;
+
+use super::{
+    receipt_merkle_proof::{MerkleProof, MerkleProofNode},
+    transaction_receipt::TransactionReceipt,
+    trie::{
+        branch::BranchNode, extension::ExtensionNode, leaf::Leaf, nibble::Nibbles,
+        node_ref::NodeRef,
+    },
+};
+
+/// Node of a [`CompactMerkleProof`].
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompactProofNode {
+    /// An extension node, with its prefix in the compact (hex-prefix) encoding.
+    ExtensionNode { prefix: Vec<u8> },
+
+    /// A branch node.
+    ///
+    /// Bit `i` of `bitmap` is set if the `i`-th child is a sibling of the path, i.e. it's present
+    /// and isn't on the path to the leaf. `siblings` are the references to these children in the
+    /// nibble order. The child on the path is recomputed from the rest of the proof and its index
+    /// is the next nibble of the key.
+    BranchNode {
+        bitmap: u16,
+        siblings: Vec<NodeRef>,
+        value: Option<Vec<u8>>,
+    },
+}
+
+/// Compact form of a [`MerkleProof`].
+///
+/// Only the data that can't be recomputed during verification is kept: absent siblings and the
+/// indices of the path are dropped. The proof has an RLP wire encoding, which is how a
+/// [`MerkleProof`] is serialized.
+#[derive(Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactMerkleProof {
+    pub proof: Vec<CompactProofNode>,
+    pub key: Vec<u8>,
+}
+
+/// Error type for malformed [`CompactMerkleProof`]s.
+#[derive(Debug, PartialEq)]
+pub enum CompactProofError {
+    /// The path of the proof is longer than the key.
+    KeyTooShort,
+    /// A branch has a sibling in place of the child on the path.
+    SiblingOnPath { depth: usize },
+    /// The number of siblings doesn't match the bitmap of a branch.
+    SiblingCountMismatch { expected: usize, actual: usize },
+    /// An extension prefix isn't a valid compact encoding.
+    InvalidPrefix,
+}
+
+impl From<&MerkleProof> for CompactMerkleProof {
+    fn from(proof: &MerkleProof) -> Self {
+        let proof_nodes = proof
+            .proof
+            .iter()
+            .map(|node| match node {
+                MerkleProofNode::ExtensionNode { prefix } => CompactProofNode::ExtensionNode {
+                    prefix: prefix.encode_compact(),
+                },
+                MerkleProofNode::BranchNode {
+                    branches,
+                    value,
+                    index,
+                } => {
+                    let mut bitmap = 0u16;
+                    let mut siblings = Vec::new();
+                    for (i, branch) in branches.iter().enumerate() {
+                        // The child on the path is replaced during verification.
+                        if i == *index as usize {
+                            continue;
+                        }
+                        if let Some(child) = branch {
+                            bitmap |= 1 << i;
+                            siblings.push(child.clone());
+                        }
+                    }
+                    CompactProofNode::BranchNode {
+                        bitmap,
+                        siblings,
+                        value: value.clone(),
+                    }
+                }
+            })
+            .collect();
+
+        CompactMerkleProof {
+            proof: proof_nodes,
+            key: proof.key.clone(),
+        }
+    }
+}
+
+impl TryFrom<&CompactMerkleProof> for MerkleProof {
+    type Error = CompactProofError;
+
+    fn try_from(compact: &CompactMerkleProof) -> Result<Self, Self::Error> {
+        let key = Nibbles::from_raw(compact.key.clone(), true);
+        let (depths, _) = compact.depths(&key)?;
+
+        let mut proof = Vec::with_capacity(compact.proof.len());
+        for (node, depth) in compact.proof.iter().zip(depths) {
+            match node {
+                CompactProofNode::ExtensionNode { prefix } => {
+                    proof.push(MerkleProofNode::ExtensionNode {
+                        prefix: decode_prefix(prefix)?,
+                    });
+                }
+                CompactProofNode::BranchNode {
+                    bitmap,
+                    siblings,
+                    value,
+                } => {
+                    proof.push(MerkleProofNode::BranchNode {
+                        branches: Box::new(expand_siblings(*bitmap, siblings)),
+                        value: value.clone(),
+                        index: key.at(depth) as u8,
+                    });
+                }
+            }
+        }
+
+        Ok(MerkleProof {
+            proof,
+            key: compact.key.clone(),
+        })
+    }
+}
+
+impl CompactMerkleProof {
+    /// Given a transaction receipt, compute the Merkle root of the Patricia Merkle Trie using the
+    /// rest of the proof. Same as [`MerkleProof::merkle_root`], without expanding the proof.
+    pub fn merkle_root(&self, leaf: &TransactionReceipt) -> Result<H256, CompactProofError> {
+        let key = Nibbles::from_raw(self.key.clone(), true);
+        let (depths, path_len) = self.depths(&key)?;
+
+        let mut proof = self.proof.iter().zip(depths).rev().peekable();
+        let mut node = match proof.peek() {
+            // The key ends at a branch, so the receipt is the value of the branch.
+            Some((
+                CompactProofNode::BranchNode {
+                    bitmap, siblings, ..
+                },
+                depth,
+            )) if key.at(*depth) == 16 => {
+                let branches = expand_siblings(*bitmap, siblings);
+                proof.next();
+                NodeRef::from_encoded(alloy_rlp::encode(&BranchNode {
+                    branches,
+                    value: Some(alloy_rlp::encode(leaf)),
+                }))
+            }
+            _ => NodeRef::from_encoded(alloy_rlp::encode(&Leaf::from_transaction_receipt(
+                key.offset(path_len),
+                leaf.clone(),
+            ))),
+        };
+
+        for (proof_node, depth) in proof {
+            node = match proof_node {
+                CompactProofNode::ExtensionNode { prefix } => NodeRef::from_encoded(
+                    alloy_rlp::encode(&ExtensionNode::new(decode_prefix(prefix)?, node)),
+                ),
+                CompactProofNode::BranchNode {
+                    bitmap,
+                    siblings,
+                    value,
+                } => {
+                    let mut branches = expand_siblings(*bitmap, siblings);
+                    branches[key.at(depth)] = Some(node);
+                    NodeRef::from_encoded(alloy_rlp::encode(&BranchNode {
+                        branches,
+                        value: value.clone(),
+                    }))
+                }
+            };
+        }
+        Ok(node.hash())
+    }
+
+    /// Checks the shape of the proof against the key and returns the depth of every node, along
+    /// with the length of the path covered by the proof. The rest of the key belongs to the leaf.
+    fn depths(&self, key: &Nibbles) -> Result<(Vec<usize>, usize), CompactProofError> {
+        let mut depth = 0;
+        let mut depths = Vec::with_capacity(self.proof.len());
+        for (i, node) in self.proof.iter().enumerate() {
+            depths.push(depth);
+            match node {
+                CompactProofNode::ExtensionNode { prefix } => {
+                    depth += decode_prefix(prefix)?.len();
+                    if depth >= key.len() {
+                        return Err(CompactProofError::KeyTooShort);
+                    }
+                }
+                CompactProofNode::BranchNode {
+                    bitmap, siblings, ..
+                } => {
+                    if depth >= key.len() {
+                        return Err(CompactProofError::KeyTooShort);
+                    }
+                    match key.at(depth) {
+                        // Only the last node can hold the proven value, as the key ends there.
+                        16 if i + 1 != self.proof.len() => {
+                            return Err(CompactProofError::KeyTooShort)
+                        }
+                        16 => {}
+                        index if bitmap & (1 << index) != 0 => {
+                            return Err(CompactProofError::SiblingOnPath { depth })
+                        }
+                        _ => {}
+                    }
+                    if bitmap.count_ones() as usize != siblings.len() {
+                        return Err(CompactProofError::SiblingCountMismatch {
+                            expected: bitmap.count_ones() as usize,
+                            actual: siblings.len(),
+                        });
+                    }
+                    depth += 1;
+                }
+            }
+        }
+        Ok((depths, depth))
+    }
+}
+
+/// Places the siblings of a branch at the indices set in the bitmap.
+fn expand_siblings(bitmap: u16, siblings: &[NodeRef]) -> [Option<NodeRef>; 16] {
+    let mut siblings = siblings.iter();
+    let mut branches: [Option<NodeRef>; 16] = Default::default();
+    for (i, branch) in branches.iter_mut().enumerate() {
+        if bitmap & (1 << i) != 0 {
+            *branch = siblings.next().cloned();
+        }
+    }
+    branches
+}
+
+/// Decodes a compact encoded extension prefix.
+fn decode_prefix(prefix: &[u8]) -> Result<Nibbles, CompactProofError> {
+    match prefix.first() {
+        // Only the extension flags, with a zero padding nibble if the path is even.
+        Some(0x00) | Some(0x10..=0x1f) => Ok(Nibbles::from_compact(prefix.to_vec())),
+        _ => Err(CompactProofError::InvalidPrefix),
+    }
+}
+
+// Wire encoding:
+//
+// proof     = [key, [node, ...]]
+// extension = [prefix]
+// branch    = [bitmap, [sibling, ...], [value] or []]
+
+impl Encodable for CompactProofNode {
+    fn encode(&self, out: &mut dyn BufMut) {
+        let mut payload = Vec::new();
+        match self {
+            CompactProofNode::ExtensionNode { prefix } => prefix.as_slice().encode(&mut payload),
+            CompactProofNode::BranchNode {
+                bitmap,
+                siblings,
+                value,
+            } => {
+                bitmap.encode(&mut payload);
+                siblings.encode(&mut payload);
+                encode_list(value.iter().map(Vec::as_slice), &mut payload);
+            }
+        }
+        encode_payload(&payload, out);
+    }
+}
+
+impl Decodable for CompactProofNode {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = list_payload(buf)?;
+        let node = match Header::decode(&mut &payload[..])? {
+            // The only item of an extension is a string.
+            Header { list: false, .. } if is_single_item(payload)? => {
+                CompactProofNode::ExtensionNode {
+                    prefix: decode_bytes(&mut payload)?,
+                }
+            }
+            _ => {
+                let bitmap = u16::decode(&mut payload)?;
+                let siblings = Vec::<NodeRef>::decode(&mut payload)?;
+                let mut value = list_payload(&mut payload)?;
+                let value = match value.is_empty() {
+                    true => None,
+                    false => Some(decode_bytes(&mut value)?),
+                };
+                CompactProofNode::BranchNode {
+                    bitmap,
+                    siblings,
+                    value,
+                }
+            }
+        };
+        if !payload.is_empty() {
+            return Err(alloy_rlp::Error::UnexpectedLength);
+        }
+        Ok(node)
+    }
+}
+
+impl Encodable for CompactMerkleProof {
+    fn encode(&self, out: &mut dyn BufMut) {
+        let mut payload = Vec::new();
+        self.key.as_slice().encode(&mut payload);
+        self.proof.encode(&mut payload);
+        encode_payload(&payload, out);
+    }
+}
+
+impl Decodable for CompactMerkleProof {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = list_payload(buf)?;
+        let key = decode_bytes(&mut payload)?;
+        let proof = Vec::<CompactProofNode>::decode(&mut payload)?;
+        if !payload.is_empty() {
+            return Err(alloy_rlp::Error::UnexpectedLength);
+        }
+        Ok(CompactMerkleProof { proof, key })
+    }
+}
+
+fn encode_payload(payload: &[u8], out: &mut dyn BufMut) {
+    Header {
+        list: true,
+        payload_length: payload.len(),
+    }
+    .encode(out);
+    out.put_slice(payload);
+}
+
+fn encode_list<'a>(items: impl Iterator<Item = &'a [u8]>, out: &mut dyn BufMut) {
+    let mut payload = Vec::new();
+    items.for_each(|item| item.encode(&mut payload));
+    encode_payload(&payload, out);
+}
+
+/// Reads the header of a list and returns its payload, advancing `buf` past the list.
+fn list_payload<'a>(buf: &mut &'a [u8]) -> alloy_rlp::Result<&'a [u8]> {
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(alloy_rlp::Error::UnexpectedString);
+    }
+    take(buf, header.payload_length)
+}
+
+fn decode_bytes(buf: &mut &[u8]) -> alloy_rlp::Result<Vec<u8>> {
+    let header = Header::decode(buf)?;
+    if header.list {
+        return Err(alloy_rlp::Error::UnexpectedList);
+    }
+    take(buf, header.payload_length).map(<[u8]>::to_vec)
+}
+
+fn is_single_item(mut payload: &[u8]) -> alloy_rlp::Result<bool> {
+    let header = Header::decode(&mut payload)?;
+    Ok(payload.len() == header.payload_length)
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> alloy_rlp::Result<&'a [u8]> {
+    if buf.len() < len {
+        return Err(alloy_rlp::Error::InputTooShort);
+    }
+    let (head, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use alloy_rlp::Decodable;
+
+    use crate::{
+        Bloom, CompactMerkleProof, CompactProofError, CompactProofNode, MerkleProof,
+        MerkleProofNode, Nibbles, NodeRef, Receipt, TransactionReceipt, TxType, H256,
+    };
+
+    fn receipt() -> TransactionReceipt {
+        TransactionReceipt {
+            bloom: Bloom::new([0; 256]),
+            receipt: Receipt {
+                tx_type: TxType::EIP1559,
+                success: true,
+                cumulative_gas_used: 21000,
+                logs: vec![],
+            },
+        }
+    }
+
+    fn proof() -> MerkleProof {
+        let mut branches: [Option<NodeRef>; 16] = Default::default();
+        branches[0] = Some(NodeRef::Hash(H256([1; 32])));
+        branches[15] = Some(NodeRef::Inline(vec![0xc2, 0x20, 0x01]));
+        MerkleProof {
+            proof: vec![
+                MerkleProofNode::ExtensionNode {
+                    prefix: Nibbles::from_hex(vec![1, 2, 3]),
+                },
+                MerkleProofNode::BranchNode {
+                    branches: Box::new(branches),
+                    value: Some(vec![0xc0]),
+                    index: 4,
+                },
+            ],
+            key: vec![0x12, 0x34, 0x56],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let proof = proof();
+        let compact = CompactMerkleProof::from(&proof);
+        assert_eq!(
+            compact.proof[1],
+            CompactProofNode::BranchNode {
+                bitmap: 0b1000_0000_0000_0001,
+                siblings: vec![
+                    NodeRef::Hash(H256([1; 32])),
+                    NodeRef::Inline(vec![0xc2, 0x20, 0x01])
+                ],
+                value: Some(vec![0xc0]),
+            }
+        );
+        assert_eq!(
+            compact.merkle_root(&receipt()),
+            Ok(proof.merkle_root(&receipt()))
+        );
+
+        let encoded = alloy_rlp::encode(&compact);
+        let decoded = CompactMerkleProof::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded, compact);
+        assert_eq!(MerkleProof::try_from(&decoded).unwrap(), proof);
+    }
+
+    #[test]
+    fn key_ending_at_branch() {
+        let mut branches: [Option<NodeRef>; 16] = Default::default();
+        branches[3] = Some(NodeRef::Hash(H256([3; 32])));
+        let mut proof = proof();
+        proof.proof[0] = MerkleProofNode::ExtensionNode {
+            prefix: Nibbles::from_hex(vec![1, 2, 3, 4]),
+        };
+        proof.proof[1] = MerkleProofNode::BranchNode {
+            branches: Box::new(branches),
+            value: None,
+            index: 16,
+        };
+        proof.key = vec![0x12, 0x34];
+
+        let compact = CompactMerkleProof::from(&proof);
+        assert_eq!(
+            compact.merkle_root(&receipt()),
+            Ok(proof.merkle_root(&receipt()))
+        );
+        assert_eq!(MerkleProof::try_from(&compact).unwrap(), proof);
+
+        // Only the last node of the proof can hold the value.
+        let mut compact = compact;
+        compact
+            .proof
+            .push(CompactProofNode::ExtensionNode { prefix: vec![0x15] });
+        assert_eq!(
+            compact.merkle_root(&receipt()),
+            Err(CompactProofError::KeyTooShort)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialized_as_compact_proof() {
+        let proof = proof();
+        let json = serde_json::to_string(&proof).unwrap();
+        let compact = alloy_rlp::encode(CompactMerkleProof::from(&proof));
+        assert_eq!(
+            json,
+            format!(
+                "{{\"compact\":{}}}",
+                serde_json::to_string(&compact).unwrap()
+            )
+        );
+        assert_eq!(serde_json::from_str::<MerkleProof>(&json).unwrap(), proof);
+
+        // The expanded form is still accepted.
+        let expanded =
+            r#"{"proof":[{"ExtensionNode":{"prefix":{"hex_data":[1,2,3]}}}],"key":[18]}"#;
+        assert_eq!(
+            serde_json::from_str::<MerkleProof>(expanded).unwrap(),
+            MerkleProof {
+                proof: vec![MerkleProofNode::ExtensionNode {
+                    prefix: Nibbles::from_hex(vec![1, 2, 3]),
+                }],
+                key: vec![0x12],
+            }
+        );
+
+        let mut malformed = CompactMerkleProof::from(&proof);
+        malformed.key = vec![0x12];
+        let json = format!(
+            "{{\"compact\":{}}}",
+            serde_json::to_string(&alloy_rlp::encode(malformed)).unwrap()
+        );
+        assert!(serde_json::from_str::<MerkleProof>(&json).is_err());
+    }
+
+    #[test]
+    fn malformed_proofs() {
+        let mut compact = CompactMerkleProof::from(&proof());
+        compact.key = vec![0x12];
+        assert_eq!(
+            compact.merkle_root(&receipt()),
+            Err(CompactProofError::KeyTooShort)
+        );
+
+        let mut compact = CompactMerkleProof::from(&proof());
+        if let CompactProofNode::BranchNode { bitmap, .. } = &mut compact.proof[1] {
+            *bitmap |= 1 << 4;
+        }
+        assert_eq!(
+            MerkleProof::try_from(&compact),
+            Err(CompactProofError::SiblingOnPath { depth: 3 })
+        );
+
+        let mut compact = CompactMerkleProof::from(&proof());
+        if let CompactProofNode::BranchNode { siblings, .. } = &mut compact.proof[1] {
+            siblings.pop();
+        }
+        assert_eq!(
+            compact.merkle_root(&receipt()),
+            Err(CompactProofError::SiblingCountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        let mut compact = CompactMerkleProof::from(&proof());
+        compact.proof[0] = CompactProofNode::ExtensionNode { prefix: vec![0x20] };
+        assert_eq!(
+            compact.merkle_root(&receipt()),
+            Err(CompactProofError::InvalidPrefix)
+        );
+    }
+}
@@ -2,9 +2,17 @@ use alloc::{boxed::Box, vec::Vec};
 
 use crate::H256;
 
+#[cfg(feature = "serde")]
+use alloy_rlp::Decodable;
+
+#[cfg(feature = "serde")]
+use super::compact_merkle_proof::CompactMerkleProof;
 use super::{
     transaction_receipt::TransactionReceipt,
-    trie::{branch::BranchNode, extension::ExtensionNode, leaf::Leaf, nibble::Nibbles},
+    trie::{
+        branch::BranchNode, extension::ExtensionNode, leaf::Leaf, nibble::Nibbles,
+        node_ref::NodeRef,
+    },
 };
 
 /// Nodes of a Merkle proof that a transaction has been included in a block. Corresponds to `branch`
@@ -19,8 +27,8 @@ pub enum MerkleProofNode {
     ///
     /// The `prefix` is the nibble path to the next node.
     ///
-    /// Implicitly, there is a `pointer`, which is a hash resulting from the previous elements of
-    /// the Merkle proof.
+    /// Implicitly, there is a `pointer`, which is a reference to the node resulting from the
+    /// previous elements of the Merkle proof.
     ///
     /// See the Ethereum [Yellow Paper][1] for more details.
     ///
@@ -32,11 +40,13 @@ pub enum MerkleProofNode {
 
     /// A branch node in the Patricia Merkle Trie.
     ///
-    /// `branches` is an array of 16 (optional) pointers to the next node, corresponding to the 16
-    /// possible nibble values.
+    /// `branches` is an array of 16 (optional) references to the next node, corresponding to the
+    /// 16 possible nibble values. Children shorter than a hash are embedded, see [`NodeRef`].
     ///
-    /// `index` is the nibble corresponding to where the hash resulting from the previous elements
-    /// of the Merkle proof is to be slotted in.
+    /// `index` is the nibble corresponding to where the reference resulting from the previous
+    /// elements of the Merkle proof is to be slotted in. An `index` of 16 is only valid in the
+    /// last node of a proof, for a key that ends at the branch: the proven value is then the
+    /// `value` of the branch, which the verifier provides.
     ///
     /// See the Ethereum [Yellow Paper][1] for more details.
     ///
@@ -45,7 +55,7 @@ pub enum MerkleProofNode {
     /// [1]: https://ethereum.github.io/yellowpaper/paper.pdf
     /// [2]: https://github.com/paradigmxyz/reth/blob/8c70524fc6031dcc268fd771797f35d6229848e7/crates/primitives/src/trie/nodes/branch.rs#L8-L15
     BranchNode {
-        branches: Box<[Option<H256>; 16]>,
+        branches: Box<[Option<NodeRef>; 16]>,
         value: Option<Vec<u8>>,
         index: u8,
     },
@@ -59,22 +69,71 @@ pub enum MerkleProofNode {
 /// Requires a [`ReceiptWithBloom`] to generate a leaf node, and the rest of the proof proceeds
 /// from the leaf node.
 ///
+/// Proofs are serialized as the RLP of their [`CompactMerkleProof`](crate::CompactMerkleProof), under a `compact` field.
+/// The expanded form, with `proof` and `key` fields, is still accepted when deserializing.
+///
 /// [1]: https://ethereum.org/se/developers/docs/data-structures-and-encoding/patricia-merkle-trie/
 #[derive(Debug, PartialEq, Default)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedMerkleProof"))]
 pub struct MerkleProof {
     pub proof: Vec<MerkleProofNode>,
     pub key: Vec<u8>,
 }
 
+/// Deserialized proof, in either form.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum UncheckedMerkleProof {
+    Expanded {
+        proof: Vec<MerkleProofNode>,
+        key: Vec<u8>,
+    },
+    Compact {
+        compact: Vec<u8>,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedMerkleProof> for MerkleProof {
+    type Error = alloy_rlp::Error;
+
+    fn try_from(proof: UncheckedMerkleProof) -> Result<Self, Self::Error> {
+        match proof {
+            UncheckedMerkleProof::Expanded { proof, key } => Ok(MerkleProof { proof, key }),
+            UncheckedMerkleProof::Compact { compact } => {
+                let compact = CompactMerkleProof::decode(&mut compact.as_slice())?;
+                MerkleProof::try_from(&compact)
+                    .map_err(|_| alloy_rlp::Error::Custom("malformed compact Merkle proof"))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MerkleProof {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut proof = serializer.serialize_struct("MerkleProof", 1)?;
+        proof.serialize_field(
+            "compact",
+            &alloy_rlp::encode(CompactMerkleProof::from(self)),
+        )?;
+        proof.end()
+    }
+}
+
 impl MerkleProof {
     /// Given a transaction receipt, compute the Merkle root of the Patricia Merkle Trie using the
     /// rest of the Merkle proof.
     pub fn merkle_root(&self, leaf: &TransactionReceipt) -> H256 {
-        // Recovering a Merkle root from a Merkle proof involves computing the hash of the leaf node
-        // and the hashes of the rest of the nodes in the proof.
+        // Recovering a Merkle root from a Merkle proof involves computing the reference to the
+        // leaf node and the references to the rest of the nodes in the proof. A reference is the
+        // hash of the node, unless the node is short enough to be embedded in its parent.
         //
-        // The final hash is the Merkle root.
+        // The hash of the final node is the Merkle root.
 
         // Full nibble path of the leaf node.
         let mut key = Nibbles::from_raw(self.key.clone(), true);
@@ -86,33 +145,45 @@ impl MerkleProof {
             }
         }
 
-        let mut hash = H256::from_slice(&alloy_rlp::encode(&Leaf::from_transaction_receipt(
-            key,
-            leaf.clone(),
-        )));
+        let mut proof = self.proof.iter().rev();
+        let mut node = match self.proof.last() {
+            // The key ends at a branch, so the receipt is the value of the branch.
+            Some(MerkleProofNode::BranchNode {
+                branches,
+                index: 16,
+                ..
+            }) => {
+                proof.next();
+                NodeRef::from_encoded(alloy_rlp::encode(&BranchNode {
+                    branches: branches.as_ref().clone(),
+                    value: Some(alloy_rlp::encode(leaf)),
+                }))
+            }
+            _ => NodeRef::from_encoded(alloy_rlp::encode(&Leaf::from_transaction_receipt(
+                key,
+                leaf.clone(),
+            ))),
+        };
 
-        for node in self.proof.iter().rev() {
-            match node {
-                MerkleProofNode::ExtensionNode { prefix } => {
-                    hash = H256::from_slice(&alloy_rlp::encode(&ExtensionNode::new(
-                        prefix.clone(),
-                        hash,
-                    )));
-                }
+        for proof_node in proof {
+            node = match proof_node {
+                MerkleProofNode::ExtensionNode { prefix } => NodeRef::from_encoded(
+                    alloy_rlp::encode(&ExtensionNode::new(prefix.clone(), node)),
+                ),
                 MerkleProofNode::BranchNode {
                     branches,
                     index,
                     value,
                 } => {
-                    let mut branches = *branches.as_ref();
-                    branches[(index & 0x0f) as usize] = Some(hash);
-                    hash = H256::from_slice(&alloy_rlp::encode(&BranchNode {
+                    let mut branches = branches.as_ref().clone();
+                    branches[(index & 0x0f) as usize] = Some(node);
+                    NodeRef::from_encoded(alloy_rlp::encode(&BranchNode {
                         branches,
                         value: value.clone(),
-                    }));
+                    }))
                 }
-            }
+            };
         }
-        hash
+        node.hash()
     }
 }
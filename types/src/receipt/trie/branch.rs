@@ -1,28 +1,34 @@
 use alloc::vec::Vec;
 use alloy_rlp::{length_of_length, BufMut, Encodable};
 
-use crate::{encode::rlp_node, H256};
+use crate::encode::rlp_node;
+
+use super::node_ref::NodeRef;
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BranchNode {
-    pub branches: [Option<H256>; 16],
+    pub branches: [Option<NodeRef>; 16],
     pub value: Option<Vec<u8>>,
 }
 
 impl BranchNode {
     fn header(&self) -> alloy_rlp::Header {
-        // 1 for the branch index + (32 for hash or 1 for empty string)
-        let payload_length = 1 + self.branches.iter().fold(0, |acc, i| {
-            if let Some(hash) = i {
-                acc + hash.length()
+        // Empty children and value are encoded as an empty string, which is 1 byte long.
+        let children = self.branches.iter().fold(0, |acc, i| {
+            if let Some(child) = i {
+                acc + child.length()
             } else {
                 acc + 1
             }
         });
+        let value = self
+            .value
+            .as_ref()
+            .map_or(1, |value| value.as_slice().length());
         alloy_rlp::Header {
             list: true,
-            payload_length,
+            payload_length: children + value,
         }
     }
 }
@@ -34,15 +40,15 @@ impl Encodable for BranchNode {
         let buf_mut = &mut buf;
         crate::encode!(buf_mut, header);
         for i in self.branches.iter() {
-            if let Some(hash) = i {
-                crate::encode!(buf_mut, hash);
+            if let Some(child) = i {
+                crate::encode!(buf_mut, child);
             } else {
                 buf_mut.put_u8(alloy_rlp::EMPTY_STRING_CODE);
             }
         }
 
         if let Some(value) = &self.value {
-            buf_mut.put_slice(value);
+            crate::encode!(buf_mut, value.as_slice());
         } else {
             buf_mut.put_u8(alloy_rlp::EMPTY_STRING_CODE);
         }
@@ -65,7 +71,7 @@ mod tests {
     use hasher::HasherKeccak;
 
     use crate::{
-        receipt::trie::{leaf::Leaf, nibble::Nibbles},
+        receipt::trie::{leaf::Leaf, nibble::Nibbles, node_ref::NodeRef},
         Bloom, Log, Receipt, TransactionReceipt, H160, H256,
     };
 
@@ -107,7 +113,7 @@ mod tests {
                     Leaf::from_transaction_receipt(Nibbles::from_raw(vec![i], true), receipt);
                 let mut buffer = vec![];
                 leaf.encode(&mut buffer);
-                branch_node.branches[i as usize] = Some(H256(buffer[..32].try_into().unwrap()));
+                branch_node.branches[i as usize] = Some(NodeRef::from_encoded(buffer));
 
                 cita_branch.insert(
                     i as usize,
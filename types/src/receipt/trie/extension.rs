@@ -1,19 +1,19 @@
 use alloc::vec::Vec;
 use alloy_rlp::Encodable;
 
-use crate::{encode, H256};
+use crate::encode;
 
-use super::nibble::Nibbles;
+use super::{nibble::Nibbles, node_ref::NodeRef};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtensionNode {
     prefix: Vec<u8>,
-    pointer: H256,
+    pointer: NodeRef,
 }
 
 impl ExtensionNode {
-    pub fn new(prefix: Nibbles, pointer: H256) -> Self {
+    pub fn new(prefix: Nibbles, pointer: NodeRef) -> Self {
         Self {
             prefix: prefix.encode_compact(),
             pointer,
@@ -52,7 +52,7 @@ mod tests {
     use hasher::HasherKeccak;
     use test_strategy::proptest;
 
-    use crate::{receipt::trie::leaf::Leaf, Bloom, Log, Receipt, TransactionReceipt, H160};
+    use crate::{receipt::trie::leaf::Leaf, Bloom, Log, Receipt, TransactionReceipt, H160, H256};
 
     use super::*;
 
@@ -83,7 +83,7 @@ mod tests {
 
         let node = ExtensionNode::new(
             Nibbles::from_raw(prefix.clone(), false),
-            H256(leaf_encoded[..32].try_into().unwrap()),
+            NodeRef::from_encoded(leaf_encoded),
         );
 
         let our_encoded = alloy_rlp::encode(node);
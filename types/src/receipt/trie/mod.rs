@@ -2,3 +2,4 @@ pub mod branch;
 pub mod extension;
 pub mod leaf;
 pub mod nibble;
+pub mod node_ref;
@@ -0,0 +1,166 @@
+use alloc::vec::Vec;
+use alloy_rlp::{BufMut, Decodable, Encodable, Header};
+
+use crate::H256;
+
+/// Reference to a trie node from its parent.
+///
+/// Per the Yellow Paper, a node whose RLP is shorter than 32 bytes is embedded in its parent as
+/// is, while longer nodes are referenced by the keccak hash of their RLP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged, try_from = "UncheckedNodeRef"))]
+pub enum NodeRef {
+    Hash(H256),
+    /// RLP of the embedded node, a single RLP list shorter than a hash. Use [`NodeRef::inline`]
+    /// to build it from untrusted bytes.
+    Inline(Vec<u8>),
+}
+
+/// Deserialized reference whose inline node hasn't been checked yet.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum UncheckedNodeRef {
+    Hash(H256),
+    Inline(Vec<u8>),
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedNodeRef> for NodeRef {
+    type Error = alloy_rlp::Error;
+
+    fn try_from(node: UncheckedNodeRef) -> Result<Self, Self::Error> {
+        match node {
+            UncheckedNodeRef::Hash(hash) => Ok(NodeRef::Hash(hash)),
+            UncheckedNodeRef::Inline(rlp) => NodeRef::inline(rlp),
+        }
+    }
+}
+
+impl NodeRef {
+    /// Reference to an embedded node. Fails unless `rlp` is exactly one RLP list shorter than a
+    /// hash, otherwise the same node could be given by several proofs.
+    pub fn inline(rlp: Vec<u8>) -> alloy_rlp::Result<Self> {
+        if rlp.len() >= 32 {
+            return Err(alloy_rlp::Error::Custom(
+                "inline node is not shorter than a hash",
+            ));
+        }
+        let mut payload = rlp.as_slice();
+        let header = Header::decode(&mut payload)?;
+        if !header.list {
+            return Err(alloy_rlp::Error::UnexpectedString);
+        }
+        if payload.len() != header.payload_length {
+            return Err(alloy_rlp::Error::Custom(
+                "inline node is not a single RLP list",
+            ));
+        }
+        Ok(NodeRef::Inline(rlp))
+    }
+
+    /// Builds the reference from the output of a node encoder, which is either the RLP of the
+    /// node or its hash.
+    pub fn from_encoded(encoded: Vec<u8>) -> Self {
+        if encoded.len() == 32 {
+            NodeRef::Hash(H256::from_slice(&encoded))
+        } else {
+            NodeRef::Inline(encoded)
+        }
+    }
+
+    /// Hash of the referenced node. The root of a trie is always hashed, even if it's short
+    /// enough to be embedded.
+    pub fn hash(&self) -> H256 {
+        match self {
+            NodeRef::Hash(hash) => *hash,
+            NodeRef::Inline(rlp) => H256(keccak_hash::keccak(rlp).0),
+        }
+    }
+}
+
+impl Encodable for NodeRef {
+    fn encode(&self, out: &mut dyn BufMut) {
+        match self {
+            NodeRef::Hash(hash) => hash.encode(out),
+            NodeRef::Inline(rlp) => out.put_slice(rlp),
+        }
+    }
+
+    fn length(&self) -> usize {
+        match self {
+            NodeRef::Hash(hash) => hash.length(),
+            NodeRef::Inline(rlp) => rlp.len(),
+        }
+    }
+}
+
+impl Decodable for NodeRef {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = *buf;
+        let header = Header::decode(&mut payload)?;
+        if !header.list {
+            return H256::decode(buf).map(NodeRef::Hash);
+        }
+
+        // Embedded nodes are kept together with their header.
+        let len = buf.len() - payload.len() + header.payload_length;
+        if buf.len() < len {
+            return Err(alloy_rlp::Error::InputTooShort);
+        }
+        let (rlp, rest) = buf.split_at(len);
+        let node = NodeRef::inline(rlp.to_vec())?;
+        *buf = rest;
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_rlp::Decodable;
+
+    use super::NodeRef;
+
+    #[test]
+    fn inline_node_is_a_short_list() {
+        // Leaf with a one byte key and value.
+        let leaf = vec![0xc4, 0x82, 0x20, 0x01, 0x02];
+        assert_eq!(
+            NodeRef::inline(leaf.clone()),
+            Ok(NodeRef::Inline(leaf.clone()))
+        );
+        assert_eq!(
+            NodeRef::decode(&mut leaf.as_slice()),
+            Ok(NodeRef::Inline(leaf))
+        );
+
+        // Empty node, strings, concatenated lists and truncated lists.
+        for rlp in [
+            vec![],
+            vec![0x80],
+            vec![0x82, 0x20, 0x01],
+            vec![0xc1, 0x80, 0xc1, 0x80],
+            vec![0xc4, 0x82, 0x20, 0x01],
+        ] {
+            assert!(NodeRef::inline(rlp).is_err());
+        }
+
+        // A list long enough to be hashed can't be embedded.
+        let mut long = vec![0xdf];
+        long.extend([0x01; 31]);
+        assert!(NodeRef::inline(long.clone()).is_err());
+        assert!(NodeRef::decode(&mut long.as_slice()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_checks_inline_node() {
+        let leaf: NodeRef = serde_json::from_str("[196,130,32,1,2]").unwrap();
+        assert_eq!(leaf, NodeRef::Inline(vec![0xc4, 0x82, 0x20, 0x01, 0x02]));
+
+        for json in ["[]", "[128]", "[193,128,193,128]"] {
+            assert!(serde_json::from_str::<NodeRef>(json).is_err());
+        }
+    }
+}